@@ -69,7 +69,7 @@ loginButton.addEventListener("click", (e) => {
             alert("You have successfully logged in.");
         } else {
             response.json().then(data => {
-                let error_msg = data.error;
+                let error_msg = data.title;
                 if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
                     loginErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
                     loginErrAlter.style.display = "block";
@@ -104,13 +104,20 @@ signupButton.addEventListener("click", (e) => {
             signupForm.password.value = "";
             signupForm.twoFA.checked = false;
             signupErrAlter.style.display = "none";
+            signupPasswordStrength.style.display = "none";
             alert("You have successfully created a user.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
             signupSection.style.display = "none";
         } else {
             response.json().then(data => {
-                let error_msg = data.error;
+                let error_msg = data.title;
+                if (data.violations) {
+                    error_msg += ": " + data.violations.map(violation => violation.message).join(", ");
+                }
+                if (data.errors) {
+                    error_msg += ": " + data.errors.map(error => `${error.field} ${error.message.toLowerCase()}`).join(", ");
+                }
                 if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
                     signupErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
                     signupErrAlter.style.display = "block";
@@ -122,6 +129,62 @@ signupButton.addEventListener("click", (e) => {
     });
 });
 
+const signupPasswordStrength = document.getElementById("signup-password-strength");
+const strengthLabels = ["Very weak", "Weak", "Fair", "Strong", "Very strong"];
+let strengthTimeout;
+
+// Estimates the password while the user types it, once they pause.
+signupForm.password.addEventListener("input", () => {
+    clearTimeout(strengthTimeout);
+    strengthTimeout = setTimeout(showPasswordStrength, 300);
+});
+
+function showPasswordStrength() {
+    const password = signupForm.password.value;
+    if (password === "") {
+        signupPasswordStrength.style.display = "none";
+        return;
+    }
+
+    // The email only helps once it is complete.
+    const body = { password };
+    if (signupForm.email.value !== "" && signupForm.email.checkValidity()) {
+        body.email = signupForm.email.value;
+    }
+
+    fetch('/password-strength', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify(body),
+    }).then(response => {
+        if (!response.ok) {
+            signupPasswordStrength.style.display = "none";
+            return;
+        }
+        response.json().then(data => {
+            // The password changed while this one was being estimated.
+            if (signupForm.password.value !== password) {
+                return;
+            }
+            const lines = [`${strengthLabels[data.score]}: cracked in ${data.crackTimeDisplay}`];
+            if (data.warning) {
+                lines.push(data.warning);
+            }
+            lines.push(...data.violations.map(violation => violation.message));
+            lines.push(...data.suggestions);
+            signupPasswordStrength.replaceChildren(...lines.map(line => {
+                const div = document.createElement("div");
+                div.textContent = line;
+                return div;
+            }));
+            signupPasswordStrength.className = `form-text text-start ${data.score < 3 || data.violations.length > 0 ? "text-danger" : "text-success"}`;
+            signupPasswordStrength.style.display = "block";
+        });
+    });
+}
+
 const TwoFAForm = document.getElementById("2fa-form");
 const TwoFAButton = document.getElementById("2fa-form-submit");
 const TwoFAErrAlter = document.getElementById("2fa-err-alert");
@@ -151,7 +214,7 @@ TwoFAButton.addEventListener("click", (e) => {
             signupSection.style.display = "none";
         } else {
             response.json().then(data => {
-                let error_msg = data.error;
+                let error_msg = data.title;
                 if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
                     TwoFAErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
                     TwoFAErrAlter.style.display = "block";
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS users (
+    email TEXT PRIMARY KEY NOT NULL,
+    password_hash TEXT NOT NULL,
+    requires_2fa BOOLEAN NOT NULL DEFAULT FALSE
+);
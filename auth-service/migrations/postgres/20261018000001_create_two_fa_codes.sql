@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS two_fa_codes (
+    email TEXT PRIMARY KEY NOT NULL,
+    login_attempt_id TEXT NOT NULL,
+    code TEXT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE two_fa_codes ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
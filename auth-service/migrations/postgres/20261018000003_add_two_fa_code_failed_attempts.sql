@@ -0,0 +1 @@
+ALTER TABLE two_fa_codes ADD COLUMN failed_attempts INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS passkey_credentials (
+    credential_id TEXT PRIMARY KEY NOT NULL,
+    email TEXT NOT NULL,
+    public_key BYTEA NOT NULL,
+    sign_count BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS passkey_credentials_email_idx ON passkey_credentials (email);
+
+CREATE TABLE IF NOT EXISTS passkey_challenges (
+    challenge TEXT PRIMARY KEY NOT NULL,
+    ceremony TEXT NOT NULL,
+    email TEXT,
+    expires_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Only the SHA-256 hashes of the codes are kept; using a code deletes its row.
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    email TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    PRIMARY KEY (email, code_hash)
+);
@@ -0,0 +1,7 @@
+-- Only the SHA-256 hash of the token is kept, and a user has at most one change pending.
+CREATE TABLE IF NOT EXISTS email_change_tokens (
+    email TEXT PRIMARY KEY NOT NULL,
+    new_email TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,2 @@
+-- A JSON array of role names.
+ALTER TABLE users ADD COLUMN roles TEXT NOT NULL DEFAULT '[]';
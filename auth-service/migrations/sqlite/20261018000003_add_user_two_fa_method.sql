@@ -0,0 +1,9 @@
+-- One of 'none', 'email' or 'totp'. TOTP secrets are kept as entered into authenticator
+-- apps, since codes cannot be checked against a hash of them.
+ALTER TABLE users ADD COLUMN two_fa_method TEXT NOT NULL DEFAULT 'none';
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+ALTER TABLE users ADD COLUMN pending_totp_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_last_used_step INTEGER;
+
+UPDATE users SET two_fa_method = 'email' WHERE requires_2fa;
+ALTER TABLE users DROP COLUMN requires_2fa;
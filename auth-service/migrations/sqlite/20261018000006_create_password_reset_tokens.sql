@@ -0,0 +1,6 @@
+-- Only the SHA-256 hash of the token is kept, and a user has at most one pending.
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    email TEXT PRIMARY KEY NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMP NOT NULL
+);
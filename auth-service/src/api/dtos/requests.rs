@@ -1,5 +1,5 @@
 use serde::Deserialize;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 /// Defines the sign-up request model.
@@ -13,10 +13,10 @@ pub struct SignUpRequest {
     /// The user's email address.
     #[validate(email)]
     pub email: String,
-    /// The user's password.
-    #[validate(length(min = 4))]
+    /// The user's password, which must follow the password policy.
     pub password: String,
-    /// Indicates if two-factor authentication is required.
+    /// Indicates if a code must be emailed at every login. Authenticator apps are
+    /// enrolled later, through `/enroll-totp`.
     #[serde(rename = "requires2FA")]
     pub requires_2fa: bool,
 }
@@ -29,10 +29,10 @@ pub struct SignUpRequest {
 }))]
 pub struct LoginRequest {
     /// The user's email address.
-    //#[validate(email)]
+    #[validate(email)]
     pub email: String,
     /// The user's password.
-    //#[validate(length(min = 4))]
+    #[validate(length(min = 4))]
     pub password: String,
 }
 
@@ -49,12 +49,46 @@ pub struct Verify2faRequest {
     pub email: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
-    /// The user's password.
+    /// The 2FA code, or one of the user's recovery codes.
     #[serde(rename = "2FACode")]
     #[validate(length(min = 6))]
     pub _2fa_code: String,
 }
 
+/// Defines the request model starting a TOTP enrollment.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "secret"
+}))]
+pub struct EnrollTotpRequest {
+    /// The user's password, proving the request does not come from a hijacked session.
+    pub password: String,
+}
+
+/// Defines the request model confirming a TOTP enrollment.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "2FACode": "123456",
+    "password": "secret"
+}))]
+pub struct ConfirmTotpRequest {
+    /// The code currently shown by the authenticator app.
+    #[serde(rename = "2FACode")]
+    pub two_fa_code: String,
+    /// The user's password.
+    pub password: String,
+}
+
+/// Defines the request model replacing the logged-in user's recovery codes.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "secret"
+}))]
+pub struct RegenerateRecoveryCodesRequest {
+    /// The user's password, proving the request does not come from a hijacked session.
+    pub password: String,
+}
+
 #[derive(Debug, Deserialize, ToSchema, Validate)]
 #[schema(example = json!({
     "token": "123e4567-e89b-12d3-a456-426614174000"
@@ -63,3 +97,225 @@ pub struct VerifyTokenRequest {
     /// The user's email address.
     pub token: String,
 }
+
+/// Defines the query of the link emailed to verify an address.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyEmailRequest {
+    /// The token the link carries.
+    pub token: String,
+}
+
+/// Defines the request for another verification email.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "email": "email@example.com"
+}))]
+pub struct ResendVerificationEmailRequest {
+    /// The address to verify.
+    #[validate(email)]
+    pub email: String,
+}
+
+/// Defines the request for a password reset link.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "email": "email@example.com"
+}))]
+pub struct PasswordResetRequest {
+    /// The address of the account whose password was forgotten.
+    #[validate(email)]
+    pub email: String,
+}
+
+/// Defines the request setting a new password with the token of a reset link.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "token": "k7Qx2mP9wLrT4vYb8nZc3hJf6sDg1aEu5oKi0pWqXyM",
+    "newPassword": "new-secret"
+}))]
+pub struct ConfirmPasswordResetRequest {
+    /// The token the reset link carries.
+    pub token: String,
+    /// The password replacing the forgotten one.
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+/// Defines the request changing the logged-in user's password.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "currentPassword": "secret",
+    "newPassword": "new-secret"
+}))]
+pub struct ChangePasswordRequest {
+    /// The password in use, proving the request does not come from a hijacked session.
+    #[serde(rename = "currentPassword")]
+    pub current_password: String,
+    /// The password replacing it.
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+/// Defines the request moving the logged-in user to another email address.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "newEmail": "new@example.com",
+    "password": "secret"
+}))]
+pub struct ChangeEmailRequest {
+    /// The address to move the account to.
+    #[serde(rename = "newEmail")]
+    #[validate(email)]
+    pub new_email: String,
+    /// The user's password.
+    pub password: String,
+}
+
+/// Defines the query of the link emailed to confirm an email change.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfirmEmailChangeRequest {
+    /// The token the link carries.
+    pub token: String,
+}
+
+/// Defines the request estimating the strength of a password.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "secret",
+    "email": "email@example.com"
+}))]
+pub struct PasswordStrengthRequest {
+    /// The password to estimate.
+    pub password: String,
+    /// The email address of the user choosing the password, whose words are among the
+    /// first an attacker would try.
+    #[validate(email)]
+    pub email: Option<String>,
+}
+
+/// Defines the request deleting the logged-in user's account.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "secret"
+}))]
+pub struct DeleteAccountRequest {
+    /// The user's password.
+    pub password: String,
+}
+
+/// Defines the OpenID Connect authentication request, sent as query parameters.
+/// Parameters are optional here so that missing ones can be reported as OAuth errors.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthorizeRequest {
+    /// Must be `code`.
+    pub response_type: Option<String>,
+    /// The ID of the registered client.
+    pub client_id: Option<String>,
+    /// One of the redirect URIs registered for the client.
+    pub redirect_uri: Option<String>,
+    /// Space-separated scopes, which must include `openid`.
+    pub scope: Option<String>,
+    /// Opaque value returned to the client with the response.
+    pub state: Option<String>,
+    /// Opaque value returned to the client in the ID token.
+    pub nonce: Option<String>,
+    /// The base64url SHA-256 of the client's code verifier.
+    pub code_challenge: Option<String>,
+    /// Must be `S256`.
+    pub code_challenge_method: Option<String>,
+}
+
+/// Defines the OAuth 2.0 token request, sent as a form.
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "grant_type": "authorization_code",
+    "code": "SplxlOBeZQQYbYS6WxSbIA",
+    "redirect_uri": "https://app.example.com/callback",
+    "client_id": "app",
+    "code_verifier": "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+}))]
+pub struct TokenRequest {
+    /// Must be `authorization_code`.
+    pub grant_type: Option<String>,
+    /// The code received on the redirect URI.
+    pub code: Option<String>,
+    /// The redirect URI of the authentication request.
+    pub redirect_uri: Option<String>,
+    /// The ID of the registered client.
+    pub client_id: Option<String>,
+    /// The secret of a confidential client.
+    pub client_secret: Option<String>,
+    /// The PKCE code verifier.
+    pub code_verifier: Option<String>,
+}
+
+/// Defines the request model starting a passkey registration.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "secret"
+}))]
+pub struct PasskeyRegistrationStartRequest {
+    /// The user's password, proving the request does not come from a hijacked session.
+    pub password: String,
+}
+
+/// Defines the request model finishing a passkey registration: the credential returned by
+/// `navigator.credentials.create()`, in its JSON form.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "id": "AQIDBA",
+    "type": "public-key",
+    "response": {
+        "clientDataJSON": "eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIiwi...",
+        "attestationObject": "o2NmbXRkbm9uZWdhdHRTdG10oGhhdXRoRGF0YV..."
+    }
+}))]
+pub struct PasskeyRegistrationRequest {
+    /// The base64url-encoded credential ID.
+    pub id: String,
+    pub response: PasskeyAttestationResponse,
+}
+
+/// The authenticator's response to a registration, with base64url-encoded fields.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PasskeyAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+/// Defines the request model finishing a passkey login: the credential returned by
+/// `navigator.credentials.get()`, in its JSON form.
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+#[schema(example = json!({
+    "id": "AQIDBA",
+    "type": "public-key",
+    "response": {
+        "clientDataJSON": "eyJ0eXBlIjoid2ViYXV0aG4uZ2V0Iiwi...",
+        "authenticatorData": "SZYN5YgOjGh0NBcPZHZgW4_krrmihjLHmVzzuoMdl2MdAAAAAA",
+        "signature": "MEUCIQDz...",
+        "userHandle": "9a8bSWHT9O6QfjE0BKTjJv2frm6mEIZjqw2zWx-7dDk"
+    }
+}))]
+pub struct PasskeyLoginRequest {
+    /// The base64url-encoded credential ID.
+    pub id: String,
+    pub response: PasskeyAssertionResponse,
+}
+
+/// The authenticator's response to a login, with base64url-encoded fields.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PasskeyAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+    /// The user handle the passkey was created under.
+    #[serde(rename = "userHandle")]
+    pub user_handle: Option<String>,
+}
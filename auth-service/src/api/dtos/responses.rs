@@ -1,27 +1,45 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{
+        HeaderValue, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE, RETRY_AFTER, WWW_AUTHENTICATE},
+    },
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::domain::error::AuthAPIError;
+use crate::{
+    api::middleware::current_request_id,
+    domain::{
+        error::{AuthAPIError, FieldError, OAuthError},
+        models::PasswordRule,
+    },
+};
 
 /// Defines the response model for successful sign-up.
 #[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 #[schema(example = json!({
-    "message": "User created successfully."
+    "message": "User created successfully.",
+    "recoveryCodes": ["k7m2q-x9fpr", "b3hnw-t8dcz"]
 }))]
 pub struct SignUpResponse {
     pub message: String,
+    /// The recovery codes of a user who signed up with 2FA. They are shown this once.
+    #[serde(
+        rename = "recoveryCodes",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub recovery_codes: Option<Vec<String>>,
 }
 
 /// Defines the response model for successful login requiring 2FA.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
     "message": "MFA required.",
-    "loginAttemptId": "123e4567-e89b-12d3-a456-426614174000"
+    "loginAttemptId": "123e4567-e89b-12d3-a456-426614174000",
+    "2FAMethod": "email"
 }))]
 pub struct MFARequiredResponse {
     /// The success message.
@@ -29,37 +47,616 @@ pub struct MFARequiredResponse {
     /// The unique identifier for the login attempt.
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    /// Where the code comes from: `email` if it was just emailed, `totp` if it is read
+    /// from an authenticator app.
+    #[serde(rename = "2FAMethod")]
+    pub two_fa_method: String,
+}
+
+/// Defines the response model of a TOTP enrollment.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "secret": "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP",
+    "otpauthUri": "otpauth://totp/Auth%20Service:email%40example%2Ecom?secret=JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP&issuer=Auth%20Service&algorithm=SHA1&digits=6&period=30",
+    "qrCodeSvg": "<?xml version=\"1.0\" standalone=\"yes\"?><svg ...</svg>"
+}))]
+pub struct TotpEnrollmentResponse {
+    /// The base32 secret, for users who type it into their authenticator app.
+    pub secret: String,
+    /// The `otpauth://` URI authenticator apps import the secret from.
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+    /// The URI rendered as an SVG QR code, to be scanned with the app.
+    #[serde(rename = "qrCodeSvg")]
+    pub qr_code_svg: String,
+}
+
+/// Defines the response model carrying a new set of recovery codes, each usable once
+/// in place of a 2FA code. They are shown this once: only their hashes are kept.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "recoveryCodes": ["k7m2q-x9fpr", "b3hnw-t8dcz"]
+}))]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+/// Defines how hard a password is to guess, as estimated by `/password-strength`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "score": 1,
+    "guessesLog10": 4.2,
+    "crackTimeSeconds": 1.6,
+    "crackTimeDisplay": "2 seconds",
+    "warning": "This is similar to a commonly used password",
+    "suggestions": ["Add another word or two. Uncommon words are better."],
+    "violations": [{ "rule": "breached", "message": "Appears in a known data breach" }]
+}))]
+pub struct PasswordStrengthResponse {
+    /// From 0, guessed within a thousand guesses, to 4, not within ten billion.
+    pub score: u8,
+    /// The base-10 logarithm of the number of guesses needed.
+    pub guesses_log10: f64,
+    /// How long an attacker holding the password hash would take to guess the password.
+    pub crack_time_seconds: f64,
+    /// The crack time in words, such as "3 hours" or "centuries".
+    pub crack_time_display: String,
+    /// Why the password is weak, when it is.
+    pub warning: Option<String>,
+    /// How to choose a stronger password.
+    pub suggestions: Vec<String>,
+    /// The rules of the password policy the password fails, empty when it would be accepted.
+    pub violations: Vec<PasswordViolation>,
+}
+
+/// Defines the archive of everything kept about a user, as served by `/account/export`.
+/// Secrets are left out: the password hash, the TOTP secret and the recovery code hashes
+/// are only told apart by whether they are set.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "email": "email@example.com",
+    "emailVerified": true,
+    "twoFAMethod": "totp",
+    "roles": ["admin"],
+    "failedLoginAttempts": 0,
+    "lockedUntil": null,
+    "recoveryCodesLeft": 10,
+    "passkeys": [{ "credentialId": "AbCdEf", "signCount": 3 }],
+    "exportedAt": "2026-10-18T12:00:00Z"
+}))]
+pub struct AccountExportResponse {
+    pub email: String,
+    pub email_verified: bool,
+    /// The second factor required at login: `none`, `email` or `totp`.
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: String,
+    pub roles: Vec<String>,
+    /// Consecutive failed logins since the last success or lockout.
+    pub failed_login_attempts: u32,
+    /// Until when logins are refused after too many failed attempts, in RFC 3339.
+    pub locked_until: Option<String>,
+    pub recovery_codes_left: usize,
+    pub passkeys: Vec<ExportedPasskey>,
+    /// When the archive was made, in RFC 3339.
+    pub exported_at: String,
+}
+
+/// Defines a passkey registered to the user, in an account export.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedPasskey {
+    /// The base64url-encoded credential ID.
+    pub credential_id: String,
+    /// The signature counter last reported by the authenticator.
+    pub sign_count: u32,
+}
+
+/// Defines the options of a passkey registration, to be passed to
+/// `navigator.credentials.create()` as its `publicKey` member (base64url fields decoded).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "rp": { "id": "localhost", "name": "Auth Service" },
+    "user": {
+        "id": "9a8bSWHT9O6QfjE0BKTjJv2frm6mEIZjqw2zWx-7dDk",
+        "name": "email@example.com",
+        "displayName": "email@example.com"
+    },
+    "challenge": "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+    "pubKeyCredParams": [
+        { "type": "public-key", "alg": -7 },
+        { "type": "public-key", "alg": -8 },
+        { "type": "public-key", "alg": -257 }
+    ],
+    "timeout": 300000,
+    "excludeCredentials": [],
+    "authenticatorSelection": {
+        "residentKey": "required",
+        "requireResidentKey": true,
+        "userVerification": "required"
+    },
+    "attestation": "none"
+}))]
+pub struct PasskeyRegistrationOptions {
+    pub rp: PasskeyRelyingParty,
+    pub user: PasskeyUser,
+    /// The base64url-encoded challenge to sign.
+    pub challenge: String,
+    pub pub_key_cred_params: Vec<PasskeyCredentialParameters>,
+    /// How many milliseconds the ceremony may take.
+    pub timeout: u64,
+    /// The user's existing passkeys, which the authenticator must not register twice.
+    pub exclude_credentials: Vec<PasskeyCredentialDescriptor>,
+    pub authenticator_selection: PasskeyAuthenticatorSelection,
+    pub attestation: String,
+}
+
+/// The relying party passkeys are scoped to.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PasskeyRelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+/// The account a passkey is created for.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUser {
+    /// The base64url-encoded user handle.
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+/// A signature algorithm the service accepts, as a COSE algorithm identifier.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PasskeyCredentialParameters {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+/// Names a registered passkey.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PasskeyCredentialDescriptor {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    /// The base64url-encoded credential ID.
+    pub id: String,
 }
 
-/// Defines the error response model.
+/// The authenticators a registration asks for: ones that keep the passkey and verify the user.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAuthenticatorSelection {
+    pub resident_key: String,
+    pub require_resident_key: bool,
+    pub user_verification: String,
+}
+
+/// Defines the options of a passkey login, to be passed to `navigator.credentials.get()`
+/// as its `publicKey` member (base64url fields decoded). No credentials are listed: the
+/// user picks any passkey they hold for the service.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 #[schema(example = json!({
-    "error": "Invalid credentials."
+    "challenge": "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+    "timeout": 300000,
+    "rpId": "localhost",
+    "allowCredentials": [],
+    "userVerification": "required"
+}))]
+pub struct PasskeyLoginOptions {
+    /// The base64url-encoded challenge to sign.
+    pub challenge: String,
+    /// How many milliseconds the ceremony may take.
+    pub timeout: u64,
+    pub rp_id: String,
+    pub allow_credentials: Vec<PasskeyCredentialDescriptor>,
+    pub user_verification: String,
+}
+
+/// Defines the error response model, an RFC 9457 (formerly RFC 7807) problem detail served
+/// as `application/problem+json`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "type": "urn:auth-service:problem:invalid_password",
+    "title": "Invalid password",
+    "status": 400,
+    "code": "invalid_password",
+    "request_id": "5f0c6f4e-8f3a-4f7e-9a57-7a0d2b1e4c3d",
+    "violations": [
+        { "rule": "min_length", "message": "Must be at least 8 characters long" },
+        { "rule": "breached", "message": "Appears in a known data breach" }
+    ]
 }))]
 pub struct ErrorResponse {
-    /// The error message.
-    pub error: String,
+    /// A URI naming the kind of problem, built from its code.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// A short summary of the problem, the same for every occurrence of its code.
+    pub title: String,
+    /// The HTTP status code.
+    pub status: u16,
+    /// What went wrong this time, when there is more to say than the title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub detail: Option<String>,
+    /// What clients should match the problem on.
+    pub code: ErrorCode,
+    /// The ID of the request, also returned in the `x-request-id` header.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub request_id: Option<String>,
+    /// The fields of the request that are not valid, for `invalid_input` and
+    /// `unprocessable_content` problems.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub errors: Option<Vec<InvalidField>>,
+    /// The password rules a new password fails, for `invalid_password` problems.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub violations: Option<Vec<PasswordViolation>>,
+}
+
+/// Defines the stable code of each kind of problem.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    UnsupportedMediaType,
+    MalformedRequest,
+    PayloadTooLarge,
+    UnprocessableContent,
+    InvalidInput,
+    InvalidPassword,
+    InvalidEmail,
+    UserAlreadyExists,
+    InvalidCredentials,
+    IncorrectCredentials,
+    TwoFaCodeExpired,
+    TooManyTwoFaAttempts,
+    TooManyRequests,
+    AccountLocked,
+    PasskeyAlreadyRegistered,
+    EmailNotVerified,
+    MissingToken,
+    InvalidToken,
+    UnexpectedError,
+}
+
+impl ErrorCode {
+    /// The code as serialized.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnsupportedMediaType => "unsupported_media_type",
+            Self::MalformedRequest => "malformed_request",
+            Self::PayloadTooLarge => "payload_too_large",
+            Self::UnprocessableContent => "unprocessable_content",
+            Self::InvalidInput => "invalid_input",
+            Self::InvalidPassword => "invalid_password",
+            Self::InvalidEmail => "invalid_email",
+            Self::UserAlreadyExists => "user_already_exists",
+            Self::InvalidCredentials => "invalid_credentials",
+            Self::IncorrectCredentials => "incorrect_credentials",
+            Self::TwoFaCodeExpired => "two_fa_code_expired",
+            Self::TooManyTwoFaAttempts => "too_many_two_fa_attempts",
+            Self::TooManyRequests => "too_many_requests",
+            Self::AccountLocked => "account_locked",
+            Self::PasskeyAlreadyRegistered => "passkey_already_registered",
+            Self::EmailNotVerified => "email_not_verified",
+            Self::MissingToken => "missing_token",
+            Self::InvalidToken => "invalid_token",
+            Self::UnexpectedError => "unexpected_error",
+        }
+    }
+}
+
+/// Defines a field of the request that is not valid.
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct InvalidField {
+    /// The path of the field, as sent, such as `email` or `response.signature`; `.` stands
+    /// for the body itself.
+    pub field: String,
+    /// Why the field is not valid.
+    pub message: String,
+}
+
+impl From<FieldError> for InvalidField {
+    fn from(error: FieldError) -> Self {
+        Self {
+            field: error.field,
+            message: error.message,
+        }
+    }
+}
+
+/// Defines a password rule a new password fails.
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PasswordViolation {
+    /// The code of the rule: `min_length`, `max_length`, `lowercase`, `uppercase`, `digit`,
+    /// `symbol`, `max_repeated_chars`, `email_local_part`, `min_strength` or `breached`.
+    pub rule: String,
+    /// What the rule asks for.
+    pub message: String,
+}
+
+impl From<&PasswordRule> for PasswordViolation {
+    fn from(rule: &PasswordRule) -> Self {
+        Self {
+            rule: rule.code().to_owned(),
+            message: rule.to_string(),
+        }
+    }
 }
 
 impl IntoResponse for AuthAPIError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AuthAPIError::InvalidPassword => (StatusCode::BAD_REQUEST, "Invalid password"),
-            AuthAPIError::InvalidEmail => (StatusCode::BAD_REQUEST, "Invalid email address"),
-            AuthAPIError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
-            AuthAPIError::InvalidCredentials => (StatusCode::BAD_REQUEST, "Invalid credentials"),
-            AuthAPIError::IncorrectCredentials => {
-                (StatusCode::UNAUTHORIZED, "Incorrect credentials")
-            }
-            AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing token"),
-            AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
-            AuthAPIError::UnexpectedError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
+        // Retry-After is a whole number of seconds, rounded up so clients do not retry early.
+        let retry_after = match &self {
+            AuthAPIError::TooManyRequests { retry_after }
+            | AuthAPIError::AccountLocked { retry_after } => {
+                Some((retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1))
             }
+            _ => None,
+        };
+        let detail = retry_after.map(|seconds| format!("Retry in {seconds} seconds"));
+        let (status, code, title) = match &self {
+            AuthAPIError::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                "Expected a JSON body",
+            ),
+            AuthAPIError::MalformedRequest => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::MalformedRequest,
+                "Malformed request",
+            ),
+            AuthAPIError::PayloadTooLarge => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                "Request body too large",
+            ),
+            AuthAPIError::UnprocessableContent(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::UnprocessableContent,
+                "Unprocessable content",
+            ),
+            AuthAPIError::InvalidInput(_) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidInput,
+                "Invalid input",
+            ),
+            AuthAPIError::InvalidPassword(_) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidPassword,
+                "Invalid password",
+            ),
+            AuthAPIError::InvalidEmail => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidEmail,
+                "Invalid email address",
+            ),
+            AuthAPIError::UserAlreadyExists => (
+                StatusCode::CONFLICT,
+                ErrorCode::UserAlreadyExists,
+                "User already exists",
+            ),
+            AuthAPIError::InvalidCredentials => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidCredentials,
+                "Invalid credentials",
+            ),
+            AuthAPIError::IncorrectCredentials => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::IncorrectCredentials,
+                "Incorrect credentials",
+            ),
+            AuthAPIError::TwoFACodeExpired => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::TwoFaCodeExpired,
+                "2FA code expired",
+            ),
+            AuthAPIError::TooManyTwoFAAttempts => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::TooManyTwoFaAttempts,
+                "Too many failed 2FA attempts, please log in again",
+            ),
+            AuthAPIError::TooManyRequests { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::TooManyRequests,
+                "Too many requests",
+            ),
+            AuthAPIError::AccountLocked { .. } => (
+                StatusCode::LOCKED,
+                ErrorCode::AccountLocked,
+                "Account temporarily locked",
+            ),
+            AuthAPIError::PasskeyAlreadyRegistered => (
+                StatusCode::CONFLICT,
+                ErrorCode::PasskeyAlreadyRegistered,
+                "Passkey already registered",
+            ),
+            AuthAPIError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                ErrorCode::EmailNotVerified,
+                "Email not verified",
+            ),
+            AuthAPIError::MissingToken => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::MissingToken,
+                "Missing token",
+            ),
+            AuthAPIError::InvalidToken => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::InvalidToken,
+                "Invalid token",
+            ),
+            AuthAPIError::UnexpectedError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::UnexpectedError,
+                "Unexpected error",
+            ),
+        };
+        let (errors, violations) = match self {
+            AuthAPIError::InvalidInput(errors) | AuthAPIError::UnprocessableContent(errors) => (
+                Some(errors.into_iter().map(InvalidField::from).collect()),
+                None,
+            ),
+            AuthAPIError::InvalidPassword(rules) => (
+                None,
+                Some(rules.iter().map(PasswordViolation::from).collect()),
+            ),
+            _ => (None, None),
         };
         let body = Json(ErrorResponse {
-            error: error_message.to_string(),
+            problem_type: format!("urn:auth-service:problem:{}", code.as_str()),
+            title: title.to_owned(),
+            status: status.as_u16(),
+            detail,
+            code,
+            request_id: current_request_id(),
+            errors,
+            violations,
+        });
+        let mut response = (
+            status,
+            [(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/problem+json"),
+            )],
+            body,
+        )
+            .into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// Defines the response model of a successful token request.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "access_token": "eyJhbGciOiJFZERTQSJ9...",
+    "token_type": "Bearer",
+    "expires_in": 600,
+    "id_token": "eyJhbGciOiJFZERTQSJ9...",
+    "scope": "openid email"
+}))]
+pub struct TokenResponse {
+    /// A JWT accepted by `/userinfo` only.
+    pub access_token: String,
+    /// Always `Bearer`.
+    pub token_type: String,
+    /// Lifetime of the access token, in seconds.
+    pub expires_in: i64,
+    /// A JWT identifying the user to the client.
+    pub id_token: String,
+    /// The granted scopes.
+    pub scope: String,
+}
+
+/// Defines the response model of the userinfo endpoint.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "sub": "email@example.com",
+    "email": "email@example.com"
+}))]
+pub struct UserInfoResponse {
+    /// The subject of the user's tokens.
+    pub sub: String,
+    /// The user's email address, when the `email` scope was granted.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub email: Option<String>,
+}
+
+/// Defines the OpenID Provider metadata served for discovery (OpenID Connect Discovery 1.0).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub scopes_supported: Vec<String>,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+/// Defines the error response model of the OpenID Connect endpoints (RFC 6749, section 5.2).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "error": "invalid_grant",
+    "error_description": "The authorization code is invalid or expired."
+}))]
+pub struct OAuthErrorResponse {
+    /// The error code.
+    pub error: String,
+    /// A human-readable explanation of the error.
+    pub error_description: String,
+}
+
+impl OAuthError {
+    /// The error code sent to the client, in a response body or on the redirect URI.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant => "invalid_grant",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::InvalidScope => "invalid_scope",
+            OAuthError::LoginRequired => "login_required",
+            OAuthError::InvalidToken => "invalid_token",
+            OAuthError::ServerError => "server_error",
+        }
+    }
+
+    /// A human-readable explanation of the error.
+    pub fn description(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest => {
+                "The request is missing a parameter or has an invalid one."
+            }
+            OAuthError::InvalidClient => "Client authentication failed.",
+            OAuthError::InvalidGrant => "The authorization code is invalid or expired.",
+            OAuthError::UnsupportedGrantType => "Only the authorization_code grant is supported.",
+            OAuthError::UnsupportedResponseType => "Only the code response type is supported.",
+            OAuthError::InvalidScope => "The openid scope is required.",
+            OAuthError::LoginRequired => "The user is not logged in.",
+            OAuthError::InvalidToken => "The access token is invalid or expired.",
+            OAuthError::ServerError => "Unexpected error.",
+        }
+    }
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            OAuthError::InvalidClient | OAuthError::InvalidToken => StatusCode::UNAUTHORIZED,
+            OAuthError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let body = Json(OAuthErrorResponse {
+            error: self.code().to_owned(),
+            error_description: self.description().to_owned(),
         });
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        let headers = response.headers_mut();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        // Bearer token errors are also reported in the challenge (RFC 6750, section 3).
+        if self == OAuthError::InvalidToken {
+            headers.insert(
+                WWW_AUTHENTICATE,
+                HeaderValue::from_static(r#"Bearer error="invalid_token""#),
+            );
+        }
+        response
     }
 }
@@ -0,0 +1,222 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::domain::error::{AuthAPIError, FieldError};
+
+/// Extracts a JSON body and checks it against the `#[validate(...)]` rules of its type.
+///
+/// A body that is not declared as JSON is refused with a 415, one that is not well-formed
+/// JSON with a 400, and one that does not fit the type, such as a missing field, with a
+/// 422 naming the field. A body breaking the validation rules is refused with a 400 that
+/// lists every invalid field.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<Value>::from_request(request, state).await.map_err(
+            |rejection| match rejection {
+                JsonRejection::MissingJsonContentType(_) => AuthAPIError::UnsupportedMediaType,
+                _ => AuthAPIError::MalformedRequest,
+            },
+        )?;
+
+        let value: T = serde_path_to_error::deserialize(&body)
+            .map_err(|error| AuthAPIError::UnprocessableContent(vec![shape_error(error)]))?;
+        value
+            .validate()
+            .map_err(|errors| AuthAPIError::InvalidInput(field_errors(&errors, &body)))?;
+        Ok(Self(value))
+    }
+}
+
+// Tells where the body departs from the expected shape.
+fn shape_error(error: serde_path_to_error::Error<serde_json::Error>) -> FieldError {
+    let path = error.path().to_string();
+    let message = error.inner().to_string();
+    // Missing fields are reported at the object holding them.
+    match message
+        .strip_prefix("missing field `")
+        .and_then(|field| field.strip_suffix('`'))
+    {
+        Some(field) => FieldError {
+            field: match path.as_str() {
+                "." => field.to_owned(),
+                _ => format!("{path}.{field}"),
+            },
+            message: "Is required".to_owned(),
+        },
+        None => FieldError {
+            field: path,
+            message: capitalize(&message),
+        },
+    }
+}
+
+// Lists the fields breaking validation rules, in a stable order.
+fn field_errors(errors: &ValidationErrors, body: &Value) -> Vec<FieldError> {
+    let mut fields: Vec<_> = errors.field_errors().into_iter().collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    fields
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            let field = sent_name(field.as_ref(), body);
+            errors.iter().map(move |error| FieldError {
+                field: field.clone(),
+                message: describe(error),
+            })
+        })
+        .collect()
+}
+
+// Validation errors are keyed by the Rust name of the field, which may be renamed in JSON,
+// as `new_email` is to `newEmail`. The key the client sent for it is reported instead,
+// matched ignoring case and punctuation, and otherwise the Rust name.
+fn sent_name(field: &str, body: &Value) -> String {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+    body.as_object()
+        .and_then(|object| object.keys().find(|key| normalize(key) == normalize(field)))
+        .cloned()
+        .unwrap_or_else(|| field.to_owned())
+}
+
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).and_then(Value::as_u64);
+    match error.code.as_ref() {
+        "email" => "Must be a valid email address".to_owned(),
+        "length" => match (param("min"), param("max"), param("equal")) {
+            (_, _, Some(equal)) => format!("Must be {equal} characters long"),
+            (Some(min), Some(max), _) => format!("Must be {min} to {max} characters long"),
+            (Some(min), None, _) => format!("Must be at least {min} characters long"),
+            (None, Some(max), _) => format!("Must be at most {max} characters long"),
+            (None, None, _) => "Has an invalid length".to_owned(),
+        },
+        "url" => "Must be a valid URL".to_owned(),
+        code => format!("Breaks the {code} rule"),
+    }
+}
+
+fn capitalize(message: &str) -> String {
+    let mut chars = message.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::header::CONTENT_TYPE};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Body2fa {
+        #[serde(rename = "newEmail")]
+        #[validate(email)]
+        new_email: String,
+        #[serde(rename = "2FACode")]
+        #[validate(length(min = 6))]
+        _2fa_code: String,
+    }
+
+    async fn extract(content_type: &str, body: &str) -> Result<Body2fa, AuthAPIError> {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_owned()))
+            .unwrap();
+        ValidatedJson::<Body2fa>::from_request(request, &())
+            .await
+            .map(|ValidatedJson(body)| body)
+    }
+
+    fn fields(errors: &[FieldError]) -> Vec<(&str, &str)> {
+        errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.message.as_str()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_valid_bodies_are_extracted() {
+        let body = extract(
+            "application/json",
+            r#"{"newEmail":"jane@example.com","2FACode":"123456"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(body.new_email, "jane@example.com");
+        assert_eq!(body._2fa_code, "123456");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_bodies_are_rejected() {
+        assert!(matches!(
+            extract("text/plain", "{}").await,
+            Err(AuthAPIError::UnsupportedMediaType)
+        ));
+        assert!(matches!(
+            extract("application/json", r#"{"newEmail":"#).await,
+            Err(AuthAPIError::MalformedRequest)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_shape_errors_name_the_field() {
+        let Err(AuthAPIError::UnprocessableContent(errors)) =
+            extract("application/json", r#"{"newEmail":"jane@example.com"}"#).await
+        else {
+            panic!("Expected an unprocessable content error");
+        };
+        assert_eq!(fields(&errors), [("2FACode", "Is required")]);
+
+        let Err(AuthAPIError::UnprocessableContent(errors)) =
+            extract("application/json", r#"{"newEmail":1,"2FACode":"123456"}"#).await
+        else {
+            panic!("Expected an unprocessable content error");
+        };
+        assert_eq!(
+            fields(&errors),
+            [("newEmail", "Invalid type: integer `1`, expected a string")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_invalid_field_is_listed_under_its_sent_name() {
+        let Err(AuthAPIError::InvalidInput(errors)) = extract(
+            "application/json",
+            r#"{"newEmail":"invalid","2FACode":"123"}"#,
+        )
+        .await
+        else {
+            panic!("Expected an invalid input error");
+        };
+        assert_eq!(
+            fields(&errors),
+            [
+                ("2FACode", "Must be at least 6 characters long"),
+                ("newEmail", "Must be a valid email address"),
+            ]
+        );
+    }
+}
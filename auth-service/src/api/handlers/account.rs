@@ -0,0 +1,181 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{StatusCode, header::CONTENT_DISPOSITION},
+    response::IntoResponse,
+};
+use axum_extra::extract::{CookieJar, cookie::Cookie};
+use chrono::Utc;
+
+use super::{authenticated_user, check_password, revoke_sessions};
+use crate::{
+    AppState,
+    api::{
+        dtos::{AccountExportResponse, DeleteAccountRequest, ErrorResponse, ExportedPasskey},
+        extractors::ValidatedJson,
+        utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+    domain::{
+        error::AuthAPIError,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
+    },
+};
+
+#[utoipa::path(
+    delete,
+    path = "/account",
+    description = "Delete the logged-in user's account. The user must hold the `jwt` cookie and give their password. The user is purged from every store, their passkeys and pending 2FA code included, and every session is revoked.",
+    request_body = DeleteAccountRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Account deleted", headers(("x-set-cookie" = String, description = "jwt=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; HttpOnly; SameSite=Lax; Secure; Path=/, refresh_token=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Path=/")),),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_delete_account<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<DeleteAccountRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+    check_password(&state, &user, &request.password).await?;
+
+    match state
+        .user_store
+        .write()
+        .await
+        .delete_user(&user.email)
+        .await
+    {
+        Ok(()) => {}
+        // Deleted concurrently, by another session of the user.
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+    state
+        .two_fa_store
+        .write()
+        .await
+        .remove_code(&user.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .passkey_store
+        .write()
+        .await
+        .delete_credentials(&user.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    revoke_sessions(&state, &user.email).await?;
+
+    let jar = jar
+        .clone()
+        .remove(Cookie::new(JWT_COOKIE_NAME, cookie.value().to_owned()))
+        .remove(Cookie::build(REFRESH_TOKEN_COOKIE_NAME).path("/"));
+
+    // The account is deleted even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &user.email,
+            "Your account was deleted",
+            "Your account and everything kept about it were deleted. \
+             If you did not do this, contact us at once.",
+        )
+        .await;
+
+    Ok((jar, StatusCode::OK))
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/export",
+    description = "Download a JSON archive of everything kept about the logged-in user, who must hold the `jwt` cookie. Secrets such as the password hash are left out.",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Account archive", body = AccountExportResponse, content_type = "application/json",
+            headers(("Content-Disposition" = String, description = "attachment; filename=\"account-export.json\""))),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_export_account<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+
+    let recovery_codes_left = state
+        .user_store
+        .read()
+        .await
+        .count_recovery_codes(&user.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    let passkeys = state
+        .passkey_store
+        .read()
+        .await
+        .get_credentials(&user.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .into_iter()
+        .map(|credential| ExportedPasskey {
+            credential_id: credential.credential_id,
+            sign_count: credential.sign_count,
+        })
+        .collect();
+
+    let archive = AccountExportResponse {
+        email: user.email.as_ref().to_owned(),
+        email_verified: user.email_verified,
+        two_fa_method: user.two_fa_method.name().to_owned(),
+        roles: user.roles,
+        failed_login_attempts: user.failed_login_attempts,
+        locked_until: user
+            .locked_until
+            .map(|locked_until| locked_until.to_rfc3339()),
+        recovery_codes_left,
+        passkeys,
+        exported_at: Utc::now().to_rfc3339(),
+    };
+
+    Ok((
+        [(
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"account-export.json\"",
+        )],
+        Json(archive),
+    ))
+}
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Redirect},
+};
+use axum_extra::extract::CookieJar;
+use chrono::{TimeDelta, Utc};
+use url::Url;
+
+use super::authenticated_user;
+use crate::{
+    AppState,
+    api::{
+        dtos::{AuthorizeRequest, OAuthErrorResponse},
+        utils::constants::{AUTHORIZATION_CODE_TTL_SECONDS, JWT_COOKIE_NAME},
+    },
+    domain::{
+        error::{AuthAPIError, OAuthError},
+        models::{AuthorizationCode, AuthorizationCodeRecord, CodeChallenge},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, OAuthClientStoreError, PasskeyStore,
+            RefreshTokenStore, TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/authorize",
+    description = "OpenID Connect authentication request, using the authorization code flow with PKCE. The user must be logged in, i.e. hold the `jwt` cookie set by `/login`. The response is a redirect to the client's redirect URI carrying either a `code` or an `error`, along with the `state` of the request.",
+    params(AuthorizeRequest),
+    tag = "oidc",
+    responses(
+        (status = 303, description = "Redirect to the client with an authorization code or an error"),
+        (status = 400, description = "Unknown client or unregistered redirect URI", body = OAuthErrorResponse, content_type = "application/json"),
+        (status = 500, description = "Unexpected error", body = OAuthErrorResponse, content_type = "application/json"),
+    )
+)]
+pub async fn handle_authorize<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    Query(request): Query<AuthorizeRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    // Until the redirect URI is known to belong to the client, errors are shown to the
+    // user rather than sent anywhere.
+    let client_id = request
+        .client_id
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest)?;
+    let client = match state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => return Err(OAuthError::InvalidRequest),
+        Err(_) => return Err(OAuthError::ServerError),
+    };
+    let redirect_uri = request
+        .redirect_uri
+        .as_deref()
+        .filter(|redirect_uri| client.allows_redirect_uri(redirect_uri))
+        .ok_or(OAuthError::InvalidRequest)?;
+    let mut location = Url::parse(redirect_uri).map_err(|_| OAuthError::InvalidRequest)?;
+
+    let result = issue_authorization_code(&state, &jar, &request, redirect_uri).await;
+
+    {
+        let mut query = location.query_pairs_mut();
+        match &result {
+            Ok(code) => query.append_pair("code", code.as_ref()),
+            Err(error) => query.append_pair("error", error.code()),
+        };
+        if let Some(client_state) = &request.state {
+            query.append_pair("state", client_state);
+        }
+    }
+
+    Ok(Redirect::to(location.as_str()))
+}
+
+// Checks the rest of the request and issues a code to the logged-in user.
+async fn issue_authorization_code<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    jar: &CookieJar,
+    request: &AuthorizeRequest,
+    redirect_uri: &str,
+) -> Result<AuthorizationCode, OAuthError> {
+    if request.response_type.as_deref() != Some("code") {
+        return Err(OAuthError::UnsupportedResponseType);
+    }
+    let scope = request.scope.as_deref().unwrap_or_default();
+    if !scope.split_whitespace().any(|scope| scope == "openid") {
+        return Err(OAuthError::InvalidScope);
+    }
+    // PKCE is mandatory, and the plain method would hand the verifier to anyone who sees the request.
+    if request.code_challenge_method.as_deref() != Some("S256") {
+        return Err(OAuthError::InvalidRequest);
+    }
+    let code_challenge = request
+        .code_challenge
+        .clone()
+        .ok_or(OAuthError::InvalidRequest)
+        .and_then(|challenge| {
+            CodeChallenge::parse(challenge).map_err(|_| OAuthError::InvalidRequest)
+        })?;
+
+    let token = jar
+        .get(JWT_COOKIE_NAME)
+        .ok_or(OAuthError::LoginRequired)?
+        .value();
+    let user = authenticated_user(state, token)
+        .await
+        .map_err(|error| match error {
+            AuthAPIError::InvalidToken => OAuthError::LoginRequired,
+            _ => OAuthError::ServerError,
+        })?;
+
+    let ttl =
+        TimeDelta::try_seconds(AUTHORIZATION_CODE_TTL_SECONDS).ok_or(OAuthError::ServerError)?;
+    let record = AuthorizationCodeRecord {
+        client_id: request.client_id.clone().unwrap_or_default(),
+        redirect_uri: redirect_uri.to_owned(),
+        email: user.email,
+        scope: scope.to_owned(),
+        nonce: request.nonce.clone(),
+        code_challenge,
+        expires_at: Utc::now() + ttl,
+    };
+
+    let code = AuthorizationCode::default();
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .add_authorization_code(&code, record)
+        .await
+        .map_err(|_| OAuthError::ServerError)?;
+
+    Ok(code)
+}
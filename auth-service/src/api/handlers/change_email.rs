@@ -0,0 +1,165 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_extra::extract::CookieJar;
+use url::Url;
+
+use super::{authenticated_user, check_password, expiry, revoke_sessions};
+use crate::{
+    AppState,
+    api::{
+        dtos::{ChangeEmailRequest, ConfirmEmailChangeRequest, ErrorResponse},
+        extractors::ValidatedJson,
+        utils::constants::{EMAIL_CHANGE_URL, EMAIL_VERIFICATION_TTL_SECONDS, JWT_COOKIE_NAME},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, EmailChangeToken},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/change-email",
+    description = "Move the logged-in user to another email address. The user must hold the `jwt` cookie and give their password. The change only takes effect once confirmed through the link emailed to the new address, and is called off by a password change or reset.",
+    request_body = ChangeEmailRequest,
+    tag = "auth",
+    responses(
+        (status = 202, description = "A confirmation link was emailed to the new address"),
+        (status = 400, description = "Missing token or invalid email address", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Email already exists", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_change_email<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<ChangeEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+
+    let new_email = Email::parse(&request.new_email).map_err(|_| AuthAPIError::InvalidEmail)?;
+    check_password(&state, &user, &request.password).await?;
+
+    match state.user_store.read().await.get_user(&new_email).await {
+        Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+
+    // The change is kept until confirmed, so that changing the password, resetting it or
+    // deleting the account calls it off.
+    let token = EmailChangeToken::default();
+    let expires_at = expiry(*EMAIL_VERIFICATION_TTL_SECONDS)?;
+    state
+        .user_store
+        .write()
+        .await
+        .set_email_change_token(&user.email, &new_email, &token, expires_at)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let mut link = Url::parse(&EMAIL_CHANGE_URL).map_err(|_| AuthAPIError::UnexpectedError)?;
+    link.query_pairs_mut().append_pair("token", token.as_ref());
+    let content = format!(
+        "Confirm that your account moves to this address by opening {link}. \
+         If you did not ask for it, you can ignore this email."
+    );
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(&new_email, "Confirm your new email", &content)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/confirm-email-change",
+    description = "Move the user to their new email address with the link emailed to it, which is then used up. Every session is revoked, since it was issued to the previous address, and the previous address is notified.",
+    params(ConfirmEmailChangeRequest),
+    tag = "auth",
+    responses(
+        (status = 200, description = "Email changed"),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Token is not valid, expired or was already used", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Email already exists", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_confirm_email_change<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    Query(request): Query<ConfirmEmailChangeRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = EmailChangeToken::parse(request.token).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let mut user_store = state.user_store.write().await;
+    let (email, new_email) = user_store
+        .consume_email_change_token(&token)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .ok_or(AuthAPIError::InvalidToken)?;
+    match user_store.update_email(&email, &new_email).await {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(UserStoreError::UserAlreadyExists) => return Err(AuthAPIError::UserAlreadyExists),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+    drop(user_store);
+    state
+        .passkey_store
+        .write()
+        .await
+        .update_email(&email, &new_email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    revoke_sessions(&state, &email).await?;
+
+    // The address is changed even if the notice is not delivered.
+    let content = format!(
+        "Your account moved to {}, which you now log in with. \
+         If you did not do this, contact us at once.",
+        new_email.as_ref()
+    );
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(&email, "Your email was changed", &content)
+        .await;
+
+    Ok(StatusCode::OK)
+}
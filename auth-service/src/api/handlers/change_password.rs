@@ -0,0 +1,84 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+
+use super::{authenticated_user, check_new_password, check_password};
+use crate::{
+    AppState,
+    api::{
+        dtos::{ChangePasswordRequest, ErrorResponse},
+        extractors::ValidatedJson,
+        utils::constants::{JWT_COOKIE_NAME, PASSWORD_HASHING_POLICY},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::PasswordHash,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/change-password",
+    description = "Change the logged-in user's password. The user must hold the `jwt` cookie and give their current password, a wrong one counting as a failed login. The user is notified by email.",
+    request_body = ChangePasswordRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Password changed"),
+        (status = 400, description = "Missing token, or a new password that fails the password policy, which lists the rules it fails", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the current password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_change_password<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+
+    let new_password = check_new_password(&request.new_password, Some(&user.email)).await?;
+    check_password(&state, &user, &request.current_password).await?;
+
+    let password_hash = PasswordHash::compute(&new_password, &PASSWORD_HASHING_POLICY)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(&user.email, password_hash)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    // The password is changed even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &user.email,
+            "Your password was changed",
+            "The password of your account was changed. \
+             If you did not do this, reset it at once and review your account.",
+        )
+        .await;
+
+    Ok(StatusCode::OK)
+}
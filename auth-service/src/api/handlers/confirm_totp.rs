@@ -0,0 +1,97 @@
+use axum::{Json, extract::State, http::header::CACHE_CONTROL, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+
+use super::{authenticated_user, check_password, issue_recovery_codes};
+use crate::{
+    AppState,
+    api::{
+        dtos::{ConfirmTotpRequest, ErrorResponse, RecoveryCodesResponse},
+        extractors::ValidatedJson,
+        utils::constants::{JWT_COOKIE_NAME, TOTP_SKEW_STEPS},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{TwoFACode, TwoFAMethod},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/confirm-totp",
+    description = "Finish enrolling an authenticator app with the first code it shows and the user's password, a wrong one counting as a failed login. From then on, logins ask for a code from the app instead of an emailed one. The response carries a new set of recovery codes, each usable once in place of such a code, which replace any previous ones. The user is notified by email.",
+    request_body = ConfirmTotpRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Authenticator app enrolled", body = RecoveryCodesResponse, content_type = "application/json"),
+        (status = 400, description = "Missing token or invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid, the password is incorrect, no enrollment is pending, or the code is wrong", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_confirm_totp<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<ConfirmTotpRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+    check_password(&state, &user, &request.password).await?;
+    let code =
+        TwoFACode::parse(request.two_fa_code).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let secret = user
+        .pending_totp_secret
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+    let step = secret
+        .verify(&code, Utc::now(), *TOTP_SKEW_STEPS)
+        .ok_or(AuthAPIError::IncorrectCredentials)?;
+
+    let mut user_store = state.user_store.write().await;
+    user_store
+        .update_two_fa_method(&user.email, TwoFAMethod::Totp(secret))
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    // The confirming code cannot be replayed to log in.
+    user_store
+        .record_totp_use(&user.email, step)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    let recovery_codes = issue_recovery_codes(&mut *user_store, &user.email).await?;
+    drop(user_store);
+
+    // The authenticator app is enrolled even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &user.email,
+            "An authenticator app was set up",
+            "An authenticator app now provides the second factor of your account. \
+             If you did not do this, reset your password at once and review your account.",
+        )
+        .await;
+
+    Ok((
+        [(CACHE_CONTROL, "no-store")],
+        Json(RecoveryCodesResponse { recovery_codes }),
+    ))
+}
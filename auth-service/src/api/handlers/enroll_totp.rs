@@ -0,0 +1,83 @@
+use axum::{Json, extract::State, http::header::CACHE_CONTROL, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use qrcode::{QrCode, render::svg};
+
+use super::{authenticated_user, check_password};
+use crate::{
+    AppState,
+    api::{
+        dtos::{EnrollTotpRequest, ErrorResponse, TotpEnrollmentResponse},
+        extractors::ValidatedJson,
+        utils::constants::{JWT_COOKIE_NAME, TOTP_ISSUER},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::TotpSecret,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/enroll-totp",
+    description = "Start enrolling an authenticator app as second factor. The user must hold the `jwt` cookie and give their password, a wrong one counting as a failed login. The new secret only takes effect once confirmed with a first code through `/confirm-totp`; until then the current 2FA method stays in force, and enrolling again replaces the secret.",
+    request_body = EnrollTotpRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "TOTP secret to load into the authenticator app", body = TotpEnrollmentResponse, content_type = "application/json"),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_enroll_totp<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<EnrollTotpRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+    check_password(&state, &user, &request.password).await?;
+
+    let secret = TotpSecret::default();
+    let otpauth_uri = secret.otpauth_uri(&TOTP_ISSUER, &user.email);
+    let qr_code_svg = QrCode::new(otpauth_uri.as_bytes())
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_pending_totp_secret(&user.email, secret.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    // The secret is as good as a password for the second factor, so keep it out of caches.
+    Ok((
+        [(CACHE_CONTROL, "no-store")],
+        Json(TotpEnrollmentResponse {
+            secret: secret.as_ref().to_owned(),
+            otpauth_uri,
+            qr_code_svg,
+        }),
+    ))
+}
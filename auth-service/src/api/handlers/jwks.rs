@@ -0,0 +1,19 @@
+use crate::api::utils::auth::key_ring;
+use axum::{Json, http::header, response::IntoResponse};
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    description = "Public keys to verify issued JWTs with, selected by the token's `kid` header. The active key comes first; HMAC secrets are never listed.",
+    tag = "auth",
+    responses(
+        (status = 200, description = "JSON Web Key Set", body = Object, content_type = "application/json"),
+    )
+)]
+pub async fn handle_jwks() -> impl IntoResponse {
+    // Consumers may cache the set, but should pick up a new key within minutes.
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(key_ring().jwks()),
+    )
+}
@@ -1,16 +1,26 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
+use chrono::{TimeDelta, Utc};
+use serde::Serialize;
 
+use super::issue_refresh_token;
 use crate::{
     AppState,
     api::{
         dtos::{ErrorResponse, LoginRequest, MFARequiredResponse},
-        utils::auth::generate_auth_cookie,
+        extractors::ValidatedJson,
+        utils::{
+            auth::generate_auth_cookie,
+            constants::{LOCKOUT_POLICY, PASSWORD_HASHING_POLICY, PASSWORD_POLICY},
+        },
     },
     domain::{
         error::AuthAPIError,
-        models::{Email, LoginAttemptId, Password, TwoFACode},
-        ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore},
+        models::{Email, LoginAttemptId, Password, PasswordHash, TwoFACode, TwoFAMethod, User},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
     },
 };
 
@@ -22,49 +32,205 @@ use crate::{
     tag = "auth",
     responses(
         (status = 200, description = "Login successful", 
-            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Secure; Path=/")),
+            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Secure; Path=/, refresh_token=your_refresh_token; HttpOnly; SameSite=Strict; Path=/; Max-Age=1209600")),
         ),
         (status = 206, description = "Login requires 2FA", body = MFARequiredResponse, content_type = "application/json"),
-        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/json"),
-        (status = 401, description = "Authentication failed", body = ErrorResponse, content_type = "application/json"),
-        (status = 422, description = "Unprocessable content", body = ErrorResponse, content_type = "application/json"),
-        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Authentication failed", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 403, description = "Email not verified yet", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Body larger than 64 KiB", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Too many requests",
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn handle_login<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    State(state): State<AppState<S, B, T, E>>,
+pub async fn handle_login<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
     jar: CookieJar,
-    Json(request): Json<LoginRequest>,
+    ValidatedJson(request): ValidatedJson<LoginRequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
     let email = Email::parse(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
-    let password =
-        Password::parse(&request.password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let password = Password::parse_existing(&request.password, &PASSWORD_POLICY)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
 
-    let user_store = state.user_store.write().await;
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(_) => {
+            // Takes as long as a wrong password would, so that timing does not reveal
+            // which accounts exist.
+            PasswordHash::verify_dummy(&password, &PASSWORD_HASHING_POLICY).await;
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+    };
 
-    if user_store.validate_user(&email, &password).await.is_err() {
-        return Err(AuthAPIError::IncorrectCredentials);
+    // A locked account is refused before the password is checked, so guessing gains nothing.
+    if let Some(retry_after) = user.locked_for(Utc::now()) {
+        return Err(AuthAPIError::AccountLocked { retry_after });
     }
 
-    let user = match user_store.get_user(&email).await {
-        Ok(user) => user,
-        Err(_) => return Err(AuthAPIError::IncorrectCredentials),
-    };
+    if user.password_hash.verify(&password).await.is_err() {
+        return Err(handle_failed_login(&email, &state).await);
+    }
+
+    // Only told once the password is known, so that it does not reveal which accounts exist.
+    if !user.email_verified {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
+    // Upgrade hashes produced under an older, weaker policy now that we hold the plaintext.
+    // This is best effort: a failed rehash must not prevent the user from logging in.
+    if user.password_hash.needs_rehash(&PASSWORD_HASHING_POLICY)
+        && let Ok(password_hash) = PasswordHash::compute(&password, &PASSWORD_HASHING_POLICY).await
+    {
+        let _ = state
+            .user_store
+            .write()
+            .await
+            .update_password(&email, password_hash)
+            .await;
+    }
 
     // Handle request based on user's 2FA configuration
-    match user.requires_2fa {
-        true => handle_2fa(&email, &state, jar).await,
-        false => handle_no_2fa(&user.email, jar).await,
+    match user.two_fa_method {
+        TwoFAMethod::None => handle_no_2fa(&user, &state, jar).await,
+        _ => handle_2fa(&email, &user.two_fa_method, &state, jar).await,
     }
 }
 
-// New!
-async fn handle_2fa<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
+// Counts the failure and locks the account once the lockout threshold is reached.
+pub(crate) async fn handle_failed_login<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
     email: &Email,
-    state: &AppState<S, B, T, E>, // New!
+    state: &AppState<S, B, T, E, R, O, P>,
+) -> AuthAPIError {
+    let mut user_store = state.user_store.write().await;
+    let Ok(failed_attempts) = user_store.record_failed_login(email).await else {
+        return AuthAPIError::UnexpectedError;
+    };
+    if failed_attempts < LOCKOUT_POLICY.max_failed_attempts {
+        return AuthAPIError::IncorrectCredentials;
+    }
+
+    let lockout_duration = LOCKOUT_POLICY.lockout_duration;
+    let Ok(locked_until) = TimeDelta::from_std(lockout_duration).map(|d| Utc::now() + d) else {
+        return AuthAPIError::UnexpectedError;
+    };
+    if user_store.lock_user(email, locked_until).await.is_err() {
+        return AuthAPIError::UnexpectedError;
+    }
+    drop(user_store);
+
+    // The lock holds even if the notification cannot be delivered.
+    let content = format!(
+        "Your account was locked after {failed_attempts} failed login attempts. \
+         It unlocks automatically at {}. If this was not you, change your password.",
+        locked_until.format("%Y-%m-%d %H:%M UTC")
+    );
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(email, "Account locked", &content)
+        .await;
+
+    AuthAPIError::AccountLocked {
+        retry_after: lockout_duration,
+    }
+}
+
+// Clears the failed logins of a user who completed a login. With 2FA, that is only once the
+// second factor was verified too, so that the password alone does not reset the count.
+pub(crate) async fn clear_failed_logins<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    user: &User,
+) -> Result<(), AuthAPIError> {
+    if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+        state
+            .user_store
+            .write()
+            .await
+            .reset_failed_logins(&user.email)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+    }
+    Ok(())
+}
+
+// Checks the password of a logged-in user before a sensitive change. A wrong password
+// counts as a failed login, so that a hijacked session cannot guess it at will.
+pub(crate) async fn check_password<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    user: &User,
+    password: &str,
+) -> Result<(), AuthAPIError> {
+    if let Some(retry_after) = user.locked_for(Utc::now()) {
+        return Err(AuthAPIError::AccountLocked { retry_after });
+    }
+
+    let verified = match Password::parse_existing(password, &PASSWORD_POLICY) {
+        Ok(password) => user.password_hash.verify(&password).await.is_ok(),
+        Err(_) => false,
+    };
+    if !verified {
+        return Err(handle_failed_login(&user.email, state).await);
+    }
+    Ok(())
+}
+
+async fn handle_2fa<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    email: &Email,
+    two_fa_method: &TwoFAMethod,
+    state: &AppState<S, B, T, E, R, O, P>,
     jar: CookieJar,
 ) -> Result<(CookieJar, (StatusCode, Json<LoginResponse>)), AuthAPIError> {
-    // First, we must generate a new random login attempt ID and 2FA code
+    // First, we must generate a new random login attempt ID and 2FA code.
+    // TOTP users never see the code: their attempt is settled against the app's code,
+    // under the same lifetime and attempt limit.
     let login_attempt_id = LoginAttemptId::default();
     let two_fa_code = TwoFACode::default();
 
@@ -78,7 +244,12 @@ async fn handle_2fa<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailCli
     {
         return Err(AuthAPIError::UnexpectedError);
     }
-    if email_client.send_email(&email, "Login", two_fa_code.as_ref()).await.is_err() {
+    if *two_fa_method == TwoFAMethod::Email
+        && email_client
+            .send_email(email, "Login", two_fa_code.as_ref())
+            .await
+            .is_err()
+    {
         return Err(AuthAPIError::UnexpectedError);
     }
     Ok((
@@ -88,19 +259,31 @@ async fn handle_2fa<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailCli
             Json(LoginResponse::TwoFactorAuth(MFARequiredResponse {
                 message: "2FA required".to_owned(),
                 login_attempt_id: login_attempt_id.as_ref().to_string(),
+                two_fa_method: two_fa_method.name().to_owned(),
             })),
         ),
     ))
 }
 
-// New!
-async fn handle_no_2fa(
-    email: &Email,
+async fn handle_no_2fa<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    user: &User,
+    state: &AppState<S, B, T, E, R, O, P>,
     jar: CookieJar,
 ) -> Result<(CookieJar, (StatusCode, Json<LoginResponse>)), AuthAPIError> {
-    let auth_cookie = generate_auth_cookie(&email).map_err(|_| AuthAPIError::UnexpectedError)?;
+    clear_failed_logins(state, user).await?;
+
+    let auth_cookie = generate_auth_cookie(user).map_err(|_| AuthAPIError::UnexpectedError)?;
+    let refresh_cookie = issue_refresh_token(state, &user.email, None).await?;
 
-    let updated_jar = jar.add(auth_cookie);
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
 
     Ok((
         updated_jar,
@@ -108,8 +291,6 @@ async fn handle_no_2fa(
     ))
 }
 
-use serde::Serialize;
-
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum LoginResponse {
@@ -1,12 +1,20 @@
+use super::is_token_banned;
 use crate::{
     AppState,
     api::{
         dtos::ErrorResponse,
-        utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+        utils::{
+            auth::validate_token,
+            constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+        },
     },
     domain::{
         error::AuthAPIError,
-        ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore},
+        models::RefreshToken,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            RefreshTokenStoreError, TwoFACodeStore, UserStore,
+        },
     },
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
@@ -18,43 +26,64 @@ use axum_extra::extract::{CookieJar, cookie::Cookie};
     description = "Logout user",
     tag = "auth",
     responses(
-        (status = 200, description = "Logout successful", headers(("x-set-cookie" = String, description = "jwt=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; HttpOnly; SameSite=Lax; Secure; Path=/")),),
-        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/json"),
-        (status = 401, description = "JWT is not valid", body = ErrorResponse, content_type = "application/json"),
-        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/json"),
+        (status = 200, description = "Logout successful", headers(("x-set-cookie" = String, description = "jwt=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; HttpOnly; SameSite=Lax; Secure; Path=/, refresh_token=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Path=/")),),
+        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn handle_logout<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    State(state): State<AppState<S, B, T, E>>,
+pub async fn handle_logout<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
     jar: CookieJar,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
     let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
 
     let token = cookie.value().to_owned();
 
+    let claims = validate_token(&token)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
     let mut banned_store = state.banned_store.write().await;
 
-    match banned_store.is_banned(&token).await {
-        Ok(is_banned) => {
-            if is_banned {
-                return Err(AuthAPIError::InvalidToken);
-            } else {
-                validate_token(&token)
-                    .await
-                    .map_err(|_| AuthAPIError::InvalidToken)?;
+    if is_token_banned(&*banned_store, &claims).await? {
+        return Err(AuthAPIError::InvalidToken);
+    }
+    banned_store
+        .add_token(&claims.jti, claims.valid_until())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    drop(banned_store);
 
-                banned_store
-                    .add_token(&token)
+    let mut jar = jar
+        .clone()
+        .remove(Cookie::new(JWT_COOKIE_NAME, cookie.value().to_owned()));
+
+    // Revoke the whole family, so no token rotated from this login can be used anymore.
+    if let Some(refresh_cookie) = jar.get(REFRESH_TOKEN_COOKIE_NAME) {
+        if let Ok(refresh_token) = RefreshToken::parse(refresh_cookie.value().to_owned()) {
+            let mut refresh_token_store = state.refresh_token_store.write().await;
+            match refresh_token_store.get_token(&refresh_token).await {
+                Ok(record) => refresh_token_store
+                    .revoke_family(&record.family_id)
                     .await
-                    .map_err(|_| AuthAPIError::UnexpectedError)?;
+                    .map_err(|_| AuthAPIError::UnexpectedError)?,
+                Err(RefreshTokenStoreError::UnexpectedError) => {
+                    return Err(AuthAPIError::UnexpectedError);
+                }
+                Err(_) => {}
             }
         }
-        Err(_) => return Err(AuthAPIError::UnexpectedError),
+        jar = jar.remove(Cookie::build(REFRESH_TOKEN_COOKIE_NAME).path("/"));
     }
 
-    let jar = jar
-        .clone()
-        .remove(Cookie::new(JWT_COOKIE_NAME, cookie.value().to_owned()));
-
     Ok((jar, StatusCode::OK.into_response()))
 }
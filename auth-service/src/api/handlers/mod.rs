@@ -1,13 +1,47 @@
+mod account;
+mod authorize;
+mod change_email;
+mod change_password;
+mod confirm_totp;
+mod enroll_totp;
+mod jwks;
 mod login;
 mod logout;
+mod openid_configuration;
+mod passkey_login;
+mod passkey_registration;
+mod password_reset;
+mod password_strength;
+mod recovery_codes;
+mod refresh;
 mod root;
 mod signup;
+mod token;
+mod userinfo;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
 
+pub use account::*;
+pub use authorize::*;
+pub use change_email::*;
+pub use change_password::*;
+pub use confirm_totp::*;
+pub use enroll_totp::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use openid_configuration::*;
+pub use passkey_login::*;
+pub use passkey_registration::*;
+pub use password_reset::*;
+pub use password_strength::*;
+pub use recovery_codes::*;
+pub use refresh::*;
 pub use root::*;
 pub use signup::*;
+pub use token::*;
+pub use userinfo::*;
 pub use verify_2fa::*;
+pub use verify_email::*;
 pub use verify_token::*;
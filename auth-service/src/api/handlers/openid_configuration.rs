@@ -0,0 +1,46 @@
+use axum::{Json, http::header, response::IntoResponse};
+
+use crate::api::{
+    dtos::OpenIdConfiguration,
+    utils::{auth::key_ring, constants::JWT_ISSUER},
+};
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/openid-configuration",
+    description = "OpenID Provider metadata. Endpoint URLs are built from the issuer, so `JWT_ISSUER` must be the public base URL of the service.",
+    tag = "oidc",
+    responses(
+        (status = 200, description = "OpenID Provider metadata", body = OpenIdConfiguration, content_type = "application/json"),
+    )
+)]
+pub async fn handle_openid_configuration() -> impl IntoResponse {
+    let issuer = JWT_ISSUER.trim_end_matches('/');
+    let strings = |values: &[&str]| values.iter().map(|&value| value.to_owned()).collect();
+    // The key signing tokens right now; it changes with the key ring.
+    let algorithm = format!("{:?}", key_ring().active().algorithm());
+
+    let configuration = OpenIdConfiguration {
+        issuer: JWT_ISSUER.clone(),
+        authorization_endpoint: format!("{issuer}/authorize"),
+        token_endpoint: format!("{issuer}/token"),
+        userinfo_endpoint: format!("{issuer}/userinfo"),
+        jwks_uri: format!("{issuer}/.well-known/jwks.json"),
+        scopes_supported: strings(&["openid", "email"]),
+        response_types_supported: strings(&["code"]),
+        grant_types_supported: strings(&["authorization_code"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: vec![algorithm],
+        token_endpoint_auth_methods_supported: strings(&["none", "client_secret_post"]),
+        code_challenge_methods_supported: strings(&["S256"]),
+        claims_supported: strings(&[
+            "sub", "iss", "aud", "exp", "iat", "nbf", "jti", "nonce", "roles", "email",
+        ]),
+    };
+
+    // Cached like the JWKS it points to.
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(configuration),
+    )
+}
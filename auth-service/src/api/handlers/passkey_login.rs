@@ -0,0 +1,184 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{TimeDelta, Utc};
+use sha2::{Digest, Sha256};
+
+use super::issue_refresh_token;
+use crate::{
+    AppState,
+    api::{
+        dtos::{ErrorResponse, PasskeyLoginOptions, PasskeyLoginRequest},
+        extractors::ValidatedJson,
+        utils::{
+            auth::generate_auth_cookie,
+            constants::{PASSKEY_CHALLENGE_TTL_SECONDS, WEBAUTHN_RP_ID},
+            webauthn::{AuthenticatorData, user_handle, verify_client_data},
+        },
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{PasskeyCeremony, PasskeyChallenge, PasskeyChallengeRecord},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, PasskeyStoreError,
+            RefreshTokenStore, TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/passkeys/login/start",
+    description = "Start logging in with a passkey. Returns the options to request an assertion with; the authenticator's response goes to `/passkeys/login/finish` within 5 minutes.",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Passkey request options", body = PasskeyLoginOptions, content_type = "application/json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_passkey_login_start<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let challenge = PasskeyChallenge::default();
+    let record = PasskeyChallengeRecord {
+        ceremony: PasskeyCeremony::Authentication,
+        email: None,
+        expires_at: Utc::now() + TimeDelta::seconds(PASSKEY_CHALLENGE_TTL_SECONDS),
+    };
+    state
+        .passkey_store
+        .write()
+        .await
+        .add_challenge(&challenge, record)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(Json(PasskeyLoginOptions {
+        challenge: challenge.as_ref().to_owned(),
+        timeout: PASSKEY_CHALLENGE_TTL_SECONDS as u64 * 1000,
+        rp_id: WEBAUTHN_RP_ID.clone(),
+        allow_credentials: Vec::new(),
+        user_verification: "required".to_owned(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/passkeys/login/finish",
+    description = "Finish logging in with the authenticator's response to the options of `/passkeys/login/start`. The passkey verifies the user, so no second factor is asked for.",
+    request_body = PasskeyLoginRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Login successful",
+            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Secure; Path=/, refresh_token=your_refresh_token; HttpOnly; SameSite=Strict; Path=/; Max-Age=1209600")),
+        ),
+        (status = 400, description = "Malformed response", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Unknown passkey, invalid signature, or the response does not answer a pending login", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_passkey_login_finish<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<PasskeyLoginRequest>,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let decode = |field: &str| {
+        URL_SAFE_NO_PAD
+            .decode(field)
+            .map_err(|_| AuthAPIError::InvalidCredentials)
+    };
+    let client_data_json = decode(&request.response.client_data_json)?;
+    let authenticator_data = decode(&request.response.authenticator_data)?;
+    let signature = decode(&request.response.signature)?;
+    let challenge = verify_client_data(&client_data_json, PasskeyCeremony::Authentication)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+    let mut passkey_store = state.passkey_store.write().await;
+    let record = match passkey_store.consume_challenge(&challenge).await {
+        Ok(record) => record,
+        Err(PasskeyStoreError::UnexpectedError) => return Err(AuthAPIError::UnexpectedError),
+        Err(_) => return Err(AuthAPIError::IncorrectCredentials),
+    };
+    if record.ceremony != PasskeyCeremony::Authentication {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let credential = match passkey_store.get_credential(&request.id).await {
+        Ok(credential) => credential,
+        Err(PasskeyStoreError::CredentialNotFound) => {
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+    if request
+        .response
+        .user_handle
+        .is_some_and(|handle| handle != user_handle(&credential.email))
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // The authenticator signs its data followed by the hash of the client data.
+    let parsed_data = AuthenticatorData::parse(&authenticator_data)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+    let mut signed = authenticator_data;
+    signed.extend_from_slice(&Sha256::digest(&client_data_json));
+    if !credential.public_key.verify(&signed, &signature) {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // A counter that does not increase betrays a cloned authenticator. Counters stuck at 0
+    // are those of authenticators that keep none.
+    if (parsed_data.sign_count != 0 || credential.sign_count != 0)
+        && !passkey_store
+            .update_sign_count(&credential.credential_id, parsed_data.sign_count)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+    drop(passkey_store);
+
+    let user = match state
+        .user_store
+        .read()
+        .await
+        .get_user(&credential.email)
+        .await
+    {
+        Ok(user) => user,
+        Err(_) => return Err(AuthAPIError::IncorrectCredentials),
+    };
+    if let Some(retry_after) = user.locked_for(Utc::now()) {
+        return Err(AuthAPIError::AccountLocked { retry_after });
+    }
+
+    let auth_cookie = generate_auth_cookie(&user).map_err(|_| AuthAPIError::UnexpectedError)?;
+    let refresh_cookie = issue_refresh_token(&state, &user.email, None).await?;
+
+    Ok((
+        jar.add(auth_cookie).add(refresh_cookie),
+        StatusCode::OK.into_response(),
+    ))
+}
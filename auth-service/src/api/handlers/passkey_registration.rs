@@ -0,0 +1,220 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{TimeDelta, Utc};
+
+use super::{authenticated_user, check_password};
+use crate::{
+    AppState,
+    api::{
+        dtos::{
+            ErrorResponse, PasskeyAuthenticatorSelection, PasskeyCredentialDescriptor,
+            PasskeyCredentialParameters, PasskeyRegistrationOptions, PasskeyRegistrationRequest,
+            PasskeyRegistrationStartRequest, PasskeyRelyingParty, PasskeyUser,
+        },
+        extractors::ValidatedJson,
+        utils::{
+            constants::{
+                JWT_COOKIE_NAME, PASSKEY_CHALLENGE_TTL_SECONDS, WEBAUTHN_RP_ID, WEBAUTHN_RP_NAME,
+            },
+            webauthn::{
+                AuthenticatorData, authenticator_data_from_attestation, user_handle,
+                verify_client_data,
+            },
+        },
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{
+            PASSKEY_ALGORITHMS, PasskeyCeremony, PasskeyChallenge, PasskeyChallengeRecord,
+            PasskeyCredential,
+        },
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, PasskeyStoreError,
+            RefreshTokenStore, TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/passkeys/register/start",
+    description = "Start registering a passkey for the logged-in user, who must hold the `jwt` cookie and give their password, a wrong one counting as a failed login. Returns the options to create the passkey with; the authenticator's response goes to `/passkeys/register/finish` within 5 minutes.",
+    request_body = PasskeyRegistrationStartRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Passkey creation options", body = PasskeyRegistrationOptions, content_type = "application/json"),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_passkey_register_start<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<PasskeyRegistrationStartRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+    // A passkey logs in without password nor second factor, so a hijacked session must not
+    // be able to add one.
+    check_password(&state, &user, &request.password).await?;
+
+    let mut passkey_store = state.passkey_store.write().await;
+    let exclude_credentials = passkey_store
+        .get_credentials(&user.email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .into_iter()
+        .map(|credential| PasskeyCredentialDescriptor {
+            credential_type: "public-key".to_owned(),
+            id: credential.credential_id,
+        })
+        .collect();
+
+    let challenge = PasskeyChallenge::default();
+    let record = PasskeyChallengeRecord {
+        ceremony: PasskeyCeremony::Registration,
+        email: Some(user.email.clone()),
+        expires_at: Utc::now() + TimeDelta::seconds(PASSKEY_CHALLENGE_TTL_SECONDS),
+    };
+    passkey_store
+        .add_challenge(&challenge, record)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(Json(PasskeyRegistrationOptions {
+        rp: PasskeyRelyingParty {
+            id: WEBAUTHN_RP_ID.clone(),
+            name: WEBAUTHN_RP_NAME.clone(),
+        },
+        user: PasskeyUser {
+            id: user_handle(&user.email),
+            name: user.email.as_ref().to_owned(),
+            display_name: user.email.as_ref().to_owned(),
+        },
+        challenge: challenge.as_ref().to_owned(),
+        pub_key_cred_params: PASSKEY_ALGORITHMS
+            .iter()
+            .map(|&alg| PasskeyCredentialParameters {
+                credential_type: "public-key".to_owned(),
+                alg,
+            })
+            .collect(),
+        timeout: PASSKEY_CHALLENGE_TTL_SECONDS as u64 * 1000,
+        exclude_credentials,
+        // Discoverable credentials let users log in without typing their email first.
+        authenticator_selection: PasskeyAuthenticatorSelection {
+            resident_key: "required".to_owned(),
+            require_resident_key: true,
+            user_verification: "required".to_owned(),
+        },
+        attestation: "none".to_owned(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/passkeys/register/finish",
+    description = "Finish registering a passkey with the authenticator's response to the options of `/passkeys/register/start`, which checked the user's password. The user can then log in through `/passkeys/login/start`, and is notified by email.",
+    request_body = PasskeyRegistrationRequest,
+    tag = "auth",
+    responses(
+        (status = 201, description = "Passkey registered"),
+        (status = 400, description = "Missing token or malformed response", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid, or the response does not answer a pending registration of this user", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Passkey already registered", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_passkey_register_finish<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<PasskeyRegistrationRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+
+    let decode = |field: &str| {
+        URL_SAFE_NO_PAD
+            .decode(field)
+            .map_err(|_| AuthAPIError::InvalidCredentials)
+    };
+    let client_data_json = decode(&request.response.client_data_json)?;
+    let attestation_object = decode(&request.response.attestation_object)?;
+    let challenge = verify_client_data(&client_data_json, PasskeyCeremony::Registration)
+        .map_err(|_| AuthAPIError::IncorrectCredentials)?;
+
+    let mut passkey_store = state.passkey_store.write().await;
+    let record = match passkey_store.consume_challenge(&challenge).await {
+        Ok(record) => record,
+        Err(PasskeyStoreError::UnexpectedError) => return Err(AuthAPIError::UnexpectedError),
+        Err(_) => return Err(AuthAPIError::IncorrectCredentials),
+    };
+    if record.ceremony != PasskeyCeremony::Registration
+        || record.email.as_ref() != Some(&user.email)
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let authenticator_data = authenticator_data_from_attestation(&attestation_object)
+        .and_then(|data| AuthenticatorData::parse(&data))
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let attested_credential = authenticator_data
+        .attested_credential
+        .filter(|credential| credential.credential_id == request.id)
+        .ok_or(AuthAPIError::InvalidCredentials)?;
+
+    let credential = PasskeyCredential {
+        credential_id: attested_credential.credential_id,
+        email: user.email.clone(),
+        public_key: attested_credential.public_key,
+        sign_count: authenticator_data.sign_count,
+    };
+    match passkey_store.add_credential(credential).await {
+        Ok(()) => {}
+        Err(PasskeyStoreError::CredentialAlreadyExists) => {
+            return Err(AuthAPIError::PasskeyAlreadyRegistered);
+        }
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+    drop(passkey_store);
+
+    // The passkey is registered even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &user.email,
+            "A passkey was added",
+            "A passkey was added to your account, and can now be used to log in. \
+             If you did not do this, reset your password at once and review your account.",
+        )
+        .await;
+
+    Ok(StatusCode::CREATED.into_response())
+}
@@ -0,0 +1,215 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, TimeDelta, Utc};
+use url::Url;
+
+use super::check_new_password;
+use crate::{
+    AppState,
+    api::{
+        dtos::{ConfirmPasswordResetRequest, ErrorResponse, PasswordResetRequest},
+        extractors::ValidatedJson,
+        utils::constants::{
+            PASSWORD_HASHING_POLICY, PASSWORD_RESET_TOKEN_TTL_SECONDS, PASSWORD_RESET_URL,
+            TOKEN_TTL_SECONDS, TOKEN_VALIDATION_LEEWAY_SECONDS,
+        },
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, PasswordHash, PasswordResetToken},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/password-reset/request",
+    description = "Email a password reset link to the user, valid once. The response is the same, and as fast, whether or not an email was sent, so that it does not tell which accounts exist.",
+    request_body = PasswordResetRequest,
+    tag = "auth",
+    responses(
+        (status = 202, description = "A link was emailed if the account exists"),
+        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Body larger than 64 KiB", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Too many requests",
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_request_password_reset<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    ValidatedJson(request): ValidatedJson<PasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let token = PasswordResetToken::default();
+    let expires_at = expiry(*PASSWORD_RESET_TOKEN_TTL_SECONDS)?;
+    let mut link = Url::parse(&PASSWORD_RESET_URL).map_err(|_| AuthAPIError::UnexpectedError)?;
+    link.query_pairs_mut().append_pair("token", token.as_ref());
+    let content = format!(
+        "Choose a new password by opening {link} before {}. \
+         If you did not ask for it, you can ignore this email: your password is unchanged.",
+        expires_at.format("%Y-%m-%d %H:%M UTC")
+    );
+
+    match state
+        .user_store
+        .write()
+        .await
+        .set_password_reset_token(&email, &token, expires_at)
+        .await
+    {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Ok(StatusCode::ACCEPTED),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+
+    // The email is sent in the background so that the response comes as fast as for an
+    // unknown account. A failed send is logged rather than reported, for the same reason.
+    let email_client = state.email_client.clone();
+    tokio::spawn(async move {
+        let result = email_client
+            .read()
+            .await
+            .send_email(&email, "Reset your password", &content)
+            .await;
+        if let Err(e) = result {
+            tracing::error!(error = ?e, "failed to send the password reset email");
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/password-reset/confirm",
+    description = "Set a new password with the token of a reset link, which is then used up. Every session of the user is revoked: their JWTs are banned and their refresh tokens dropped.",
+    request_body = ConfirmPasswordResetRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Password changed"),
+        (status = 400, description = "The password fails the password policy, which lists the rules it fails", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Token is not valid, expired or was already used", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_confirm_password_reset<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    ValidatedJson(request): ValidatedJson<ConfirmPasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = PasswordResetToken::parse(request.token).map_err(|_| AuthAPIError::InvalidToken)?;
+    let email = state
+        .user_store
+        .read()
+        .await
+        .get_password_reset_email(&token)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+        .ok_or(AuthAPIError::InvalidToken)?;
+
+    // An invalid password is refused before the token is used up, so the link still works.
+    let password = check_new_password(&request.new_password, Some(&email)).await?;
+    let password_hash = PasswordHash::compute(&password, &PASSWORD_HASHING_POLICY)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let mut user_store = state.user_store.write().await;
+    // The token may have been used or replaced since it was looked up: only the request
+    // that consumes it for the same user goes on.
+    let consumed = user_store
+        .consume_password_reset_token(&token)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    if consumed.as_ref() != Some(&email) {
+        return Err(AuthAPIError::InvalidToken);
+    }
+    match user_store.update_password(&email, password_hash).await {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    }
+    drop(user_store);
+
+    revoke_sessions(&state, &email).await?;
+
+    // The password is changed even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &email,
+            "Your password was changed",
+            "Your password was reset and every session was logged out. \
+             If you did not do this, reset it again and review your account.",
+        )
+        .await;
+
+    Ok(StatusCode::OK)
+}
+
+// Logs the user out everywhere: every JWT issued to them so far is banned until the last
+// one expires, and their refresh tokens are dropped. Tokens carry their issue time in
+// whole seconds, so those issued in the same second as the ban are refused as well.
+pub(crate) async fn revoke_sessions<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    email: &Email,
+) -> Result<(), AuthAPIError> {
+    let now = Utc::now();
+    let expires_at = expiry(TOKEN_TTL_SECONDS as u64 + TOKEN_VALIDATION_LEEWAY_SECONDS)?;
+    state
+        .banned_store
+        .write()
+        .await
+        .ban_subject(email.as_ref(), now, expires_at)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user(email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+// The instant `ttl_seconds` from now.
+pub(crate) fn expiry(ttl_seconds: u64) -> Result<DateTime<Utc>, AuthAPIError> {
+    i64::try_from(ttl_seconds)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+        .ok_or(AuthAPIError::UnexpectedError)
+}
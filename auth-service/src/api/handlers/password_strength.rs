@@ -0,0 +1,56 @@
+use axum::{Json, http::header::CACHE_CONTROL, response::IntoResponse};
+
+use super::password_violations;
+use crate::{
+    api::{
+        dtos::{
+            ErrorResponse, PasswordStrengthRequest, PasswordStrengthResponse, PasswordViolation,
+        },
+        extractors::ValidatedJson,
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, PasswordPolicy},
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/password-strength",
+    description = "Estimate how hard a password is to guess, for feedback while the user types it. The estimate is the one the password policy rejects weak passwords with, and the response lists the policy rules the password fails. Give the user's email, when known, so that passwords built from it are scored as weak.",
+    request_body = PasswordStrengthRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "Password strength", body = PasswordStrengthResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid email address", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_password_strength(
+    ValidatedJson(request): ValidatedJson<PasswordStrengthRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = request
+        .email
+        .as_deref()
+        .map(Email::parse)
+        .transpose()
+        .map_err(|_| AuthAPIError::InvalidEmail)?;
+
+    let strength = PasswordPolicy::strength(&request.password, email.as_ref());
+    let violations = password_violations(&request.password, email.as_ref()).await?;
+
+    let response = Json(PasswordStrengthResponse {
+        score: strength.score,
+        guesses_log10: strength.guesses_log10,
+        crack_time_seconds: strength.crack_time_seconds(),
+        crack_time_display: strength.crack_time_display(),
+        warning: strength.warning.map(str::to_owned),
+        suggestions: strength.suggestions.iter().map(|s| s.to_string()).collect(),
+        violations: violations.iter().map(PasswordViolation::from).collect(),
+    });
+
+    // The answer is about a password, which must not linger in caches.
+    Ok(([(CACHE_CONTROL, "no-store")], response))
+}
@@ -0,0 +1,95 @@
+use axum::{Json, extract::State, http::header::CACHE_CONTROL, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+
+use super::{authenticated_user, check_password};
+use crate::{
+    AppState,
+    api::{
+        dtos::{ErrorResponse, RecoveryCodesResponse, RegenerateRecoveryCodesRequest},
+        extractors::ValidatedJson,
+        utils::constants::{JWT_COOKIE_NAME, RECOVERY_CODE_COUNT},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, RecoveryCode},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/recovery-codes",
+    description = "Replace the logged-in user's recovery codes with a new set, e.g. once most of them are used up. The user must hold the `jwt` cookie and give their password, a wrong one counting as a failed login. The previous codes stop working, the new ones are shown this once, and the user is notified by email.",
+    request_body = RegenerateRecoveryCodesRequest,
+    tag = "auth",
+    responses(
+        (status = 200, description = "New recovery codes", body = RecoveryCodesResponse, content_type = "application/json"),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid or the password is incorrect", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_regenerate_recovery_codes<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+    ValidatedJson(request): ValidatedJson<RegenerateRecoveryCodesRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let cookie = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+    let user = authenticated_user(&state, cookie.value()).await?;
+    check_password(&state, &user, &request.password).await?;
+
+    let recovery_codes =
+        issue_recovery_codes(&mut *state.user_store.write().await, &user.email).await?;
+
+    // The codes are replaced even if the notice is not delivered.
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            &user.email,
+            "Your recovery codes were replaced",
+            "New recovery codes were generated for your account, and the previous ones no \
+             longer work. If you did not do this, reset your password at once and review your \
+             account.",
+        )
+        .await;
+
+    // The codes are as good as the second factor, so keep them out of caches.
+    Ok((
+        [(CACHE_CONTROL, "no-store")],
+        Json(RecoveryCodesResponse { recovery_codes }),
+    ))
+}
+
+// Replaces the user's recovery codes with a new set and returns them in the clear, which
+// is the only time they are: the store keeps their hashes.
+pub(crate) async fn issue_recovery_codes<S: UserStore>(
+    user_store: &mut S,
+    email: &Email,
+) -> Result<Vec<String>, AuthAPIError> {
+    let codes = (0..RECOVERY_CODE_COUNT)
+        .map(|_| RecoveryCode::default())
+        .collect::<Vec<_>>();
+    user_store
+        .set_recovery_codes(email, &codes)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    Ok(codes.iter().map(|code| code.as_ref().to_owned()).collect())
+}
@@ -0,0 +1,136 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::{CookieJar, cookie::Cookie};
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    AppState,
+    api::{
+        dtos::ErrorResponse,
+        utils::{
+            auth::{create_refresh_cookie, generate_auth_cookie},
+            constants::{REFRESH_TOKEN_COOKIE_NAME, REFRESH_TOKEN_TTL_SECONDS},
+        },
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, RefreshToken, RefreshTokenRecord},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            RefreshTokenStoreError, TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    description = "Rotate the refresh token and issue a new JWT",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Tokens rotated",
+            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Path=/, refresh_token=your_refresh_token; HttpOnly; SameSite=Strict; Path=/; Max-Age=1209600")),
+        ),
+        (status = 400, description = "Missing refresh token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Refresh token is not valid, expired or was already used", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 403, description = "Email not verified", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_refresh<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    jar: CookieJar,
+) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
+    let cookie = jar
+        .get(REFRESH_TOKEN_COOKIE_NAME)
+        .ok_or(AuthAPIError::MissingToken)?;
+    let token =
+        RefreshToken::parse(cookie.value().to_owned()).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+    let record = match refresh_token_store.use_token(&token).await {
+        Ok(record) => record,
+        // A rotated token coming back means it leaked: cut off every token of the login.
+        Err(RefreshTokenStoreError::TokenReused { family_id }) => {
+            refresh_token_store
+                .revoke_family(&family_id)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+            return Err(AuthAPIError::InvalidToken);
+        }
+        Err(RefreshTokenStoreError::UnexpectedError) => return Err(AuthAPIError::UnexpectedError),
+        Err(_) => return Err(AuthAPIError::InvalidToken),
+    };
+    drop(refresh_token_store);
+
+    // The account may have been removed since the token was issued.
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&record.email)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    // Held to the same terms as a login, so that a lock cuts off sessions already open.
+    if let Some(retry_after) = user.locked_for(Utc::now()) {
+        return Err(AuthAPIError::AccountLocked { retry_after });
+    }
+    if !user.email_verified {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
+    let refresh_cookie = issue_refresh_token(&state, &record.email, Some(record.family_id)).await?;
+    let auth_cookie = generate_auth_cookie(&user).map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
+
+    Ok((updated_jar, StatusCode::OK.into_response()))
+}
+
+// Stores a new refresh token and returns the cookie carrying it. Without a family,
+// the token starts a new one, as it does on login.
+pub(crate) async fn issue_refresh_token<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    email: &Email,
+    family_id: Option<String>,
+) -> Result<Cookie<'static>, AuthAPIError> {
+    let ttl = i64::try_from(*REFRESH_TOKEN_TTL_SECONDS)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .ok_or(AuthAPIError::UnexpectedError)?;
+    let record = RefreshTokenRecord {
+        email: email.clone(),
+        family_id: family_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        expires_at: Utc::now() + ttl,
+    };
+
+    let token = RefreshToken::default();
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .add_token(&token, record)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    Ok(create_refresh_cookie(&token))
+}
@@ -1,52 +1,131 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    extract::State,
+    http::{StatusCode, header::CACHE_CONTROL},
+    response::IntoResponse,
+};
 
+use super::{issue_recovery_codes, send_verification_email};
 use crate::{
     AppState,
-    api::dtos::{ErrorResponse, SignUpRequest, SignUpResponse},
+    api::{
+        dtos::{ErrorResponse, SignUpRequest, SignUpResponse},
+        extractors::ValidatedJson,
+        utils::constants::{BREACHED_PASSWORDS, PASSWORD_HASHING_POLICY, PASSWORD_POLICY},
+    },
     domain::{
         error::AuthAPIError,
-        models::{Email, Password, User},
-        ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore},
+        models::{Email, Password, PasswordHash, PasswordRule, TwoFAMethod, User},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
     },
 };
 
 #[utoipa::path(
     post,
     path = "/signup",
-    description = "Register a new user",
+    description = "Register a new user, who must verify their email address through the emailed link before logging in. A user who signs up with 2FA is handed recovery codes, each usable once at `/verify-2fa` in place of the emailed code.",
     request_body = SignUpRequest,
     tag = "auth",
     responses(
         (status = 201, description = "User created successfully", body = SignUpResponse, content_type = "application/json"),
-        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/json"),
-        (status = 409, description = "Email already exists", body = ErrorResponse, content_type = "application/json"),
-        (status = 422, description = "Unprocessable content", body = ErrorResponse, content_type = "application/json"),
-        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid input, or a password that fails the password policy, which lists the rules it fails", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 409, description = "Email already exists", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Body larger than 64 KiB", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Too many requests",
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn handle_signup<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    State(state): State<AppState<S, B, T, E>>,
-    Json(request): Json<SignUpRequest>,
+pub async fn handle_signup<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    ValidatedJson(request): ValidatedJson<SignUpRequest>,
 ) -> Result<impl IntoResponse, AuthAPIError> {
     let email = Email::parse(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
-    let password =
-        Password::parse(&request.password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let password = check_new_password(&request.password, Some(&email)).await?;
+
+    if state.user_store.read().await.get_user(&email).await.is_ok() {
+        return Err(AuthAPIError::UserAlreadyExists);
+    }
+
+    let password_hash = PasswordHash::compute(&password, &PASSWORD_HASHING_POLICY)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
 
-    let user = User::new(email, password, request.requires_2fa);
+    // Authenticator apps are enrolled later, through /enroll-totp.
+    let two_fa_method = match request.requires_2fa {
+        true => TwoFAMethod::Email,
+        false => TwoFAMethod::None,
+    };
+    let user = User::new(email, password_hash, two_fa_method);
 
     let mut user_store = state.user_store.write().await;
 
-    if user_store.get_user(&user.email).await.is_ok() {
-        return Err(AuthAPIError::UserAlreadyExists);
+    match user_store.add_user(&user).await {
+        Ok(()) => {}
+        Err(UserStoreError::UserAlreadyExists) => return Err(AuthAPIError::UserAlreadyExists),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
     }
 
-    if user_store.add_user(&user).await.is_err() {
-        return Err(AuthAPIError::UnexpectedError);
-    }
+    let recovery_codes = match user.two_fa_method {
+        TwoFAMethod::None => None,
+        _ => Some(issue_recovery_codes(&mut *user_store, &user.email).await?),
+    };
+    drop(user_store);
+
+    // The account exists even if the email is not delivered: another can be asked for.
+    let _ = send_verification_email(&*state.email_client.read().await, &user.email).await;
 
     let response = Json(SignUpResponse {
         message: "User created successfully!".to_string(),
+        recovery_codes,
     });
 
-    Ok((StatusCode::CREATED, response))
+    Ok((StatusCode::CREATED, [(CACHE_CONTROL, "no-store")], response))
+}
+
+// Checks a password the user chooses against the password policy and, when a breached
+// password list is configured, against known breaches. Every failed rule is reported.
+pub(crate) async fn check_new_password(
+    password: &str,
+    email: Option<&Email>,
+) -> Result<Password, AuthAPIError> {
+    let violations = password_violations(password, email).await?;
+    if !violations.is_empty() {
+        return Err(AuthAPIError::InvalidPassword(violations));
+    }
+    Password::parse(password, &PASSWORD_POLICY)
+}
+
+// Lists the rules a password the user chooses fails, known breaches included.
+pub(crate) async fn password_violations(
+    password: &str,
+    email: Option<&Email>,
+) -> Result<Vec<PasswordRule>, AuthAPIError> {
+    let mut violations = PASSWORD_POLICY.violations(password, email);
+    if let Some(breached_passwords) = BREACHED_PASSWORDS.as_ref() {
+        let breached_passwords = breached_passwords.clone();
+        let candidate = password.to_owned();
+        let breached = tokio::task::spawn_blocking(move || breached_passwords.contains(&candidate))
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+        if breached {
+            violations.push(PasswordRule::Breached);
+        }
+    }
+    Ok(violations)
 }
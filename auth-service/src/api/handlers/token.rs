@@ -0,0 +1,124 @@
+use axum::{
+    Form, Json,
+    extract::State,
+    http::header::{CACHE_CONTROL, PRAGMA},
+    response::IntoResponse,
+};
+
+use crate::{
+    AppState,
+    api::{
+        dtos::{OAuthErrorResponse, TokenRequest, TokenResponse},
+        utils::{
+            auth::{generate_id_token, generate_oidc_access_token},
+            constants::TOKEN_TTL_SECONDS,
+        },
+    },
+    domain::{
+        error::OAuthError,
+        models::AuthorizationCode,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, OAuthClientStoreError, PasskeyStore,
+            RefreshTokenStore, TwoFACodeStore, UserStore, UserStoreError,
+        },
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/token",
+    description = "Redeem an authorization code for an access token and an ID token. Public clients prove they started the flow with the PKCE code verifier; confidential clients also send their `client_secret`.",
+    request_body(content = TokenRequest, content_type = "application/x-www-form-urlencoded"),
+    tag = "oidc",
+    responses(
+        (status = 200, description = "Tokens issued", body = TokenResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid request, grant or grant type", body = OAuthErrorResponse, content_type = "application/json"),
+        (status = 401, description = "Client authentication failed", body = OAuthErrorResponse, content_type = "application/json"),
+        (status = 500, description = "Unexpected error", body = OAuthErrorResponse, content_type = "application/json"),
+    )
+)]
+pub async fn handle_token<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    Form(request): Form<TokenRequest>,
+) -> Result<impl IntoResponse, OAuthError> {
+    match request.grant_type.as_deref() {
+        Some("authorization_code") => {}
+        Some(_) => return Err(OAuthError::UnsupportedGrantType),
+        None => return Err(OAuthError::InvalidRequest),
+    }
+    let (Some(client_id), Some(code), Some(redirect_uri), Some(code_verifier)) = (
+        request.client_id.as_deref(),
+        request.code,
+        request.redirect_uri.as_deref(),
+        request.code_verifier.as_deref(),
+    ) else {
+        return Err(OAuthError::InvalidRequest);
+    };
+
+    let client = match state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => return Err(OAuthError::InvalidClient),
+        Err(_) => return Err(OAuthError::ServerError),
+    };
+    if !client.authenticate(request.client_secret.as_deref()) {
+        return Err(OAuthError::InvalidClient);
+    }
+
+    let code = AuthorizationCode::parse(code).map_err(|_| OAuthError::InvalidGrant)?;
+    // The code is gone once presented, even if the rest of the request turns out wrong.
+    let record = match state
+        .oauth_client_store
+        .write()
+        .await
+        .consume_authorization_code(&code)
+        .await
+    {
+        Ok(record) => record,
+        Err(OAuthClientStoreError::UnexpectedError) => return Err(OAuthError::ServerError),
+        Err(_) => return Err(OAuthError::InvalidGrant),
+    };
+    if record.client_id != client.client_id
+        || record.redirect_uri != redirect_uri
+        || !record.code_challenge.verify(code_verifier)
+    {
+        return Err(OAuthError::InvalidGrant);
+    }
+
+    // The account may have been removed since the code was issued.
+    let user = match state.user_store.read().await.get_user(&record.email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(OAuthError::InvalidGrant),
+        Err(_) => return Err(OAuthError::ServerError),
+    };
+
+    let access_token =
+        generate_oidc_access_token(&user, &record.scope).map_err(|_| OAuthError::ServerError)?;
+    let id_token = generate_id_token(&user, &client.client_id, record.nonce)
+        .map_err(|_| OAuthError::ServerError)?;
+
+    // Responses carrying tokens must not be cached (RFC 6749, section 5.1).
+    Ok((
+        [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")],
+        Json(TokenResponse {
+            access_token,
+            token_type: "Bearer".to_owned(),
+            expires_in: TOKEN_TTL_SECONDS,
+            id_token,
+            scope: record.scope,
+        }),
+    ))
+}
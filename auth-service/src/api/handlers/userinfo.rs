@@ -0,0 +1,70 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, header::AUTHORIZATION},
+    response::IntoResponse,
+};
+
+use super::claimed_user;
+use crate::{
+    AppState,
+    api::{
+        dtos::{OAuthErrorResponse, UserInfoResponse},
+        utils::auth::validate_oidc_access_token,
+    },
+    domain::{
+        error::{AuthAPIError, OAuthError},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore,
+        },
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/userinfo",
+    description = "Return the claims about the user an access token was issued to, limited to the scopes it grants. The token is sent as `Authorization: Bearer <access_token>`.",
+    tag = "oidc",
+    responses(
+        (status = 200, description = "Claims about the user", body = UserInfoResponse, content_type = "application/json"),
+        (status = 401, description = "Access token is missing, invalid, expired or revoked", body = OAuthErrorResponse, content_type = "application/json"),
+        (status = 500, description = "Unexpected error", body = OAuthErrorResponse, content_type = "application/json"),
+    )
+)]
+pub async fn handle_userinfo<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, OAuthError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(OAuthError::InvalidToken)?;
+
+    let claims = validate_oidc_access_token(token).map_err(|_| OAuthError::InvalidToken)?;
+    let user = claimed_user(&state, &claims)
+        .await
+        .map_err(|error| match error {
+            AuthAPIError::InvalidToken => OAuthError::InvalidToken,
+            _ => OAuthError::ServerError,
+        })?;
+
+    // Only the claims the user consented to are released to the client.
+    let email_granted = claims
+        .scope
+        .as_deref()
+        .is_some_and(|scope| scope.split_whitespace().any(|scope| scope == "email"));
+    Ok(Json(UserInfoResponse {
+        sub: user.email.as_ref().to_owned(),
+        email: email_granted.then(|| user.email.as_ref().to_owned()),
+    }))
+}
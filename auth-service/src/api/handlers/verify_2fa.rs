@@ -1,57 +1,254 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use axum_extra::extract::CookieJar;
+use chrono::Utc;
 
-use crate::{AppState, api::{dtos::{ErrorResponse, Verify2faRequest}, utils::auth::generate_auth_cookie}, domain::{error::AuthAPIError, models::{Email, LoginAttemptId, TwoFACode}, ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore}}};
+use super::{clear_failed_logins, handle_failed_login, issue_refresh_token};
+use crate::{
+    AppState,
+    api::{
+        dtos::{ErrorResponse, Verify2faRequest},
+        extractors::ValidatedJson,
+        utils::{auth::generate_auth_cookie, constants::TOTP_SKEW_STEPS},
+    },
+    domain::{
+        error::AuthAPIError,
+        models::{Email, LoginAttemptId, RecoveryCode, TwoFACode, User},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, TwoFACodeStoreError, UserStore, UserStoreError,
+        },
+    },
+};
 
 #[utoipa::path(
     post,
     path = "/verify-2fa",
-    description = "Verify 2FA token",
+    description = "Verify 2FA token: the code emailed at login, or the code shown by the user's authenticator app if one is enrolled. A recovery code is accepted instead, once; the user is notified by email when one is used.",
     request_body = Verify2faRequest,
     tag = "auth",
     responses(
         (status = 200, description = "Login successful", 
-            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Secure; Path=/")),
+            headers(("x-set-cookie" = String, description = "jwt=your_token; HttpOnly; SameSite=Lax; Secure; Path=/, refresh_token=your_refresh_token; HttpOnly; SameSite=Strict; Path=/; Max-Age=1209600")),
         ),
-        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/json"),
-        (status = 401, description = "Authentication failed", body = ErrorResponse, content_type = "application/json"),
-        (status = 422, description = "Unprocessable content", body = ErrorResponse, content_type = "application/json"),
-        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Authentication failed or 2FA code expired", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 423, description = "Account temporarily locked after too many failed logins, wrong 2FA codes included",
+            headers(("Retry-After" = u64, description = "Seconds until the account unlocks")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Too many failed attempts, the 2FA code was invalidated", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn handle_verify_2fa<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    State(state): State<AppState<S, B, T, E>>,
+pub async fn handle_verify_2fa<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
     jar: CookieJar,
-    Json(request): Json<Verify2faRequest>,
+    ValidatedJson(request): ValidatedJson<Verify2faRequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
-    let email = 
-        Email::parse(&request.email)
-            .map_err(|_| AuthAPIError::InvalidCredentials)?;
-    let login_attempt_id = 
-        LoginAttemptId::parse(request.login_attempt_id)
-            .map_err(|_| AuthAPIError::InvalidCredentials)?;
-    let two_fa_code = 
-        TwoFACode::parse(request._2fa_code)
-            .map_err(|_| AuthAPIError::InvalidCredentials)?;
-
-    let two_fa_code_store = state.two_fa_store.write().await;
-    // Call `two_fa_code_store.get_code`. If the call fails
-    // return a `AuthAPIError::IncorrectCredentials`.
-    let code = two_fa_code_store.get_code(&email)
-        .await.map_err(|_| AuthAPIError::IncorrectCredentials)?;
-    
-    let code_tuple = (login_attempt_id, two_fa_code); 
-    // TODO: Validate that the `login_attempt_id` and `two_fa_code`
-    // in the request body matches values in the `code_tuple`. 
-    // If not, return a `AuthAPIError::IncorrectCredentials`.
-    if code != code_tuple {
-        return Err(AuthAPIError::IncorrectCredentials);
+    let email = Email::parse(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let login_attempt_id = LoginAttemptId::parse(request.login_attempt_id)
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let second_factor = match RecoveryCode::parse(&request._2fa_code) {
+        Ok(recovery_code) => SecondFactor::RecoveryCode(recovery_code),
+        Err(_) => SecondFactor::Code(
+            TwoFACode::parse(request._2fa_code).map_err(|_| AuthAPIError::InvalidCredentials)?,
+        ),
+    };
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::IncorrectCredentials),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    // A locked account is refused before the code is checked, so guessing gains nothing.
+    if let Some(retry_after) = user.locked_for(Utc::now()) {
+        return Err(AuthAPIError::AccountLocked { retry_after });
     }
 
-    let auth_cookie = generate_auth_cookie(&email).map_err(|_| AuthAPIError::UnexpectedError)?;
+    // Codes are only tried for the pending login attempt, so that guesses without the
+    // password can neither use them up nor get around the attempt limit.
+    match state.two_fa_store.read().await.get_code(&email).await {
+        Ok((pending_id, _)) if pending_id == login_attempt_id => {}
+        Ok(_) => return Err(AuthAPIError::IncorrectCredentials),
+        Err(e) => return Err(two_fa_error(e)),
+    }
+
+    let verified = match second_factor {
+        SecondFactor::Code(two_fa_code) => {
+            verify_code(&state, &user, &login_attempt_id, &two_fa_code).await
+        }
+        SecondFactor::RecoveryCode(recovery_code) => {
+            use_recovery_code(&state, &email, &login_attempt_id, &recovery_code).await
+        }
+    };
+    // A wrong code counts as a failed login as well. The attempt limit alone is reset by
+    // every login, which would give whoever holds the password endless guesses.
+    if let Err(error) = verified {
+        if !matches!(
+            error,
+            AuthAPIError::IncorrectCredentials | AuthAPIError::TooManyTwoFAAttempts
+        ) {
+            return Err(error);
+        }
+        return Err(match handle_failed_login(&email, &state).await {
+            AuthAPIError::IncorrectCredentials => error,
+            failure => failure,
+        });
+    }
+    clear_failed_logins(&state, &user).await?;
 
-    let updated_jar = jar.add(auth_cookie);
+    let auth_cookie = generate_auth_cookie(&user).map_err(|_| AuthAPIError::UnexpectedError)?;
 
+    let refresh_cookie = issue_refresh_token(&state, &email, None).await?;
+
+    let updated_jar = jar.add(auth_cookie).add(refresh_cookie);
 
     Ok((updated_jar, StatusCode::OK.into_response()))
 }
+
+// What the user proved their second factor with.
+enum SecondFactor {
+    Code(TwoFACode),
+    /// Stands in for a second factor the user lost access to.
+    RecoveryCode(RecoveryCode),
+}
+
+// Settles the pending login attempt with a 2FA code.
+async fn verify_code<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    user: &User,
+    login_attempt_id: &LoginAttemptId,
+    two_fa_code: &TwoFACode,
+) -> Result<(), AuthAPIError> {
+    // Consuming the code removes it, so it cannot be replayed. TOTP codes are checked
+    // against the user's secret instead of the pending code, which was never sent.
+    let totp_step = user
+        .two_fa_method
+        .totp_secret()
+        .map(|secret| secret.verify(two_fa_code, Utc::now(), *TOTP_SKEW_STEPS));
+    let mut two_fa_store = state.two_fa_store.write().await;
+    let consumed = match totp_step {
+        Some(step) => {
+            two_fa_store
+                .consume_attempt(&user.email, login_attempt_id, step.is_some())
+                .await
+        }
+        None => {
+            two_fa_store
+                .consume_code(&user.email, login_attempt_id, two_fa_code)
+                .await
+        }
+    };
+    drop(two_fa_store);
+    consumed.map_err(two_fa_error)?;
+
+    // A TOTP code stays valid for its whole time step, so it is burned once used.
+    if let Some(Some(step)) = totp_step
+        && !state
+            .user_store
+            .write()
+            .await
+            .record_totp_use(&user.email, step)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    Ok(())
+}
+
+// Settles the pending login attempt with a recovery code, which is used up, and lets the
+// user know by email.
+async fn use_recovery_code<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    email: &Email,
+    login_attempt_id: &LoginAttemptId,
+    recovery_code: &RecoveryCode,
+) -> Result<(), AuthAPIError> {
+    // The attempt is settled before the code is used up, so that an attempt which expired
+    // or was settled meanwhile does not cost the user one of their codes.
+    let valid = state
+        .user_store
+        .read()
+        .await
+        .has_recovery_code(email, recovery_code)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    state
+        .two_fa_store
+        .write()
+        .await
+        .consume_attempt(email, login_attempt_id, valid)
+        .await
+        .map_err(two_fa_error)?;
+    // Only one login attempt is pending at a time, so losing the code now means it was
+    // replaced or used up in between.
+    if !state
+        .user_store
+        .write()
+        .await
+        .consume_recovery_code(email, recovery_code)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // The login goes through even if the notification cannot be delivered.
+    let remaining = state
+        .user_store
+        .read()
+        .await
+        .count_recovery_codes(email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    let content = format!(
+        "A recovery code was used to log in to your account. You have {remaining} recovery \
+         codes left; new ones can be generated once logged in. If this was not you, change \
+         your password."
+    );
+    let _ = state
+        .email_client
+        .read()
+        .await
+        .send_email(email, "Recovery code used", &content)
+        .await;
+
+    Ok(())
+}
+
+fn two_fa_error(error: TwoFACodeStoreError) -> AuthAPIError {
+    match error {
+        TwoFACodeStoreError::Expired => AuthAPIError::TwoFACodeExpired,
+        TwoFACodeStoreError::TooManyAttempts => AuthAPIError::TooManyTwoFAAttempts,
+        TwoFACodeStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        _ => AuthAPIError::IncorrectCredentials,
+    }
+}
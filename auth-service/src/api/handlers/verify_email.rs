@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{TimeDelta, Utc};
+use url::Url;
+
+use crate::{
+    AppState,
+    api::{
+        dtos::{ErrorResponse, ResendVerificationEmailRequest, VerifyEmailRequest},
+        extractors::ValidatedJson,
+        utils::{
+            auth::{generate_email_verification_token, validate_email_verification_token},
+            constants::{EMAIL_VERIFICATION_TTL_SECONDS, EMAIL_VERIFICATION_URL},
+        },
+    },
+    domain::{
+        error::AuthAPIError,
+        models::Email,
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/verify-email",
+    description = "Verify the user's email address with the link emailed at signup, which activates the account: logins are refused until then. Following a link again is harmless.",
+    params(VerifyEmailRequest),
+    tag = "auth",
+    responses(
+        (status = 200, description = "Email verified"),
+        (status = 400, description = "Missing token", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "Token is not valid or expired", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_verify_email<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    Query(request): Query<VerifyEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = validate_email_verification_token(&request.token)
+        .ok()
+        .and_then(|sub| Email::parse(&sub).ok())
+        .ok_or(AuthAPIError::InvalidToken)?;
+
+    match state
+        .user_store
+        .write()
+        .await
+        .mark_email_verified(&email)
+        .await
+    {
+        Ok(()) => Ok(StatusCode::OK),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(_) => Err(AuthAPIError::UnexpectedError),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/resend-verification-email",
+    description = "Email a new verification link to a user who has yet to verify their address. The response is the same whether or not an email was sent, so that it does not tell which accounts exist.",
+    request_body = ResendVerificationEmailRequest,
+    tag = "auth",
+    responses(
+        (status = 202, description = "A link was emailed if the account awaits verification"),
+        (status = 400, description = "Invalid input", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 413, description = "Body larger than 64 KiB", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 429, description = "Too many requests",
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying")),
+            body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
+    )
+)]
+pub async fn handle_resend_verification_email<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    ValidatedJson(request): ValidatedJson<ResendVerificationEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(&request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let awaits_verification = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => !user.email_verified,
+        Err(UserStoreError::UserNotFound) => false,
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+    if awaits_verification {
+        send_verification_email(&*state.email_client.read().await, &email).await?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// Emails the user a link to `EMAIL_VERIFICATION_URL` carrying a signed token for their address.
+pub(crate) async fn send_verification_email<E: EmailClient>(
+    email_client: &E,
+    email: &Email,
+) -> Result<(), AuthAPIError> {
+    let token =
+        generate_email_verification_token(email).map_err(|_| AuthAPIError::UnexpectedError)?;
+    let mut link =
+        Url::parse(&EMAIL_VERIFICATION_URL).map_err(|_| AuthAPIError::UnexpectedError)?;
+    link.query_pairs_mut().append_pair("token", &token);
+    let expires_at = i64::try_from(*EMAIL_VERIFICATION_TTL_SECONDS)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+        .ok_or(AuthAPIError::UnexpectedError)?;
+
+    let content = format!(
+        "Confirm your email address by opening {link} before {}. \
+         If you did not sign up, you can ignore this email.",
+        expires_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    email_client
+        .send_email(email, "Verify your email", &content)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
@@ -1,14 +1,19 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 
 use crate::{
     AppState,
     api::{
         dtos::{ErrorResponse, VerifyTokenRequest},
-        utils::auth::validate_token,
+        extractors::ValidatedJson,
+        utils::auth::{Claims, validate_token},
     },
     domain::{
         error::AuthAPIError,
-        ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore},
+        models::{Email, User},
+        ports::{
+            BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RefreshTokenStore,
+            TwoFACodeStore, UserStore, UserStoreError,
+        },
     },
 };
 
@@ -20,25 +25,100 @@ use crate::{
     tag = "auth",
     responses(
         (status = 200, description = "Token is valid"),
-        (status = 401, description = "JWT is not valid", body = ErrorResponse, content_type = "application/json"),
-        (status = 422, description = "Unprocessable content", body = ErrorResponse, content_type = "application/json"),
-        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/json"),
+        (status = 400, description = "Body not well-formed JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 401, description = "JWT is not valid", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 415, description = "Body not declared as JSON", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 422, description = "Body missing a field or holding one of the wrong type, as listed in `errors`", body = ErrorResponse, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ErrorResponse, content_type = "application/problem+json"),
     )
 )]
-pub async fn handle_verify_token<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    State(state): State<AppState<S, B, T, E>>,
-    Json(request): Json<VerifyTokenRequest>,
+pub async fn handle_verify_token<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    State(state): State<AppState<S, B, T, E, R, O, P>>,
+    ValidatedJson(request): ValidatedJson<VerifyTokenRequest>,
 ) -> Result<impl IntoResponse, AuthAPIError> {
     let token = request.token.to_owned();
 
-    let banned_store = state.banned_store.read().await;
-    if banned_store.is_banned(&token).await.unwrap() {
+    let claims = validate_token(&token)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    if is_token_banned(&*state.banned_store.read().await, &claims).await? {
         return Err(AuthAPIError::InvalidToken);
     }
 
-    validate_token(&token)
+    Ok(StatusCode::OK.into_response())
+}
+
+// Returns the user a valid, unbanned session token was issued to. The access tokens of
+// OpenID Connect clients are refused: they are only good at /userinfo.
+pub(crate) async fn authenticated_user<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    token: &str,
+) -> Result<User, AuthAPIError> {
+    let claims = validate_token(token)
         .await
         .map_err(|_| AuthAPIError::InvalidToken)?;
+    claimed_user(state, &claims).await
+}
 
-    Ok(StatusCode::OK.into_response())
+// Returns the user an unbanned token was issued to, once its claims were validated.
+// Fails with `InvalidToken` as well when the account no longer exists.
+pub(crate) async fn claimed_user<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    state: &AppState<S, B, T, E, R, O, P>,
+    claims: &Claims,
+) -> Result<User, AuthAPIError> {
+    if is_token_banned(&*state.banned_store.read().await, claims).await? {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    let email = Email::parse(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+    match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => Ok(user),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(_) => Err(AuthAPIError::UnexpectedError),
+    }
+}
+
+// Checks whether a token was banned on its own, on logout, or along with every token
+// issued to its subject, on a password reset.
+pub(crate) async fn is_token_banned<B: BannedStore>(
+    banned_store: &B,
+    claims: &Claims,
+) -> Result<bool, AuthAPIError> {
+    if banned_store
+        .is_banned(&claims.jti)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?
+    {
+        return Ok(true);
+    }
+    let banned_until = banned_store
+        .subject_banned_until(&claims.sub)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    Ok(banned_until.is_some_and(|issued_until| claims.iat as i64 <= issued_until.timestamp()))
 }
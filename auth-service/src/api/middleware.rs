@@ -0,0 +1,230 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::{
+    error::AuthAPIError,
+    models::RateLimitPolicy,
+    ports::{RateLimiter, RateLimiterError},
+};
+
+// Largest request body buffered to find the target email.
+const MAX_BUFFERED_BODY_BYTES: usize = 64 * 1024;
+
+/// The header a reverse proxy appends the address of its client to.
+pub const FORWARDED_FOR_HEADER: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// The header carrying the ID of a request, both ways.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+// Longest request ID accepted from clients.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Tags the request with an ID, which error responses carry and the response returns in
+/// `x-request-id`. The ID the client or a proxy sent is kept, unless it is unreasonable.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LENGTH
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// The ID of the request being handled, outside of which there is none.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// The token-bucket quotas applied to one route.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RouteRateLimits {
+    /// The quota for each client IP address.
+    pub per_ip: RateLimitPolicy,
+    /// The quota for each target email address, across all clients.
+    pub per_email: RateLimitPolicy,
+}
+
+/// The rate limits of every throttled route.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub login: RouteRateLimits,
+    pub signup: RouteRateLimits,
+    pub resend_verification_email: RouteRateLimits,
+    pub password_reset: RouteRateLimits,
+    /// Whether the service runs behind a reverse proxy, whose `X-Forwarded-For` then names
+    /// the client. Without one, clients could pick the address they are limited by.
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            login: RouteRateLimits {
+                per_ip: policy(30, 60),
+                per_email: policy(10, 60),
+            },
+            signup: RouteRateLimits {
+                per_ip: policy(10, 60),
+                per_email: policy(5, 60),
+            },
+            // Each resend delivers an email, so a mailbox gets a few per hour at most.
+            resend_verification_email: RouteRateLimits {
+                per_ip: policy(10, 60),
+                per_email: policy(3, 3600),
+            },
+            // Reset links are emailed as well.
+            password_reset: RouteRateLimits {
+                per_ip: policy(10, 60),
+                per_email: policy(3, 3600),
+            },
+            trust_proxy: false,
+        }
+    }
+}
+
+fn policy(capacity: u32, period_seconds: u64) -> RateLimitPolicy {
+    RateLimitPolicy {
+        capacity,
+        period: Duration::from_secs(period_seconds),
+    }
+}
+
+/// The state of the rate limiting middleware of one route.
+#[derive(Clone)]
+pub struct RateLimitState<L: RateLimiter> {
+    limiter: Arc<RwLock<L>>,
+    /// Prefix of the bucket keys, so that routes do not share buckets.
+    route: &'static str,
+    limits: RouteRateLimits,
+    trust_proxy: bool,
+}
+
+impl<L: RateLimiter> RateLimitState<L> {
+    pub fn new(
+        limiter: Arc<RwLock<L>>,
+        route: &'static str,
+        limits: RouteRateLimits,
+        trust_proxy: bool,
+    ) -> Self {
+        Self {
+            limiter,
+            route,
+            limits,
+            trust_proxy,
+        }
+    }
+}
+
+// Only the email is needed; the handler validates the rest of the body.
+#[derive(Deserialize)]
+struct EmailField {
+    email: String,
+}
+
+/// Rejects the request with a 429 when either the client IP or the email in the
+/// JSON body ran out of tokens for this route.
+pub async fn rate_limit<L: RateLimiter>(
+    State(state): State<RateLimitState<L>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    let ip = client_ip(&request, state.trust_proxy);
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BUFFERED_BODY_BYTES).await else {
+        return Err(AuthAPIError::PayloadTooLarge);
+    };
+    let email = serde_json::from_slice::<EmailField>(&bytes)
+        .ok()
+        .map(|field| field.email.trim().to_lowercase());
+
+    if let Some(ip) = ip {
+        check(
+            &state,
+            &format!("{}:ip:{ip}", state.route),
+            &state.limits.per_ip,
+        )
+        .await?;
+    }
+    if let Some(email) = email {
+        check(
+            &state,
+            &format!("{}:email:{email}", state.route),
+            &state.limits.per_email,
+        )
+        .await?;
+    }
+
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
+
+// The address of the client. Behind a trusted proxy, it is the last one in
+// `X-Forwarded-For`, which the proxy appended: earlier ones are whatever the client sent.
+// Otherwise, or when the header is missing, it is the address of the peer.
+fn client_ip(request: &Request, trust_proxy: bool) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    if !trust_proxy {
+        return peer;
+    }
+    request
+        .headers()
+        .get_all(FORWARDED_FOR_HEADER)
+        .iter()
+        .next_back()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .or(peer)
+}
+
+async fn check<L: RateLimiter>(
+    state: &RateLimitState<L>,
+    key: &str,
+    policy: &RateLimitPolicy,
+) -> Result<(), AuthAPIError> {
+    state
+        .limiter
+        .write()
+        .await
+        .check(key, policy)
+        .await
+        .map_err(|e| match e {
+            RateLimiterError::Limited { retry_after } => {
+                AuthAPIError::TooManyRequests { retry_after }
+            }
+            RateLimiterError::UnexpectedError => AuthAPIError::UnexpectedError,
+        })
+}
@@ -1,50 +1,85 @@
 pub mod dtos;
+pub mod extractors;
 pub mod handlers;
+pub mod middleware;
 pub mod routes;
 pub mod utils;
 
 use routes::api_routes;
 
-use axum::{Router, serve::Serve};
-use std::sync::Arc;
+use axum::{
+    Router,
+    extract::connect_info::{ConnectInfo, IntoMakeServiceWithConnectInfo},
+    middleware::AddExtension,
+    serve::Serve,
+};
+use std::{net::SocketAddr, sync::Arc};
 use tokio::{net::TcpListener, sync::RwLock};
 
-use crate::domain::ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::domain::ports::{
+    BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RateLimiter, RefreshTokenStore,
+    TwoFACodeStore, UserStore,
+};
+use middleware::RateLimitConfig;
 
 #[derive(Clone)]
-pub struct AppState<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient> {
+pub struct AppState<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+> {
     pub user_store: Arc<RwLock<S>>,
     pub banned_store: Arc<RwLock<B>>,
     pub two_fa_store: Arc<RwLock<T>>,
     pub email_client: Arc<RwLock<E>>,
+    pub refresh_token_store: Arc<RwLock<R>>,
+    pub oauth_client_store: Arc<RwLock<O>>,
+    pub passkey_store: Arc<RwLock<P>>,
 }
 
-impl<S, B, T, E> AppState<S, B, T, E>
+impl<S, B, T, E, R, O, P> AppState<S, B, T, E, R, O, P>
 where
     S: UserStore,
     B: BannedStore,
     T: TwoFACodeStore,
     E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
 {
     pub fn new(
         user_store: Arc<RwLock<S>>,
         banned_store: Arc<RwLock<B>>,
         two_fa_store: Arc<RwLock<T>>,
         email_client: Arc<RwLock<E>>,
+        refresh_token_store: Arc<RwLock<R>>,
+        oauth_client_store: Arc<RwLock<O>>,
+        passkey_store: Arc<RwLock<P>>,
     ) -> Self {
         Self {
             user_store,
             banned_store,
             two_fa_store,
             email_client,
+            refresh_token_store,
+            oauth_client_store,
+            passkey_store,
         }
     }
 }
 
 // This struct encapsulates our application-related logic.
 pub struct Application {
-    /// The axum server instance.
-    server: Serve<TcpListener, Router, Router>,
+    /// The axum server instance, which exposes the client address to the rate limiter.
+    server: Serve<
+        TcpListener,
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
     // address is exposed as a public field
     // so we have access to it in tests.
     pub address: String,
@@ -52,18 +87,32 @@ pub struct Application {
 
 impl Application {
     /// Builds a new instance of the `Application`.
-    pub async fn build<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-        app_state: AppState<S, B, T, E>,
+    pub async fn build<
+        S: UserStore,
+        B: BannedStore,
+        T: TwoFACodeStore,
+        E: EmailClient,
+        R: RefreshTokenStore,
+        O: OAuthClientStore,
+        P: PasskeyStore,
+        L: RateLimiter,
+    >(
+        app_state: AppState<S, B, T, E, R, O, P>,
+        rate_limiter: Arc<RwLock<L>>,
+        rate_limit_config: RateLimitConfig,
         address: &str,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // Move the Router definition from `main.rs` to here.
         // Also, remove the `hello` route.
         // We don't need it at this point!
-        let router = api_routes(app_state);
+        let router = api_routes(app_state, rate_limiter, rate_limit_config);
 
         let listener = TcpListener::bind(address).await?;
         let address = listener.local_addr()?.to_string();
-        let server = axum::serve(listener, router);
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
 
         // Create a new Application instance and return it
         Ok(Self { server, address })
@@ -1,16 +1,26 @@
+use std::sync::Arc;
+
 use axum::{
     Json, Router,
-    http::Method,
+    http::{Method, header::CONTENT_TYPE},
+    middleware::{from_fn, from_fn_with_state},
     response::Html,
-    routing::{get, post},
+    routing::{delete, get, post},
 };
+use tokio::sync::RwLock;
 use tower_http::{cors::CorsLayer, services::ServeDir};
 use utoipa::OpenApi;
 use utoipa_rapidoc::RapiDoc;
 
 use crate::{
-    api::AppState,
-    domain::ports::{BannedStore, EmailClient, TwoFACodeStore, UserStore},
+    api::{
+        AppState,
+        middleware::{REQUEST_ID_HEADER, RateLimitConfig, RateLimitState, rate_limit, request_id},
+    },
+    domain::ports::{
+        BannedStore, EmailClient, OAuthClientStore, PasskeyStore, RateLimiter, RefreshTokenStore,
+        TwoFACodeStore, UserStore,
+    },
 };
 
 use super::handlers::*;
@@ -38,8 +48,31 @@ use super::handlers::*;
         handle_signup,
         handle_login,
         handle_logout,
+        handle_refresh,
         handle_verify_2fa,
+        handle_verify_email,
+        handle_resend_verification_email,
+        handle_request_password_reset,
+        handle_confirm_password_reset,
+        handle_password_strength,
+        handle_change_password,
+        handle_change_email,
+        handle_confirm_email_change,
+        handle_delete_account,
+        handle_export_account,
         handle_verify_token,
+        handle_enroll_totp,
+        handle_confirm_totp,
+        handle_regenerate_recovery_codes,
+        handle_passkey_register_start,
+        handle_passkey_register_finish,
+        handle_passkey_login_start,
+        handle_passkey_login_finish,
+        handle_jwks,
+        handle_authorize,
+        handle_token,
+        handle_userinfo,
+        handle_openid_configuration,
         openapi,
     ),
     components(
@@ -47,21 +80,68 @@ use super::handlers::*;
             super::dtos::SignUpRequest,
             super::dtos::LoginRequest,
             super::dtos::Verify2faRequest,
+            super::dtos::ResendVerificationEmailRequest,
+            super::dtos::PasswordResetRequest,
+            super::dtos::ConfirmPasswordResetRequest,
+            super::dtos::PasswordStrengthRequest,
+            super::dtos::PasswordStrengthResponse,
+            super::dtos::ChangePasswordRequest,
+            super::dtos::ChangeEmailRequest,
+            super::dtos::DeleteAccountRequest,
+            super::dtos::AccountExportResponse,
+            super::dtos::ExportedPasskey,
             super::dtos::VerifyTokenRequest,
+            super::dtos::EnrollTotpRequest,
+            super::dtos::ConfirmTotpRequest,
+            super::dtos::RegenerateRecoveryCodesRequest,
+            super::dtos::PasskeyRegistrationStartRequest,
+            super::dtos::PasskeyRegistrationRequest,
+            super::dtos::PasskeyAttestationResponse,
+            super::dtos::PasskeyLoginRequest,
+            super::dtos::PasskeyAssertionResponse,
             super::dtos::SignUpResponse,
             super::dtos::MFARequiredResponse,
-            super::dtos::ErrorResponse
+            super::dtos::TotpEnrollmentResponse,
+            super::dtos::RecoveryCodesResponse,
+            super::dtos::PasskeyRegistrationOptions,
+            super::dtos::PasskeyRelyingParty,
+            super::dtos::PasskeyUser,
+            super::dtos::PasskeyCredentialParameters,
+            super::dtos::PasskeyCredentialDescriptor,
+            super::dtos::PasskeyAuthenticatorSelection,
+            super::dtos::PasskeyLoginOptions,
+            super::dtos::ErrorResponse,
+            super::dtos::ErrorCode,
+            super::dtos::PasswordViolation,
+            super::dtos::InvalidField,
+            super::dtos::TokenRequest,
+            super::dtos::TokenResponse,
+            super::dtos::UserInfoResponse,
+            super::dtos::OpenIdConfiguration,
+            super::dtos::OAuthErrorResponse
         ),
     ),
     tags(
         (name = "auth", description = "Authentication endpoints."),
+        (name = "oidc", description = "OpenID Connect provider endpoints."),
         (name = "docs", description = "Documentation endpoints."),
     ),
 )]
 struct ApiDoc;
 
-pub fn api_routes<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClient>(
-    app_state: AppState<S, B, T, E>,
+pub fn api_routes<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    E: EmailClient,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+    L: RateLimiter,
+>(
+    app_state: AppState<S, B, T, E, R, O, P>,
+    rate_limiter: Arc<RwLock<L>>,
+    rate_limit_config: RateLimitConfig,
 ) -> Router {
     let allowed_origins = [
         "http://localhost:8000".parse().unwrap(),
@@ -70,24 +150,108 @@ pub fn api_routes<S: UserStore, B: BannedStore, T: TwoFACodeStore, E: EmailClien
     ];
 
     let cors = CorsLayer::new()
-        // Allow GET and POST requests
-        .allow_methods([Method::GET, Method::POST])
+        // Allow GET, POST and DELETE requests
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        // Allow JSON bodies, which browsers check before sending
+        .allow_headers([CONTENT_TYPE])
         // Allow cookies to be included in requests
         .allow_credentials(true)
+        // Let pages quote the request ID of an error
+        .expose_headers([REQUEST_ID_HEADER])
         .allow_origin(allowed_origins);
 
+    let trust_proxy = rate_limit_config.trust_proxy;
+    let login_rate_limit = RateLimitState::new(
+        rate_limiter.clone(),
+        "login",
+        rate_limit_config.login,
+        trust_proxy,
+    );
+    let signup_rate_limit = RateLimitState::new(
+        rate_limiter.clone(),
+        "signup",
+        rate_limit_config.signup,
+        trust_proxy,
+    );
+    let resend_verification_email_rate_limit = RateLimitState::new(
+        rate_limiter.clone(),
+        "resend-verification-email",
+        rate_limit_config.resend_verification_email,
+        trust_proxy,
+    );
+    let password_reset_rate_limit = RateLimitState::new(
+        rate_limiter,
+        "password-reset",
+        rate_limit_config.password_reset,
+        trust_proxy,
+    );
+
     Router::new()
         .route("/", get(handle_root))
-        .route("/login", post(handle_login))
+        .route(
+            "/login",
+            post(handle_login).layer(from_fn_with_state(login_rate_limit, rate_limit::<L>)),
+        )
         .route("/logout", post(handle_logout))
-        .route("/signup", post(handle_signup))
+        .route("/refresh", post(handle_refresh))
+        .route(
+            "/signup",
+            post(handle_signup).layer(from_fn_with_state(signup_rate_limit, rate_limit::<L>)),
+        )
+        .route("/verify-email", get(handle_verify_email))
+        .route(
+            "/resend-verification-email",
+            post(handle_resend_verification_email).layer(from_fn_with_state(
+                resend_verification_email_rate_limit,
+                rate_limit::<L>,
+            )),
+        )
+        .route(
+            "/password-reset/request",
+            post(handle_request_password_reset).layer(from_fn_with_state(
+                password_reset_rate_limit,
+                rate_limit::<L>,
+            )),
+        )
+        .route(
+            "/password-reset/confirm",
+            post(handle_confirm_password_reset),
+        )
+        .route("/password-strength", post(handle_password_strength))
+        .route("/change-password", post(handle_change_password))
+        .route("/change-email", post(handle_change_email))
+        .route("/confirm-email-change", get(handle_confirm_email_change))
+        .route("/account", delete(handle_delete_account))
+        .route("/account/export", get(handle_export_account))
         .route("/verify-2fa", post(handle_verify_2fa))
         .route("/verify-token", post(handle_verify_token))
+        .route("/enroll-totp", post(handle_enroll_totp))
+        .route("/confirm-totp", post(handle_confirm_totp))
+        .route("/recovery-codes", post(handle_regenerate_recovery_codes))
+        .route(
+            "/passkeys/register/start",
+            post(handle_passkey_register_start),
+        )
+        .route(
+            "/passkeys/register/finish",
+            post(handle_passkey_register_finish),
+        )
+        .route("/passkeys/login/start", post(handle_passkey_login_start))
+        .route("/passkeys/login/finish", post(handle_passkey_login_finish))
+        .route("/.well-known/jwks.json", get(handle_jwks))
+        .route("/authorize", get(handle_authorize))
+        .route("/token", post(handle_token))
+        .route("/userinfo", get(handle_userinfo).post(handle_userinfo))
+        .route(
+            "/.well-known/openid-configuration",
+            get(handle_openid_configuration),
+        )
         .route("/api-docs/openapi.json", get(openapi))
         .merge(RapiDoc::new("/api-docs/openapi.json").path("/api-docs"))
         .fallback_service(ServeDir::new("auth-service/assets"))
         .with_state(app_state)
         .layer(cors)
+        .layer(from_fn(request_id))
 }
 
 #[utoipa::path(
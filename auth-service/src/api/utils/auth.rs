@@ -1,15 +1,319 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError},
+};
+
+use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
 use axum_extra::extract::cookie::{Cookie, SameSite};
-use chrono::Utc;
-use jsonwebtoken::{DecodingKey, EncodingKey, Validation, decode, encode};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    errors::ErrorKind,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+    },
+};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::domain::models::{Email, RefreshToken, User};
+
+use super::constants::{
+    EMAIL_VERIFICATION_AUDIENCE, EMAIL_VERIFICATION_TTL_SECONDS, JWT_AUDIENCE, JWT_COOKIE_NAME,
+    JWT_ISSUER, JWT_KEY_RING, JWT_KEY_RING_FILE, OIDC_ACCESS_TOKEN_AUDIENCE,
+    REFRESH_TOKEN_COOKIE_NAME, REFRESH_TOKEN_TTL_SECONDS, TOKEN_TTL_SECONDS,
+    TOKEN_VALIDATION_LEEWAY_SECONDS,
+};
+
+// The `kid` of an HMAC secret configured without one.
+const DEFAULT_HMAC_KEY_ID: &str = "default";
+
+/// The key JWTs are signed with, along with what is needed to verify them.
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    // Only the public half of an asymmetric key is ever published.
+    jwk: Option<Jwk>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SigningKeyError {
+    /// Indicates that keys for this algorithm cannot be loaded from PEM.
+    UnsupportedAlgorithm(Algorithm),
+    /// Indicates that the PEM document is not a private key for the algorithm.
+    InvalidKey,
+}
+
+impl SigningKey {
+    /// An HS256 secret, shared with whoever verifies the tokens.
+    pub fn from_secret(secret: &[u8], kid: Option<String>) -> Self {
+        Self {
+            kid: kid.unwrap_or_else(|| DEFAULT_HMAC_KEY_ID.to_owned()),
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    /// Loads an RS256 or EdDSA private key from a PEM document, PKCS#8 or, for RSA, PKCS#1.
+    /// Without a `kid`, the key is identified by its RFC 7638 thumbprint.
+    pub fn from_pem(
+        algorithm: Algorithm,
+        pem: &[u8],
+        kid: Option<String>,
+    ) -> Result<Self, SigningKeyError> {
+        let (encoding_key, public_key) = match algorithm {
+            Algorithm::RS256 => {
+                let encoding_key =
+                    EncodingKey::from_rsa_pem(pem).map_err(|_| SigningKeyError::InvalidKey)?;
+                let public_key = Jwk::from_encoding_key(&encoding_key, algorithm)
+                    .map_err(|_| SigningKeyError::InvalidKey)?
+                    .algorithm;
+                (encoding_key, public_key)
+            }
+            Algorithm::EdDSA => {
+                let encoding_key =
+                    EncodingKey::from_ed_pem(pem).map_err(|_| SigningKeyError::InvalidKey)?;
+                let document = pem::parse(pem).map_err(|_| SigningKeyError::InvalidKey)?;
+                let key_pair = Ed25519KeyPair::from_pkcs8(document.contents())
+                    .map_err(|_| SigningKeyError::InvalidKey)?;
+                let public_key = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                });
+                (encoding_key, public_key)
+            }
+            _ => return Err(SigningKeyError::UnsupportedAlgorithm(algorithm)),
+        };
+
+        let decoding_key = match &public_key {
+            AlgorithmParameters::RSA(rsa) => DecodingKey::from_rsa_components(&rsa.n, &rsa.e),
+            AlgorithmParameters::OctetKeyPair(okp) => DecodingKey::from_ed_components(&okp.x),
+            _ => return Err(SigningKeyError::InvalidKey),
+        }
+        .map_err(|_| SigningKeyError::InvalidKey)?;
+
+        let kid = kid.unwrap_or_else(|| thumbprint(&public_key));
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match algorithm {
+                    Algorithm::RS256 => KeyAlgorithm::RS256,
+                    _ => KeyAlgorithm::EdDSA,
+                }),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: public_key,
+        };
+
+        Ok(Self {
+            kid,
+            algorithm,
+            encoding_key,
+            decoding_key,
+            jwk: Some(jwk),
+        })
+    }
+
+    /// The key ID written to the `kid` header of every token signed with this key.
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The public key to publish in the JWKS, or `None` for an HMAC secret.
+    pub fn jwk(&self) -> Option<&Jwk> {
+        self.jwk.as_ref()
+    }
+}
+
+// The base64url SHA-256 of the required members of the public key, in lexicographic order (RFC 7638).
+fn thumbprint(public_key: &AlgorithmParameters) -> String {
+    let members = match public_key {
+        AlgorithmParameters::RSA(rsa) => {
+            format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n)
+        }
+        AlgorithmParameters::OctetKeyPair(okp) => {
+            format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, okp.x)
+        }
+        _ => String::new(),
+    };
+    URL_SAFE_NO_PAD.encode(Sha256::digest(members.as_bytes()))
+}
+
+/// The keys tokens are verified with: the active key every new token is signed with, and
+/// verification-only keys that keep tokens signed before a rotation valid until they are retired.
+pub struct KeyRing {
+    active: SigningKey,
+    verification_keys: Vec<SigningKey>,
+}
+
+#[derive(Debug)]
+pub enum KeyRingError {
+    /// Indicates that the manifest or a key file could not be read.
+    Io { path: PathBuf, error: String },
+    /// Indicates that the manifest is not valid JSON of the expected shape.
+    InvalidManifest(String),
+    /// Indicates that a key file does not hold a key for its algorithm.
+    InvalidKey { kid: String, error: SigningKeyError },
+    /// Indicates that the active key is not among the keys of the manifest.
+    UnknownActiveKey(String),
+    /// Indicates that two keys share a `kid`, so tokens could not tell them apart.
+    DuplicateKeyId(String),
+}
+
+// The JSON document pointed to by JWT_KEY_RING_FILE, e.g.
+// {"active": "2026-10", "keys": [{"kid": "2026-10", "algorithm": "EdDSA", "file": "2026-10.pem"}]}
+#[derive(Deserialize)]
+struct KeyRingManifest {
+    active: String,
+    keys: Vec<KeyRingEntry>,
+}
+
+#[derive(Deserialize)]
+struct KeyRingEntry {
+    kid: String,
+    algorithm: Algorithm,
+    /// A PEM private key, or the secret itself for HS256. Relative to the manifest.
+    file: PathBuf,
+}
+
+impl KeyRing {
+    pub fn new(
+        active: SigningKey,
+        verification_keys: Vec<SigningKey>,
+    ) -> Result<Self, KeyRingError> {
+        let mut kids = HashSet::new();
+        for key in std::iter::once(&active).chain(&verification_keys) {
+            if !kids.insert(key.kid()) {
+                return Err(KeyRingError::DuplicateKeyId(key.kid().to_owned()));
+            }
+        }
+        Ok(Self {
+            active,
+            verification_keys,
+        })
+    }
+
+    /// Loads the keys listed in a JSON manifest.
+    pub fn load(manifest_path: &Path) -> Result<Self, KeyRingError> {
+        let manifest = read_file(manifest_path)?;
+        let manifest: KeyRingManifest = serde_json::from_slice(&manifest)
+            .map_err(|e| KeyRingError::InvalidManifest(e.to_string()))?;
+        let dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+        let mut active = None;
+        let mut verification_keys = Vec::new();
+        for entry in manifest.keys {
+            let contents = read_file(&dir.join(&entry.file))?;
+            let key = match entry.algorithm {
+                Algorithm::HS256 => Ok(SigningKey::from_secret(
+                    contents.trim_ascii_end(),
+                    Some(entry.kid.clone()),
+                )),
+                algorithm => SigningKey::from_pem(algorithm, &contents, Some(entry.kid.clone())),
+            }
+            .map_err(|error| KeyRingError::InvalidKey {
+                kid: entry.kid.clone(),
+                error,
+            })?;
+
+            if entry.kid == manifest.active && active.is_none() {
+                active = Some(key);
+            } else {
+                verification_keys.push(key);
+            }
+        }
+
+        let active = active.ok_or(KeyRingError::UnknownActiveKey(manifest.active))?;
+        Self::new(active, verification_keys)
+    }
+
+    /// The key new tokens are signed with.
+    pub fn active(&self) -> &SigningKey {
+        &self.active
+    }
 
-use crate::domain::models::Email;
+    /// Finds the key a token names in its `kid` header.
+    pub fn get(&self, kid: &str) -> Option<&SigningKey> {
+        std::iter::once(&self.active)
+            .chain(&self.verification_keys)
+            .find(|key| key.kid() == kid)
+    }
+
+    /// The public keys of the ring, the active one first. HMAC secrets are left out.
+    pub fn jwks(&self) -> JwkSet {
+        let keys = std::iter::once(&self.active)
+            .chain(&self.verification_keys)
+            .filter_map(|key| key.jwk().cloned())
+            .collect();
+        JwkSet { keys }
+    }
+}
 
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET, TOKEN_TTL_SECONDS};
+fn read_file(path: &Path) -> Result<Vec<u8>, KeyRingError> {
+    std::fs::read(path).map_err(|e| KeyRingError::Io {
+        path: path.to_owned(),
+        error: e.to_string(),
+    })
+}
+
+/// The key ring currently in use.
+pub fn key_ring() -> Arc<KeyRing> {
+    JWT_KEY_RING
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Reloads the key ring from JWT_KEY_RING_FILE, keeping the current one if the file is invalid.
+/// Without a manifest the ring is a single key from the environment, which cannot change.
+pub fn reload_key_ring() -> Result<(), KeyRingError> {
+    let Some(path) = JWT_KEY_RING_FILE.as_deref() else {
+        return Ok(());
+    };
+    let key_ring = KeyRing::load(Path::new(path))?;
+    *JWT_KEY_RING.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(key_ring);
+    Ok(())
+}
+
+/// Reloads the key ring every time the process receives SIGHUP.
+#[cfg(unix)]
+pub fn spawn_key_ring_reloader() -> std::io::Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    Ok(tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match reload_key_ring() {
+                Ok(()) => tracing::info!(
+                    active_kid = key_ring().active().kid(),
+                    "reloaded JWT key ring"
+                ),
+                Err(e) => tracing::error!(
+                    error = ?e,
+                    "failed to reload JWT key ring, keeping the current one"
+                ),
+            }
+        }
+    }))
+}
 
 // Create cookie with a new JWT auth token
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>, GenerateTokenError> {
-    let token = generate_auth_token(email)?;
+pub fn generate_auth_cookie(user: &User) -> Result<Cookie<'static>, GenerateTokenError> {
+    let token = generate_auth_token(user)?;
     Ok(create_auth_cookie(token))
 }
 
@@ -22,6 +326,17 @@ fn create_auth_cookie(token: String) -> Cookie<'static> {
         .build()
 }
 
+// Create cookie carrying an opaque refresh token
+pub fn create_refresh_cookie(token: &RefreshToken) -> Cookie<'static> {
+    let max_age = i64::try_from(*REFRESH_TOKEN_TTL_SECONDS).unwrap_or(i64::MAX);
+    Cookie::build((REFRESH_TOKEN_COOKIE_NAME, token.as_ref().to_owned()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict) // only ever needed by our own /refresh and /logout calls
+        .max_age(time::Duration::seconds(max_age))
+        .build()
+}
+
 #[derive(Debug)]
 pub enum GenerateTokenError {
     TokenError(jsonwebtoken::errors::Error),
@@ -29,61 +344,216 @@ pub enum GenerateTokenError {
 }
 
 // Create JWT auth token
-fn generate_auth_token(email: &Email) -> Result<String, GenerateTokenError> {
-    let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
-        .ok_or(GenerateTokenError::UnexpectedError)?;
+pub(crate) fn generate_auth_token(user: &User) -> Result<String, GenerateTokenError> {
+    let claims = build_claims(user, JWT_AUDIENCE.clone())?;
+    create_token(&claims, key_ring().active()).map_err(GenerateTokenError::TokenError)
+}
+
+/// Create an OpenID Connect ID token for `client_id`, carrying the nonce of the authorization
+/// request. It is signed like any other token, so clients verify it against the JWKS.
+pub fn generate_id_token(
+    user: &User,
+    client_id: &str,
+    nonce: Option<String>,
+) -> Result<String, GenerateTokenError> {
+    let claims = Claims {
+        nonce,
+        ..build_claims(user, client_id.to_owned())?
+    };
+    create_token(&claims, key_ring().active()).map_err(GenerateTokenError::TokenError)
+}
+
+/// Create the access token of an OpenID Connect client, granting `scope`. Its audience tells
+/// it apart from session tokens, so that it is accepted at /userinfo and nowhere else.
+pub fn generate_oidc_access_token(user: &User, scope: &str) -> Result<String, GenerateTokenError> {
+    let claims = Claims {
+        scope: Some(scope.to_owned()),
+        ..build_claims_for(
+            user.email.as_ref().to_owned(),
+            OIDC_ACCESS_TOKEN_AUDIENCE.to_owned(),
+            TOKEN_TTL_SECONDS,
+        )?
+    };
+    create_token(&claims, key_ring().active()).map_err(GenerateTokenError::TokenError)
+}
+
+/// Create the token of the link that verifies `email`, valid for EMAIL_VERIFICATION_TTL_SECONDS.
+/// Its audience tells it apart from auth tokens, so that neither is accepted for the other.
+pub fn generate_email_verification_token(email: &Email) -> Result<String, GenerateTokenError> {
+    let ttl_seconds = i64::try_from(*EMAIL_VERIFICATION_TTL_SECONDS)
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    let claims = build_claims_for(
+        email.as_ref().to_owned(),
+        EMAIL_VERIFICATION_AUDIENCE.to_owned(),
+        ttl_seconds,
+    )?;
+    create_token(&claims, key_ring().active()).map_err(GenerateTokenError::TokenError)
+}
+
+// Build the claims of a token for `user`, valid from now on for TOKEN_TTL_SECONDS
+fn build_claims(user: &User, aud: String) -> Result<Claims, GenerateTokenError> {
+    Ok(Claims {
+        roles: user.roles.clone(),
+        ..build_claims_for(user.email.as_ref().to_owned(), aud, TOKEN_TTL_SECONDS)?
+    })
+}
+
+// Build the claims of a token about `sub`, valid from now on for `ttl_seconds`
+fn build_claims_for(
+    sub: String,
+    aud: String,
+    ttl_seconds: i64,
+) -> Result<Claims, GenerateTokenError> {
+    let delta =
+        chrono::Duration::try_seconds(ttl_seconds).ok_or(GenerateTokenError::UnexpectedError)?;
+
+    let now = Utc::now();
 
     // Create JWT expiration time
-    let exp = Utc::now()
+    let exp = now
         .checked_add_signed(delta)
         .ok_or(GenerateTokenError::UnexpectedError)?
         .timestamp();
 
-    // Cast exp to a usize, which is what Claims expects
+    // Cast timestamps to usize, which is what Claims expects
     let exp: usize = exp
         .try_into()
         .map_err(|_| GenerateTokenError::UnexpectedError)?;
+    let iat: usize = now
+        .timestamp()
+        .try_into()
+        .map_err(|_| GenerateTokenError::UnexpectedError)?;
 
-    let sub = email.as_ref().to_owned();
+    Ok(Claims {
+        sub,
+        exp,
+        iat,
+        nbf: iat,
+        jti: uuid::Uuid::new_v4().to_string(),
+        iss: JWT_ISSUER.clone(),
+        aud,
+        roles: Vec::new(),
+        nonce: None,
+        scope: None,
+    })
+}
 
-    let claims = Claims { sub, exp };
+// Check if JWT auth token is valid by verifying it against the key it names
+pub async fn validate_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    decode_token(token, &key_ring())
+}
 
-    create_token(&claims).map_err(GenerateTokenError::TokenError)
+/// Check the access token of an OpenID Connect client.
+pub fn validate_oidc_access_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode_token_for(token, &key_ring(), OIDC_ACCESS_TOKEN_AUDIENCE)?;
+    if claims.scope.is_none() {
+        return Err(ErrorKind::MissingRequiredClaim("scope".to_owned()).into());
+    }
+    Ok(claims)
 }
 
-// Check if JWT auth token is valid by decoding it using the JWT secret
-pub async fn validate_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
+/// Check the token of a verification link, returning the email it verifies.
+pub fn validate_email_verification_token(
+    token: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    decode_token_for(token, &key_ring(), EMAIL_VERIFICATION_AUDIENCE).map(|claims| claims.sub)
+}
+
+fn decode_token(token: &str, key_ring: &KeyRing) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let claims = decode_token_for(token, key_ring, &JWT_AUDIENCE)?;
+    // Only the access tokens of OpenID Connect clients are scoped, and they never open a session.
+    if claims.scope.is_some() {
+        return Err(ErrorKind::InvalidAudience.into());
+    }
+    Ok(claims)
 }
 
-// Create JWT auth token by encoding claims using the JWT secret
-fn create_token(claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
-    encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
-    )
+fn decode_token_for(
+    token: &str,
+    key_ring: &KeyRing,
+    audience: &str,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    // Tokens issued before keys had IDs were signed with the only key there was.
+    let key = match decode_header(token)?.kid {
+        Some(kid) => key_ring.get(&kid).ok_or(ErrorKind::InvalidSignature)?,
+        None => key_ring.active(),
+    };
+
+    // The algorithm comes from our key, never from the token.
+    let mut validation = Validation::new(key.algorithm);
+    validation.leeway = TOKEN_VALIDATION_LEEWAY_SECONDS;
+    validation.validate_nbf = true;
+    validation.set_issuer(&[JWT_ISSUER.as_str()]);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+
+    decode::<Claims>(token, &key.decoding_key, &validation).map(|data| data.claims)
+}
+
+// Create JWT auth token by signing claims with the signing key
+fn create_token(claims: &Claims, key: &SigningKey) -> Result<String, jsonwebtoken::errors::Error> {
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+    encode(&header, &claims, &key.encoding_key)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
+    /// The email of the user the token was issued to.
     pub sub: String,
+    /// Expiry, in seconds since the epoch.
     pub exp: usize,
+    /// Issue time, in seconds since the epoch.
+    pub iat: usize,
+    /// The token is refused before this time, in seconds since the epoch.
+    pub nbf: usize,
+    /// A unique ID for the token, under which it is banned on logout.
+    pub jti: String,
+    /// Who issued the token.
+    pub iss: String,
+    /// Who the token is meant for.
+    pub aud: String,
+    /// The roles of the user when the token was issued. Omitted when there are none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// The nonce of the OpenID Connect authorization request, in ID tokens only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// The space-separated scopes granted to an OpenID Connect client, in its access tokens only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl Claims {
+    // The instant after which `validate_token` rejects the token, leeway included
+    pub fn valid_until(&self) -> DateTime<Utc> {
+        let valid_until = self.exp as i64 + TOKEN_VALIDATION_LEEWAY_SECONDS as i64;
+        DateTime::from_timestamp(valid_until, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use jsonwebtoken::jwk::ThumbprintHash;
+
+    use crate::domain::models::{Email, PasswordHash, TwoFAMethod};
+
+    fn user() -> User {
+        User::new(
+            Email::parse("test@example.com").unwrap(),
+            PasswordHash::parse(
+                "$argon2id$v=19$m=1024,t=1,p=1$c29tZXNhbHQ$YWFhYWFhYWFhYWFhYWFhYQ".to_owned(),
+            )
+            .unwrap(),
+            TwoFAMethod::None,
+        )
+    }
+
     #[tokio::test]
     async fn test_generate_auth_cookie() {
-        let email = Email::parse("test@example.com").unwrap();
-        let cookie = generate_auth_cookie(&email).unwrap();
+        let cookie = generate_auth_cookie(&user()).unwrap();
         assert_eq!(cookie.name(), JWT_COOKIE_NAME);
         assert_eq!(cookie.value().split('.').count(), 3);
         assert_eq!(cookie.path(), Some("/"));
@@ -102,19 +572,35 @@ mod tests {
         assert_eq!(cookie.same_site(), Some(SameSite::Lax));
     }
 
+    #[test]
+    fn test_create_refresh_cookie() {
+        let token = RefreshToken::default();
+        let cookie = create_refresh_cookie(&token);
+        assert_eq!(cookie.name(), REFRESH_TOKEN_COOKIE_NAME);
+        assert_eq!(cookie.value(), token.as_ref());
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+        assert_eq!(
+            cookie.max_age().map(|max_age| max_age.whole_seconds()),
+            Some(*REFRESH_TOKEN_TTL_SECONDS as i64)
+        );
+    }
+
     #[tokio::test]
     async fn test_generate_auth_token() {
-        let email = Email::parse("test@example.com").unwrap();
-        let result = generate_auth_token(&email).unwrap();
+        let result = generate_auth_token(&user()).unwrap();
         assert_eq!(result.split('.').count(), 3);
     }
 
     #[tokio::test]
     async fn test_validate_token_with_valid_token() {
-        let email = Email::parse("test@example.com").unwrap();
-        let token = generate_auth_token(&email).unwrap();
+        let token = generate_auth_token(&user()).unwrap();
         let result = validate_token(&token).await.unwrap();
         assert_eq!(result.sub, "test@example.com");
+        assert_eq!(result.iss, *JWT_ISSUER);
+        assert_eq!(result.aud, *JWT_AUDIENCE);
+        assert_eq!(result.nbf, result.iat);
+        assert_eq!(result.exp, result.iat + TOKEN_TTL_SECONDS as usize);
 
         let exp = Utc::now()
             .checked_add_signed(chrono::Duration::try_minutes(9).expect("valid duration"))
@@ -124,6 +610,396 @@ mod tests {
         assert!(result.exp > exp as usize);
     }
 
+    #[tokio::test]
+    async fn test_email_verification_tokens_are_not_auth_tokens() {
+        let email = Email::parse("test@example.com").unwrap();
+        let token = generate_email_verification_token(&email).unwrap();
+        assert_eq!(
+            validate_email_verification_token(&token).unwrap(),
+            "test@example.com"
+        );
+        assert!(validate_token(&token).await.is_err());
+
+        let auth_token = generate_auth_token(&user()).unwrap();
+        assert!(validate_email_verification_token(&auth_token).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oidc_access_tokens_are_not_auth_tokens() {
+        let token = generate_oidc_access_token(&user(), "openid email").unwrap();
+        let access_claims = validate_oidc_access_token(&token).unwrap();
+        assert_eq!(access_claims.sub, "test@example.com");
+        assert_eq!(access_claims.aud, OIDC_ACCESS_TOKEN_AUDIENCE);
+        assert_eq!(access_claims.scope.as_deref(), Some("openid email"));
+        assert!(validate_token(&token).await.is_err());
+
+        let auth_token = generate_auth_token(&user()).unwrap();
+        assert!(validate_oidc_access_token(&auth_token).is_err());
+
+        // Nor is a session token that somehow carries a scope.
+        let scoped = Claims {
+            scope: Some("openid".to_owned()),
+            ..claims()
+        };
+        let token = create_token(&scoped, key_ring().active()).unwrap();
+        assert_eq!(
+            decode_token(&token, &key_ring()).unwrap_err().kind(),
+            &ErrorKind::InvalidAudience
+        );
+    }
+
+    #[tokio::test]
+    async fn test_valid_until_includes_leeway() {
+        let claims = Claims {
+            exp: 1_000,
+            ..claims()
+        };
+        assert_eq!(
+            claims.valid_until().timestamp(),
+            1_000 + TOKEN_VALIDATION_LEEWAY_SECONDS as i64
+        );
+    }
+
+    const RSA_PEM: &[u8] = include_bytes!("../../../tests/fixtures/jwt_rs256.pem");
+    const ED25519_PEM: &[u8] = include_bytes!("../../../tests/fixtures/jwt_ed25519.pem");
+
+    fn single_key_ring(key: &SigningKey) -> KeyRing {
+        KeyRing::new(key.clone(), vec![]).unwrap()
+    }
+
+    fn claims() -> Claims {
+        let now = Utc::now().timestamp() as usize;
+        Claims {
+            sub: "test@example.com".to_owned(),
+            exp: now + 600,
+            iat: now,
+            nbf: now,
+            jti: uuid::Uuid::new_v4().to_string(),
+            iss: JWT_ISSUER.clone(),
+            aud: JWT_AUDIENCE.clone(),
+            roles: Vec::new(),
+            nonce: None,
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn test_tokens_get_unique_ids() {
+        let first = decode_token(&generate_auth_token(&user()).unwrap(), &key_ring()).unwrap();
+        let second = decode_token(&generate_auth_token(&user()).unwrap(), &key_ring()).unwrap();
+        assert_ne!(first.jti, second.jti);
+    }
+
+    #[test]
+    fn test_roles_claim_comes_from_user() {
+        let token = generate_auth_token(&user()).unwrap();
+        let payload = token.split('.').nth(1).unwrap();
+        let payload: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        assert!(payload.get("roles").is_none());
+
+        let admin = User {
+            roles: vec!["admin".to_owned()],
+            ..user()
+        };
+        let claims = decode_token(&generate_auth_token(&admin).unwrap(), &key_ring()).unwrap();
+        assert_eq!(claims.roles, ["admin"]);
+    }
+
+    #[test]
+    fn test_issuer_and_audience_are_validated() {
+        let key = SigningKey::from_secret(b"secret", None);
+        let key_ring = single_key_ring(&key);
+
+        let other_issuer = Claims {
+            iss: "someone-else".to_owned(),
+            ..claims()
+        };
+        let token = create_token(&other_issuer, &key).unwrap();
+        assert_eq!(
+            decode_token(&token, &key_ring).unwrap_err().kind(),
+            &ErrorKind::InvalidIssuer
+        );
+
+        let other_audience = Claims {
+            aud: "someone-else".to_owned(),
+            ..claims()
+        };
+        let token = create_token(&other_audience, &key).unwrap();
+        assert_eq!(
+            decode_token(&token, &key_ring).unwrap_err().kind(),
+            &ErrorKind::InvalidAudience
+        );
+    }
+
+    #[test]
+    fn test_id_token_is_meant_for_the_client() {
+        let token = generate_id_token(&user(), "client", Some("n-0S6_WzA2Mj".to_owned())).unwrap();
+
+        let key = key_ring().active().clone();
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_issuer(&[JWT_ISSUER.as_str()]);
+        validation.set_audience(&["client"]);
+        let claims = decode::<Claims>(&token, &key.decoding_key, &validation)
+            .unwrap()
+            .claims;
+        assert_eq!(claims.sub, "test@example.com");
+        assert_eq!(claims.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+
+        // Nor can an ID token be passed off as an access token to our own services.
+        assert_eq!(
+            decode_token(&token, &key_ring()).unwrap_err().kind(),
+            &ErrorKind::InvalidAudience
+        );
+    }
+
+    #[test]
+    fn test_token_is_refused_before_nbf() {
+        let key = SigningKey::from_secret(b"secret", None);
+        let nbf = Utc::now().timestamp() as usize + 300;
+        let not_yet_valid = Claims { nbf, ..claims() };
+        let token = create_token(&not_yet_valid, &key).unwrap();
+        assert_eq!(
+            decode_token(&token, &single_key_ring(&key))
+                .unwrap_err()
+                .kind(),
+            &ErrorKind::ImmatureSignature
+        );
+    }
+
+    #[test]
+    fn test_asymmetric_keys_sign_and_verify() {
+        for (algorithm, pem) in [(Algorithm::RS256, RSA_PEM), (Algorithm::EdDSA, ED25519_PEM)] {
+            let key = SigningKey::from_pem(algorithm, pem, None).unwrap();
+            let token = create_token(&claims(), &key).unwrap();
+
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.alg, algorithm);
+            assert_eq!(header.kid.as_deref(), Some(key.kid()));
+            assert_eq!(
+                decode_token(&token, &single_key_ring(&key)).unwrap().sub,
+                "test@example.com"
+            );
+        }
+    }
+
+    #[test]
+    fn test_published_jwk_verifies_tokens() {
+        for (algorithm, pem) in [(Algorithm::RS256, RSA_PEM), (Algorithm::EdDSA, ED25519_PEM)] {
+            let key = SigningKey::from_pem(algorithm, pem, Some("key-1".to_owned())).unwrap();
+            let token = create_token(&claims(), &key).unwrap();
+
+            // What a consumer holding only the JWKS does.
+            let jwk = key.jwk().unwrap();
+            assert_eq!(jwk.common.key_id.as_deref(), Some("key-1"));
+            let decoding_key = DecodingKey::from_jwk(jwk).unwrap();
+            let mut validation = Validation::new(algorithm);
+            validation.set_audience(&[JWT_AUDIENCE.as_str()]);
+            let claims = decode::<Claims>(&token, &decoding_key, &validation)
+                .unwrap()
+                .claims;
+            assert_eq!(claims.sub, "test@example.com");
+        }
+    }
+
+    #[test]
+    fn test_default_kid_is_stable_thumbprint() {
+        let first = SigningKey::from_pem(Algorithm::EdDSA, ED25519_PEM, None).unwrap();
+        let second = SigningKey::from_pem(Algorithm::EdDSA, ED25519_PEM, None).unwrap();
+        let other = SigningKey::from_pem(Algorithm::RS256, RSA_PEM, None).unwrap();
+        assert_eq!(first.kid(), second.kid());
+        assert_ne!(first.kid(), other.kid());
+        assert_eq!(first.kid().len(), 43);
+        assert_eq!(
+            other.kid(),
+            other.jwk().unwrap().thumbprint(ThumbprintHash::SHA256)
+        );
+    }
+
+    #[test]
+    fn test_hmac_key_is_not_published() {
+        let key = SigningKey::from_secret(b"secret", None);
+        assert!(key.jwk().is_none());
+        assert_eq!(key.kid(), DEFAULT_HMAC_KEY_ID);
+    }
+
+    #[test]
+    fn test_from_pem_rejects_mismatched_or_unsupported_keys() {
+        assert_eq!(
+            SigningKey::from_pem(Algorithm::EdDSA, RSA_PEM, None).err(),
+            Some(SigningKeyError::InvalidKey)
+        );
+        assert_eq!(
+            SigningKey::from_pem(Algorithm::RS256, ED25519_PEM, None).err(),
+            Some(SigningKeyError::InvalidKey)
+        );
+        assert_eq!(
+            SigningKey::from_pem(Algorithm::ES256, RSA_PEM, None).err(),
+            Some(SigningKeyError::UnsupportedAlgorithm(Algorithm::ES256))
+        );
+    }
+
+    #[test]
+    fn test_token_from_other_key_is_rejected() {
+        let key = SigningKey::from_pem(Algorithm::RS256, RSA_PEM, Some("a".to_owned())).unwrap();
+        let other = SigningKey::from_pem(Algorithm::RS256, RSA_PEM, Some("b".to_owned())).unwrap();
+        let key_ring = single_key_ring(&key);
+        let token = create_token(&claims(), &other).unwrap();
+        assert!(decode_token(&token, &key_ring).is_err());
+
+        let hmac_key = SigningKey::from_secret(b"secret", Some("a".to_owned()));
+        let token = create_token(&claims(), &hmac_key).unwrap();
+        assert!(decode_token(&token, &key_ring).is_err());
+    }
+
+    #[test]
+    fn test_tokens_of_previous_key_validate_until_retired() {
+        let previous = SigningKey::from_secret(b"old secret", Some("2026-04".to_owned()));
+        let current = SigningKey::from_pem(Algorithm::EdDSA, ED25519_PEM, None).unwrap();
+        let old_token = create_token(&claims(), &previous).unwrap();
+
+        // Rotation: the new key signs, the previous one only verifies.
+        let rotated = KeyRing::new(current.clone(), vec![previous]).unwrap();
+        let new_token = create_token(&claims(), rotated.active()).unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some(current.kid())
+        );
+        assert!(decode_token(&new_token, &rotated).is_ok());
+        assert!(decode_token(&old_token, &rotated).is_ok());
+
+        // Retirement: the previous key is dropped from the ring.
+        let retired = single_key_ring(&current);
+        assert!(decode_token(&new_token, &retired).is_ok());
+        assert!(decode_token(&old_token, &retired).is_err());
+    }
+
+    #[test]
+    fn test_token_without_kid_is_checked_against_active_key() {
+        let key = SigningKey::from_secret(b"secret", None);
+        let token = encode(
+            &Header::default(),
+            &claims(),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert!(decode_token(&token, &single_key_ring(&key)).is_ok());
+
+        // A verification-only key is only ever selected by its kid.
+        let ring = KeyRing::new(SigningKey::from_secret(b"new secret", None), vec![]).unwrap();
+        assert!(decode_token(&token, &ring).is_err());
+    }
+
+    #[test]
+    fn test_key_ring_rejects_duplicate_kids() {
+        let key = SigningKey::from_secret(b"secret", Some("a".to_owned()));
+        let other = SigningKey::from_secret(b"other secret", Some("a".to_owned()));
+        assert!(matches!(
+            KeyRing::new(key, vec![other]),
+            Err(KeyRingError::DuplicateKeyId(kid)) if kid == "a"
+        ));
+    }
+
+    #[test]
+    fn test_jwks_lists_public_keys_active_first() {
+        let rsa = SigningKey::from_pem(Algorithm::RS256, RSA_PEM, Some("rsa".to_owned())).unwrap();
+        let ed =
+            SigningKey::from_pem(Algorithm::EdDSA, ED25519_PEM, Some("ed".to_owned())).unwrap();
+        let hmac = SigningKey::from_secret(b"secret", Some("hmac".to_owned()));
+        let ring = KeyRing::new(ed, vec![hmac, rsa]).unwrap();
+
+        let kids: Vec<_> = ring
+            .jwks()
+            .keys
+            .into_iter()
+            .map(|jwk| jwk.common.key_id.unwrap())
+            .collect();
+        assert_eq!(kids, ["ed", "rsa"]);
+    }
+
+    fn write_manifest(dir: &Path, manifest: serde_json::Value) -> PathBuf {
+        let path = dir.join("keys.json");
+        std::fs::write(&path, manifest.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_key_ring_from_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("old-secret"), "old secret\n").unwrap();
+        let manifest = write_manifest(
+            dir.path(),
+            serde_json::json!({
+                "active": "2026-10",
+                "keys": [
+                    {
+                        "kid": "2026-10",
+                        "algorithm": "EdDSA",
+                        "file": concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/jwt_ed25519.pem"),
+                    },
+                    { "kid": "2026-04", "algorithm": "HS256", "file": "old-secret" },
+                ],
+            }),
+        );
+
+        let ring = KeyRing::load(&manifest).unwrap();
+        assert_eq!(ring.active().kid(), "2026-10");
+        assert_eq!(ring.active().algorithm(), Algorithm::EdDSA);
+
+        // The secret file's trailing newline is not part of the secret.
+        let old_key = SigningKey::from_secret(b"old secret", Some("2026-04".to_owned()));
+        let old_token = create_token(&claims(), &old_key).unwrap();
+        assert!(decode_token(&old_token, &ring).is_ok());
+    }
+
+    #[test]
+    fn test_load_key_ring_rejects_invalid_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret"), "secret").unwrap();
+
+        let manifest = write_manifest(
+            dir.path(),
+            serde_json::json!({
+                "active": "missing",
+                "keys": [{ "kid": "a", "algorithm": "HS256", "file": "secret" }],
+            }),
+        );
+        assert!(matches!(
+            KeyRing::load(&manifest),
+            Err(KeyRingError::UnknownActiveKey(kid)) if kid == "missing"
+        ));
+
+        let manifest = write_manifest(
+            dir.path(),
+            serde_json::json!({
+                "active": "a",
+                "keys": [{ "kid": "a", "algorithm": "RS256", "file": "secret" }],
+            }),
+        );
+        assert!(matches!(
+            KeyRing::load(&manifest),
+            Err(KeyRingError::InvalidKey { kid, error: SigningKeyError::InvalidKey }) if kid == "a"
+        ));
+
+        let manifest = write_manifest(
+            dir.path(),
+            serde_json::json!({
+                "active": "a",
+                "keys": [{ "kid": "a", "algorithm": "HS256", "file": "missing" }],
+            }),
+        );
+        assert!(matches!(
+            KeyRing::load(&manifest),
+            Err(KeyRingError::Io { .. })
+        ));
+
+        let manifest = write_manifest(dir.path(), serde_json::json!({ "active": "a" }));
+        assert!(matches!(
+            KeyRing::load(&manifest),
+            Err(KeyRingError::InvalidManifest(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_invalid_token() {
         let token = "invalid_token".to_owned();
@@ -0,0 +1,153 @@
+//! An offline copy of the Have I Been Pwned list of breached passwords.
+//!
+//! The file holds one `<SHA-1 hash>:<count>` line per password, hashes in uppercase hex and
+//! sorted, as written by the official downloader. Lookups follow the k-anonymity model of
+//! the online API: only the range of hashes sharing the first 5 hex digits is read, by
+//! binary search, so the file never needs to fit in memory.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use aws_lc_rs::digest::{SHA1_FOR_LEGACY_USE_ONLY, digest};
+use data_encoding::HEXUPPER;
+
+// The hash prefix a range is keyed by, in hex digits.
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+/// A breached password list in the Have I Been Pwned format.
+#[derive(Clone, Debug)]
+pub struct BreachedPasswordFile {
+    path: PathBuf,
+    len: u64,
+}
+
+impl BreachedPasswordFile {
+    /// Opens the list at `path`, which is read again on each lookup.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let len = std::fs::metadata(path)?.len();
+        Ok(Self {
+            path: path.to_owned(),
+            len,
+        })
+    }
+
+    /// Checks whether the password appears in the list.
+    pub fn contains(&self, password: &str) -> io::Result<bool> {
+        let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes());
+        let hash = HEXUPPER.encode(hash.as_ref());
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+        Ok(self
+            .range(prefix)?
+            .iter()
+            .any(|(candidate, _)| candidate == suffix))
+    }
+
+    /// Lists the hash suffixes starting with `prefix`, 5 uppercase hex digits, along with
+    /// how many times each password was seen in breaches.
+    pub fn range(&self, prefix: &str) -> io::Result<Vec<(String, u64)>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+
+        // The first line whose hash is not below the prefix starts at or after `low`.
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match line_from(&mut reader, middle)? {
+                Some(line) if key(&line) < prefix => low = middle + 1,
+                _ => high = middle,
+            }
+        }
+
+        let mut range = Vec::new();
+        let mut line = line_from(&mut reader, low)?;
+        while let Some(current) = line.filter(|current| key(current) == prefix) {
+            if let Some((hash, count)) = current.split_once(':') {
+                let count = count.parse().map_err(|_| invalid_line(&current))?;
+                range.push((hash[RANGE_PREFIX_LENGTH..].to_owned(), count));
+            }
+            line = next_line(&mut reader)?;
+        }
+        Ok(range)
+    }
+}
+
+// Reads the first line starting at or after `offset`.
+fn line_from(reader: &mut BufReader<File>, offset: u64) -> io::Result<Option<String>> {
+    if offset == 0 {
+        reader.seek(SeekFrom::Start(0))?;
+    } else {
+        // Skips the rest of the line `offset` falls in, unless it starts a line.
+        reader.seek(SeekFrom::Start(offset - 1))?;
+        reader.skip_until(b'\n')?;
+    }
+    next_line(reader)
+}
+
+fn next_line(reader: &mut BufReader<File>) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end().to_owned()))
+}
+
+fn key(line: &str) -> &str {
+    line.get(..RANGE_PREFIX_LENGTH).unwrap_or(line)
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid breached password line: {line}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    // SHA-1 of "password", which the file lists.
+    const PASSWORD_HASH: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+    fn file() -> (BreachedPasswordFile, tempfile::NamedTempFile) {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let lines = [
+            format!("00000{}:12", "A".repeat(35)),
+            format!("5BAA6{}:3", "0".repeat(35)),
+            format!("{PASSWORD_HASH}:52256179"),
+            format!("5BAA6{}:7", "F".repeat(35)),
+            format!("5BAA7{}:1", "0".repeat(35)),
+            format!("FFFFF{}:2", "F".repeat(35)),
+        ];
+        for line in lines {
+            write!(file, "{line}\r\n").unwrap();
+        }
+        (BreachedPasswordFile::open(file.path()).unwrap(), file)
+    }
+
+    #[test]
+    fn test_contains_breached_passwords_only() {
+        let (breached, _file) = file();
+        assert!(breached.contains("password").unwrap());
+        assert!(!breached.contains("Correct-horse-battery-staple").unwrap());
+    }
+
+    #[test]
+    fn test_range_lists_the_hashes_sharing_the_prefix() {
+        let (breached, _file) = file();
+        assert_eq!(
+            breached.range("5BAA6").unwrap(),
+            [
+                ("0".repeat(35), 3),
+                (PASSWORD_HASH[5..].to_owned(), 52256179),
+                ("F".repeat(35), 7),
+            ]
+        );
+        assert_eq!(breached.range("00000").unwrap().len(), 1);
+        assert_eq!(breached.range("FFFFF").unwrap().len(), 1);
+        assert_eq!(breached.range("12345").unwrap(), []);
+    }
+}
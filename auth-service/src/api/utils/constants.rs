@@ -2,9 +2,55 @@ use dotenvy::dotenv;
 use lazy_static::lazy_static;
 use std::env as std_env;
 
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use jsonwebtoken::Algorithm;
+
+use crate::{
+    api::{
+        middleware::{RateLimitConfig, RouteRateLimits},
+        utils::{
+            auth::{KeyRing, SigningKey},
+            breached_passwords::BreachedPasswordFile,
+        },
+    },
+    domain::models::{HashingPolicy, LockoutPolicy, OAuthClient, PasswordPolicy, TwoFACodePolicy},
+};
+use serde::Deserialize;
+
 // Define a lazily evaluated static. lazy_static is needed because std_env::var is not a const function.
 lazy_static! {
     pub static ref JWT_SECRET: String = set_token();
+    pub static ref JWT_ISSUER: String = set_jwt_issuer();
+    pub static ref JWT_AUDIENCE: String = set_jwt_audience();
+    pub static ref JWT_KEY_RING_FILE: Option<String> = set_key_ring_file();
+    pub static ref JWT_KEY_RING: RwLock<Arc<KeyRing>> = RwLock::new(Arc::new(set_key_ring()));
+    pub static ref PASSWORD_HASHING_POLICY: HashingPolicy = set_hashing_policy();
+    pub static ref PASSWORD_POLICY: PasswordPolicy = set_password_policy();
+    pub static ref BREACHED_PASSWORDS: Option<BreachedPasswordFile> = set_breached_passwords();
+    pub static ref DATABASE_URL: Option<String> = set_database_url();
+    pub static ref DATABASE_MAX_CONNECTIONS: u32 = set_database_max_connections();
+    pub static ref REDIS_URL: Option<String> = set_redis_url();
+    pub static ref BANNED_TOKEN_SWEEP_INTERVAL_SECONDS: u64 = set_banned_token_sweep_interval();
+    pub static ref TWO_FA_CODE_POLICY: TwoFACodePolicy = set_two_fa_code_policy();
+    pub static ref RATE_LIMIT_CONFIG: RateLimitConfig = set_rate_limit_config();
+    pub static ref LOCKOUT_POLICY: LockoutPolicy = set_lockout_policy();
+    pub static ref REFRESH_TOKEN_TTL_SECONDS: u64 = set_refresh_token_ttl();
+    pub static ref OIDC_CLIENTS: Vec<OAuthClient> = set_oidc_clients();
+    pub static ref TOTP_ISSUER: String = set_totp_issuer();
+    pub static ref TOTP_SKEW_STEPS: u64 = set_totp_skew_steps();
+    pub static ref WEBAUTHN_RP_ID: String = set_webauthn_rp_id();
+    pub static ref WEBAUTHN_RP_NAME: String = set_webauthn_rp_name();
+    pub static ref WEBAUTHN_ORIGIN: String = set_webauthn_origin();
+    pub static ref EMAIL_VERIFICATION_URL: String = set_email_verification_url();
+    pub static ref EMAIL_VERIFICATION_TTL_SECONDS: u64 = set_email_verification_ttl();
+    pub static ref EMAIL_CHANGE_URL: String = set_email_change_url();
+    pub static ref PASSWORD_RESET_URL: String = set_password_reset_url();
+    pub static ref PASSWORD_RESET_TOKEN_TTL_SECONDS: u64 = set_password_reset_token_ttl();
 }
 
 fn set_token() -> String {
@@ -16,13 +62,405 @@ fn set_token() -> String {
     secret
 }
 
+fn set_jwt_issuer() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(env::JWT_ISSUER_ENV_VAR, "auth-service".to_owned())
+}
+
+fn set_jwt_audience() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(env::JWT_AUDIENCE_ENV_VAR, "app-service".to_owned())
+}
+
+// Set JWT_KEY_RING_FILE to rotate keys: the manifest it points to is reloaded on SIGHUP.
+fn set_key_ring_file() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::JWT_KEY_RING_FILE_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+fn set_key_ring() -> KeyRing {
+    match JWT_KEY_RING_FILE.as_deref() {
+        Some(path) => KeyRing::load(Path::new(path))
+            .unwrap_or_else(|e| panic!("Failed to load the JWT key ring from {path}: {e:?}")),
+        None => KeyRing::new(set_signing_key(), Vec::new()).expect("A single key has a unique kid"),
+    }
+}
+
+// Without a key ring, HS256 signs with JWT_SECRET. RS256 and EdDSA sign with the private key in JWT_PRIVATE_KEY_FILE,
+// whose public half is published at /.well-known/jwks.json.
+fn set_signing_key() -> SigningKey {
+    dotenv().ok(); // Load environment variables
+    let algorithm = env_or(env::JWT_ALGORITHM_ENV_VAR, Algorithm::HS256);
+    let kid = std_env::var(env::JWT_KEY_ID_ENV_VAR)
+        .ok()
+        .filter(|kid| !kid.is_empty());
+    if algorithm == Algorithm::HS256 {
+        return SigningKey::from_secret(JWT_SECRET.as_bytes(), kid);
+    }
+
+    let path = std_env::var(env::JWT_PRIVATE_KEY_FILE_ENV_VAR)
+        .expect("JWT_PRIVATE_KEY_FILE must be set for asymmetric JWT algorithms.");
+    let pem = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {path}: {e}"));
+    SigningKey::from_pem(algorithm, &pem, kid)
+        .unwrap_or_else(|e| panic!("{path} is not a usable {algorithm:?} key: {e:?}"))
+}
+
+fn set_hashing_policy() -> HashingPolicy {
+    dotenv().ok(); // Load environment variables
+    let default = HashingPolicy::default();
+    HashingPolicy {
+        memory_kib: env_or(env::PASSWORD_HASH_MEMORY_KIB_ENV_VAR, default.memory_kib),
+        iterations: env_or(env::PASSWORD_HASH_ITERATIONS_ENV_VAR, default.iterations),
+        parallelism: env_or(env::PASSWORD_HASH_PARALLELISM_ENV_VAR, default.parallelism),
+    }
+}
+
+fn set_password_policy() -> PasswordPolicy {
+    dotenv().ok(); // Load environment variables
+    let default = PasswordPolicy::default();
+    PasswordPolicy {
+        min_length: env_or(env::PASSWORD_MIN_LENGTH_ENV_VAR, default.min_length),
+        max_length: env_or(env::PASSWORD_MAX_LENGTH_ENV_VAR, default.max_length),
+        require_lowercase: env_or(
+            env::PASSWORD_REQUIRE_LOWERCASE_ENV_VAR,
+            default.require_lowercase,
+        ),
+        require_uppercase: env_or(
+            env::PASSWORD_REQUIRE_UPPERCASE_ENV_VAR,
+            default.require_uppercase,
+        ),
+        require_digit: env_or(env::PASSWORD_REQUIRE_DIGIT_ENV_VAR, default.require_digit),
+        require_symbol: env_or(env::PASSWORD_REQUIRE_SYMBOL_ENV_VAR, default.require_symbol),
+        max_repeated_chars: env_or(
+            env::PASSWORD_MAX_REPEATED_CHARS_ENV_VAR,
+            default.max_repeated_chars,
+        ),
+        disallow_email_local_part: env_or(
+            env::PASSWORD_DISALLOW_EMAIL_LOCAL_PART_ENV_VAR,
+            default.disallow_email_local_part,
+        ),
+        min_strength: env_or(env::PASSWORD_MIN_STRENGTH_ENV_VAR, default.min_strength),
+    }
+}
+
+// New passwords are only checked against breaches when BREACHED_PASSWORDS_FILE points to a
+// local copy of the Have I Been Pwned list.
+fn set_breached_passwords() -> Option<BreachedPasswordFile> {
+    dotenv().ok(); // Load environment variables
+    let path = std_env::var(env::BREACHED_PASSWORDS_FILE_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())?;
+    Some(
+        BreachedPasswordFile::open(Path::new(&path))
+            .unwrap_or_else(|e| panic!("Failed to open {path}: {e}")),
+    )
+}
+
+// An unset or empty DATABASE_URL selects the in-memory stores.
+fn set_database_url() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::DATABASE_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+fn set_database_max_connections() -> u32 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::DATABASE_MAX_CONNECTIONS_ENV_VAR, 5)
+}
+
+// An unset or empty REDIS_URL keeps banned tokens and 2FA codes in process memory.
+fn set_redis_url() -> Option<String> {
+    dotenv().ok(); // Load environment variables
+    std_env::var(env::REDIS_URL_ENV_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+fn set_banned_token_sweep_interval() -> u64 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::BANNED_TOKEN_SWEEP_INTERVAL_SECONDS_ENV_VAR, 60)
+}
+
+fn set_two_fa_code_policy() -> TwoFACodePolicy {
+    dotenv().ok(); // Load environment variables
+    let default = TwoFACodePolicy::default();
+    TwoFACodePolicy {
+        lifetime: Duration::from_secs(env_or(
+            env::TWO_FA_CODE_LIFETIME_SECONDS_ENV_VAR,
+            default.lifetime.as_secs(),
+        )),
+        max_attempts: env_or(env::TWO_FA_CODE_MAX_ATTEMPTS_ENV_VAR, default.max_attempts),
+    }
+}
+
+// Quotas are written as `<capacity>/<period in seconds>`, e.g. `RATE_LIMIT_LOGIN_PER_IP=30/60`.
+fn set_rate_limit_config() -> RateLimitConfig {
+    dotenv().ok(); // Load environment variables
+    let default = RateLimitConfig::default();
+    RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: env_or(env::RATE_LIMIT_LOGIN_PER_IP_ENV_VAR, default.login.per_ip),
+            per_email: env_or(
+                env::RATE_LIMIT_LOGIN_PER_EMAIL_ENV_VAR,
+                default.login.per_email,
+            ),
+        },
+        signup: RouteRateLimits {
+            per_ip: env_or(env::RATE_LIMIT_SIGNUP_PER_IP_ENV_VAR, default.signup.per_ip),
+            per_email: env_or(
+                env::RATE_LIMIT_SIGNUP_PER_EMAIL_ENV_VAR,
+                default.signup.per_email,
+            ),
+        },
+        resend_verification_email: RouteRateLimits {
+            per_ip: env_or(
+                env::RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_IP_ENV_VAR,
+                default.resend_verification_email.per_ip,
+            ),
+            per_email: env_or(
+                env::RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_EMAIL_ENV_VAR,
+                default.resend_verification_email.per_email,
+            ),
+        },
+        password_reset: RouteRateLimits {
+            per_ip: env_or(
+                env::RATE_LIMIT_PASSWORD_RESET_PER_IP_ENV_VAR,
+                default.password_reset.per_ip,
+            ),
+            per_email: env_or(
+                env::RATE_LIMIT_PASSWORD_RESET_PER_EMAIL_ENV_VAR,
+                default.password_reset.per_email,
+            ),
+        },
+        trust_proxy: env_or(env::RATE_LIMIT_TRUST_PROXY_ENV_VAR, default.trust_proxy),
+    }
+}
+
+fn set_lockout_policy() -> LockoutPolicy {
+    dotenv().ok(); // Load environment variables
+    let default = LockoutPolicy::default();
+    LockoutPolicy {
+        max_failed_attempts: env_or(
+            env::LOGIN_MAX_FAILED_ATTEMPTS_ENV_VAR,
+            default.max_failed_attempts,
+        ),
+        lockout_duration: Duration::from_secs(env_or(
+            env::LOGIN_LOCKOUT_SECONDS_ENV_VAR,
+            default.lockout_duration.as_secs(),
+        )),
+    }
+}
+
+fn set_refresh_token_ttl() -> u64 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::REFRESH_TOKEN_TTL_SECONDS_ENV_VAR, 14 * 24 * 60 * 60) // 14 days
+}
+
+// An entry of the JSON array pointed to by OIDC_CLIENTS_FILE. Only public clients,
+// which rely on PKCE alone, leave out the secret.
+#[derive(Deserialize)]
+struct OAuthClientEntry {
+    client_id: String,
+    name: String,
+    redirect_uris: Vec<String>,
+    client_secret: Option<String>,
+}
+
+// The OpenID Connect clients registered at startup, none unless OIDC_CLIENTS_FILE is set.
+fn set_oidc_clients() -> Vec<OAuthClient> {
+    dotenv().ok(); // Load environment variables
+    let Some(path) = std_env::var(env::OIDC_CLIENTS_FILE_ENV_VAR)
+        .ok()
+        .filter(|path| !path.is_empty())
+    else {
+        return Vec::new();
+    };
+    let contents = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {path}: {e}"));
+    let entries: Vec<OAuthClientEntry> = serde_json::from_slice(&contents)
+        .unwrap_or_else(|e| panic!("{path} is not a valid list of OIDC clients: {e}"));
+    // ID tokens are addressed to the client ID, so a client named after one of the
+    // service's own audiences would be handed tokens accepted as sessions or links.
+    let reserved = [
+        JWT_AUDIENCE.as_str(),
+        EMAIL_VERIFICATION_AUDIENCE,
+        OIDC_ACCESS_TOKEN_AUDIENCE,
+    ];
+    entries
+        .into_iter()
+        .map(|entry| {
+            if reserved.contains(&entry.client_id.as_str()) {
+                panic!(
+                    "{path}: the client ID {:?} is reserved for the service's own tokens",
+                    entry.client_id
+                );
+            }
+            OAuthClient::new(
+                entry.client_id,
+                entry.name,
+                entry.redirect_uris,
+                entry.client_secret.as_deref(),
+            )
+        })
+        .collect()
+}
+
+// The service name authenticator apps list TOTP codes under.
+fn set_totp_issuer() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(env::TOTP_ISSUER_ENV_VAR, "Auth Service".to_owned())
+}
+
+// How many 30-second steps a TOTP code may lag or lead the server clock.
+fn set_totp_skew_steps() -> u64 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::TOTP_SKEW_STEPS_ENV_VAR, 1)
+}
+
+// The WebAuthn relying party ID passkeys are scoped to: the service's domain, without
+// scheme or port.
+fn set_webauthn_rp_id() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(env::WEBAUTHN_RP_ID_ENV_VAR, "localhost".to_owned())
+}
+
+// The service name shown by authenticators next to the passkey.
+fn set_webauthn_rp_name() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(env::WEBAUTHN_RP_NAME_ENV_VAR, "Auth Service".to_owned())
+}
+
+// The web origin browsers report for the pages running the passkey ceremonies.
+fn set_webauthn_origin() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(
+        env::WEBAUTHN_ORIGIN_ENV_VAR,
+        "http://localhost:3000".to_owned(),
+    )
+}
+
+// The page the verification link points to; the token is appended as `?token=`.
+fn set_email_verification_url() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(
+        env::EMAIL_VERIFICATION_URL_ENV_VAR,
+        "http://localhost:3000/verify-email".to_owned(),
+    )
+}
+
+fn set_email_verification_ttl() -> u64 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::EMAIL_VERIFICATION_TTL_SECONDS_ENV_VAR, 24 * 60 * 60) // 1 day
+}
+
+// The page the email change link points to; the token is appended as `?token=`.
+fn set_email_change_url() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(
+        env::EMAIL_CHANGE_URL_ENV_VAR,
+        "http://localhost:3000/confirm-email-change".to_owned(),
+    )
+}
+
+// The page the password reset link points to; the token is appended as `?token=`.
+fn set_password_reset_url() -> String {
+    dotenv().ok(); // Load environment variables
+    env_or(
+        env::PASSWORD_RESET_URL_ENV_VAR,
+        "http://localhost:3000/reset-password".to_owned(),
+    )
+}
+
+fn set_password_reset_token_ttl() -> u64 {
+    dotenv().ok(); // Load environment variables
+    env_or(env::PASSWORD_RESET_TOKEN_TTL_SECONDS_ENV_VAR, 60 * 60) // 1 hour
+}
+
+// Read an optional environment variable, falling back to a default when unset.
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std_env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} has an invalid value.")),
+        Err(_) => default,
+    }
+}
+
 pub mod env {
     pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
+    pub const JWT_ALGORITHM_ENV_VAR: &str = "JWT_ALGORITHM";
+    pub const JWT_PRIVATE_KEY_FILE_ENV_VAR: &str = "JWT_PRIVATE_KEY_FILE";
+    pub const JWT_KEY_ID_ENV_VAR: &str = "JWT_KEY_ID";
+    pub const JWT_ISSUER_ENV_VAR: &str = "JWT_ISSUER";
+    pub const JWT_AUDIENCE_ENV_VAR: &str = "JWT_AUDIENCE";
+    pub const JWT_KEY_RING_FILE_ENV_VAR: &str = "JWT_KEY_RING_FILE";
+    pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+    pub const DATABASE_MAX_CONNECTIONS_ENV_VAR: &str = "DATABASE_MAX_CONNECTIONS";
+    pub const REDIS_URL_ENV_VAR: &str = "REDIS_URL";
+    pub const BANNED_TOKEN_SWEEP_INTERVAL_SECONDS_ENV_VAR: &str =
+        "BANNED_TOKEN_SWEEP_INTERVAL_SECONDS";
+    pub const TWO_FA_CODE_LIFETIME_SECONDS_ENV_VAR: &str = "TWO_FA_CODE_LIFETIME_SECONDS";
+    pub const TWO_FA_CODE_MAX_ATTEMPTS_ENV_VAR: &str = "TWO_FA_CODE_MAX_ATTEMPTS";
+    pub const RATE_LIMIT_LOGIN_PER_IP_ENV_VAR: &str = "RATE_LIMIT_LOGIN_PER_IP";
+    pub const RATE_LIMIT_LOGIN_PER_EMAIL_ENV_VAR: &str = "RATE_LIMIT_LOGIN_PER_EMAIL";
+    pub const RATE_LIMIT_SIGNUP_PER_IP_ENV_VAR: &str = "RATE_LIMIT_SIGNUP_PER_IP";
+    pub const RATE_LIMIT_SIGNUP_PER_EMAIL_ENV_VAR: &str = "RATE_LIMIT_SIGNUP_PER_EMAIL";
+    pub const RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_IP_ENV_VAR: &str =
+        "RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_IP";
+    pub const RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_EMAIL_ENV_VAR: &str =
+        "RATE_LIMIT_RESEND_VERIFICATION_EMAIL_PER_EMAIL";
+    pub const RATE_LIMIT_PASSWORD_RESET_PER_IP_ENV_VAR: &str = "RATE_LIMIT_PASSWORD_RESET_PER_IP";
+    pub const RATE_LIMIT_PASSWORD_RESET_PER_EMAIL_ENV_VAR: &str =
+        "RATE_LIMIT_PASSWORD_RESET_PER_EMAIL";
+    pub const RATE_LIMIT_TRUST_PROXY_ENV_VAR: &str = "RATE_LIMIT_TRUST_PROXY";
+    pub const LOGIN_MAX_FAILED_ATTEMPTS_ENV_VAR: &str = "LOGIN_MAX_FAILED_ATTEMPTS";
+    pub const LOGIN_LOCKOUT_SECONDS_ENV_VAR: &str = "LOGIN_LOCKOUT_SECONDS";
+    pub const REFRESH_TOKEN_TTL_SECONDS_ENV_VAR: &str = "REFRESH_TOKEN_TTL_SECONDS";
+    pub const OIDC_CLIENTS_FILE_ENV_VAR: &str = "OIDC_CLIENTS_FILE";
+    pub const TOTP_ISSUER_ENV_VAR: &str = "TOTP_ISSUER";
+    pub const TOTP_SKEW_STEPS_ENV_VAR: &str = "TOTP_SKEW_STEPS";
+    pub const WEBAUTHN_RP_ID_ENV_VAR: &str = "WEBAUTHN_RP_ID";
+    pub const WEBAUTHN_RP_NAME_ENV_VAR: &str = "WEBAUTHN_RP_NAME";
+    pub const WEBAUTHN_ORIGIN_ENV_VAR: &str = "WEBAUTHN_ORIGIN";
+    pub const EMAIL_VERIFICATION_URL_ENV_VAR: &str = "EMAIL_VERIFICATION_URL";
+    pub const EMAIL_VERIFICATION_TTL_SECONDS_ENV_VAR: &str = "EMAIL_VERIFICATION_TTL_SECONDS";
+    pub const EMAIL_CHANGE_URL_ENV_VAR: &str = "EMAIL_CHANGE_URL";
+    pub const PASSWORD_RESET_URL_ENV_VAR: &str = "PASSWORD_RESET_URL";
+    pub const PASSWORD_RESET_TOKEN_TTL_SECONDS_ENV_VAR: &str = "PASSWORD_RESET_TOKEN_TTL_SECONDS";
+    pub const PASSWORD_MIN_LENGTH_ENV_VAR: &str = "PASSWORD_MIN_LENGTH";
+    pub const PASSWORD_MAX_LENGTH_ENV_VAR: &str = "PASSWORD_MAX_LENGTH";
+    pub const PASSWORD_REQUIRE_LOWERCASE_ENV_VAR: &str = "PASSWORD_REQUIRE_LOWERCASE";
+    pub const PASSWORD_REQUIRE_UPPERCASE_ENV_VAR: &str = "PASSWORD_REQUIRE_UPPERCASE";
+    pub const PASSWORD_REQUIRE_DIGIT_ENV_VAR: &str = "PASSWORD_REQUIRE_DIGIT";
+    pub const PASSWORD_REQUIRE_SYMBOL_ENV_VAR: &str = "PASSWORD_REQUIRE_SYMBOL";
+    pub const PASSWORD_MAX_REPEATED_CHARS_ENV_VAR: &str = "PASSWORD_MAX_REPEATED_CHARS";
+    pub const PASSWORD_DISALLOW_EMAIL_LOCAL_PART_ENV_VAR: &str =
+        "PASSWORD_DISALLOW_EMAIL_LOCAL_PART";
+    pub const PASSWORD_MIN_STRENGTH_ENV_VAR: &str = "PASSWORD_MIN_STRENGTH";
+    pub const BREACHED_PASSWORDS_FILE_ENV_VAR: &str = "BREACHED_PASSWORDS_FILE";
+    pub const PASSWORD_HASH_MEMORY_KIB_ENV_VAR: &str = "PASSWORD_HASH_MEMORY_KIB";
+    pub const PASSWORD_HASH_ITERATIONS_ENV_VAR: &str = "PASSWORD_HASH_ITERATIONS";
+    pub const PASSWORD_HASH_PARALLELISM_ENV_VAR: &str = "PASSWORD_HASH_PARALLELISM";
 }
 
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
 // This value determines how long the JWT auth token is valid for
 pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
+// The audience of the tokens in verification links, so that they are never taken for auth tokens
+pub const EMAIL_VERIFICATION_AUDIENCE: &str = "email-verification";
+// The audience of the access tokens of OpenID Connect clients, which only /userinfo accepts
+pub const OIDC_ACCESS_TOKEN_AUDIENCE: &str = "oidc-userinfo";
+// Clock skew tolerated on `exp` when validating a JWT
+pub const TOKEN_VALIDATION_LEEWAY_SECONDS: u64 = 60;
+// How long an OpenID Connect client has to redeem an authorization code
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+// How long a passkey ceremony may take, from its options to the authenticator's response
+pub const PASSKEY_CHALLENGE_TTL_SECONDS: i64 = 300;
+// How many recovery codes a user is given at once
+pub const RECOVERY_CODE_COUNT: usize = 10;
 
 pub mod prod {
     pub const APP_ADDRESS: &str = "0.0.0.0:3000";
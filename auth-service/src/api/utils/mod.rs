@@ -1,2 +1,4 @@
 pub mod auth;
+pub mod breached_passwords;
 pub mod constants;
+pub mod webauthn;
@@ -0,0 +1,248 @@
+//! Parsing and checks of the responses of WebAuthn ceremonies (Web Authentication Level 2),
+//! as relying party for `WEBAUTHN_RP_ID`.
+//!
+//! Only `none` attestation is supported: registered authenticators are trusted to be
+//! what they claim, as passkeys synced between devices carry no attestation anyway.
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ciborium::Value;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::constants::{WEBAUTHN_ORIGIN, WEBAUTHN_RP_ID};
+use crate::domain::models::{CosePublicKey, Email, PasskeyCeremony, PasskeyChallenge};
+
+// Authenticator data flags (section 6.1).
+const USER_PRESENT: u8 = 0x01;
+const USER_VERIFIED: u8 = 0x04;
+const ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+// rpIdHash, flags and signCount.
+const AUTHENTICATOR_DATA_HEADER_LENGTH: usize = 37;
+const AAGUID_LENGTH: usize = 16;
+
+/// The client data the browser collects for a ceremony and the authenticator signs
+/// (`clientDataJSON`).
+#[derive(Deserialize)]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    ceremony_type: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Checks the client data of a ceremony: that it is of the expected type and comes from
+/// `WEBAUTHN_ORIGIN`. Returns the challenge it answers, which is left to the caller to
+/// consume.
+pub fn verify_client_data(
+    client_data_json: &[u8],
+    ceremony: PasskeyCeremony,
+) -> Result<PasskeyChallenge, String> {
+    let client_data = serde_json::from_slice::<CollectedClientData>(client_data_json)
+        .map_err(|_| "Invalid client data".to_owned())?;
+
+    let expected_type = match ceremony {
+        PasskeyCeremony::Registration => "webauthn.create",
+        PasskeyCeremony::Authentication => "webauthn.get",
+    };
+    if client_data.ceremony_type != expected_type {
+        return Err("Unexpected ceremony type".to_owned());
+    }
+    if client_data.origin != *WEBAUTHN_ORIGIN {
+        return Err("Unexpected origin".to_owned());
+    }
+    PasskeyChallenge::parse(client_data.challenge)
+}
+
+/// The credential an authenticator created, as attested in its authenticator data.
+#[derive(Debug, PartialEq)]
+pub struct AttestedCredential {
+    /// The base64url-encoded credential ID.
+    pub credential_id: String,
+    pub public_key: CosePublicKey,
+}
+
+/// The data an authenticator reports about a ceremony (section 6.1).
+#[derive(Debug, PartialEq)]
+pub struct AuthenticatorData {
+    pub sign_count: u32,
+    /// The new credential, present in registrations only.
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+impl AuthenticatorData {
+    /// Parses authenticator data, checking that it was produced for `WEBAUTHN_RP_ID` and
+    /// that the user was both present and verified, e.g. by a PIN or a fingerprint: a
+    /// passkey stands in for the password and the second factor at once.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid authenticator data".to_owned();
+        if bytes.len() < AUTHENTICATOR_DATA_HEADER_LENGTH {
+            return Err(invalid());
+        }
+        let (rp_id_hash, rest) = bytes.split_at(32);
+        let flags = rest[0];
+        let sign_count = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]);
+
+        if rp_id_hash != Sha256::digest(WEBAUTHN_RP_ID.as_bytes()).as_slice() {
+            return Err("Unexpected relying party".to_owned());
+        }
+        if flags & USER_PRESENT == 0 || flags & USER_VERIFIED == 0 {
+            return Err("User not verified".to_owned());
+        }
+        if flags & ATTESTED_CREDENTIAL_DATA == 0 {
+            return Ok(Self {
+                sign_count,
+                attested_credential: None,
+            });
+        }
+
+        // The AAGUID, then the length-prefixed credential ID and the public key, which may
+        // be followed by extension outputs.
+        let rest = rest[5..].get(AAGUID_LENGTH..).ok_or_else(invalid)?;
+        let (length, rest) = rest.split_at_checked(2).ok_or_else(invalid)?;
+        let length = usize::from(u16::from_be_bytes([length[0], length[1]]));
+        let (credential_id, rest) = rest.split_at_checked(length).ok_or_else(invalid)?;
+        let mut remainder = rest;
+        ciborium::from_reader::<Value, _>(&mut remainder).map_err(|_| invalid())?;
+        let public_key = rest[..rest.len() - remainder.len()].to_vec();
+
+        Ok(Self {
+            sign_count,
+            attested_credential: Some(AttestedCredential {
+                credential_id: URL_SAFE_NO_PAD.encode(credential_id),
+                public_key: CosePublicKey::parse(public_key)?,
+            }),
+        })
+    }
+}
+
+/// Extracts the authenticator data from the CBOR attestation object of a registration.
+/// The attestation statement is ignored, as only `none` attestation is requested.
+pub fn authenticator_data_from_attestation(attestation_object: &[u8]) -> Result<Vec<u8>, String> {
+    ciborium::from_reader::<Value, _>(attestation_object)
+        .ok()
+        .and_then(|value| value.into_map().ok())
+        .and_then(|fields| {
+            fields
+                .into_iter()
+                .find(|(key, _)| key.as_text() == Some("authData"))
+        })
+        .and_then(|(_, value)| value.into_bytes().ok())
+        .ok_or_else(|| "Invalid attestation object".to_owned())
+}
+
+/// The base64url-encoded user handle passkeys are created under: the SHA-256 of the email,
+/// so that authenticators are not handed an identifier that reveals the user.
+pub fn user_handle(email: &Email) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(email.as_ref().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend(sign_count.to_be_bytes());
+        data
+    }
+
+    // An Ed25519 COSE_Key with an all-zero public key.
+    fn cose_key() -> Vec<u8> {
+        let mut bytes = vec![0xa4, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06, 0x21, 0x58, 0x20];
+        bytes.extend([0; 32]);
+        bytes
+    }
+
+    fn client_data(ceremony_type: &str, origin: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "type": ceremony_type,
+            "challenge": PasskeyChallenge::default().as_ref(),
+            "origin": origin,
+            "crossOrigin": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parses_assertion_data() {
+        let data = authenticator_data(&WEBAUTHN_RP_ID, USER_PRESENT | USER_VERIFIED, 7);
+        assert_eq!(
+            AuthenticatorData::parse(&data),
+            Ok(AuthenticatorData {
+                sign_count: 7,
+                attested_credential: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parses_attested_credential_followed_by_extensions() {
+        let mut data = authenticator_data(
+            &WEBAUTHN_RP_ID,
+            USER_PRESENT | USER_VERIFIED | ATTESTED_CREDENTIAL_DATA | 0x80,
+            0,
+        );
+        data.extend([0; AAGUID_LENGTH]);
+        data.extend([0, 3, 1, 2, 3]);
+        data.extend(cose_key());
+        // An extensions map: {"credProtect": 2}.
+        data.extend([0xa1, 0x6b]);
+        data.extend(b"credProtect");
+        data.push(0x02);
+
+        let parsed = AuthenticatorData::parse(&data).unwrap();
+        assert_eq!(
+            parsed.attested_credential,
+            Some(AttestedCredential {
+                credential_id: "AQID".to_owned(),
+                public_key: CosePublicKey::parse(cose_key()).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_refuses_other_relying_parties_and_unverified_users() {
+        let other_rp = authenticator_data("evil.example.com", USER_PRESENT | USER_VERIFIED, 0);
+        assert!(AuthenticatorData::parse(&other_rp).is_err());
+
+        let unverified = authenticator_data(&WEBAUTHN_RP_ID, USER_PRESENT, 0);
+        assert!(AuthenticatorData::parse(&unverified).is_err());
+
+        let truncated = authenticator_data(&WEBAUTHN_RP_ID, USER_PRESENT | USER_VERIFIED, 0);
+        assert!(AuthenticatorData::parse(&truncated[..36]).is_err());
+    }
+
+    #[test]
+    fn test_verify_client_data_checks_type_and_origin() {
+        let data = client_data("webauthn.get", &WEBAUTHN_ORIGIN);
+        assert!(verify_client_data(&data, PasskeyCeremony::Authentication).is_ok());
+        assert!(verify_client_data(&data, PasskeyCeremony::Registration).is_err());
+
+        let data = client_data("webauthn.get", "https://evil.example.com");
+        assert!(verify_client_data(&data, PasskeyCeremony::Authentication).is_err());
+    }
+
+    #[test]
+    fn test_extracts_authenticator_data_from_attestation_object() {
+        let object = Value::Map(vec![
+            (
+                Value::Text("fmt".to_owned()),
+                Value::Text("none".to_owned()),
+            ),
+            (Value::Text("attStmt".to_owned()), Value::Map(vec![])),
+            (
+                Value::Text("authData".to_owned()),
+                Value::Bytes(vec![1, 2, 3]),
+            ),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&object, &mut bytes).unwrap();
+
+        assert_eq!(
+            authenticator_data_from_attestation(&bytes),
+            Ok(vec![1, 2, 3])
+        );
+        assert!(authenticator_data_from_attestation(b"not cbor").is_err());
+    }
+}
@@ -1,8 +1,22 @@
+use std::time::Duration;
+
+use super::models::PasswordRule;
+
 /// Domain-specific errors for the authentication service.
 #[derive(Debug)]
 pub enum AuthAPIError {
-    /// Indicates that the provided password is not valid.
-    InvalidPassword,
+    /// Indicates that the request body is not declared as JSON.
+    UnsupportedMediaType,
+    /// Indicates that the request body is not well-formed JSON.
+    MalformedRequest,
+    /// Indicates that the request body is larger than the service reads.
+    PayloadTooLarge,
+    /// Indicates that the request body does not have the expected shape, and where.
+    UnprocessableContent(Vec<FieldError>),
+    /// Indicates that fields of the request are not valid, and why.
+    InvalidInput(Vec<FieldError>),
+    /// Indicates that the provided password is not valid, and which rules it fails.
+    InvalidPassword(Vec<PasswordRule>),
     /// Indicates that the provided email is not valid.
     InvalidEmail,
     /// Indicates that a user with the given email already exists.
@@ -11,6 +25,18 @@ pub enum AuthAPIError {
     InvalidCredentials,
     /// Indicates that the provided credentials are incorrect.
     IncorrectCredentials,
+    /// Indicates that the 2FA code outlived its lifetime.
+    TwoFACodeExpired,
+    /// Indicates that the 2FA code was invalidated after too many failed attempts.
+    TooManyTwoFAAttempts,
+    /// Indicates that the client sent too many requests, and when it may retry.
+    TooManyRequests { retry_after: Duration },
+    /// Indicates that the account is locked after too many failed logins, and for how long.
+    AccountLocked { retry_after: Duration },
+    /// Indicates that the passkey being registered already is.
+    PasskeyAlreadyRegistered,
+    /// Indicates that the user has yet to follow the link emailed to verify their address.
+    EmailNotVerified,
     /// Indicates that the provided token is missing.
     MissingToken,
     /// Indicates that the provided token is invalid.
@@ -18,3 +44,37 @@ pub enum AuthAPIError {
     /// Indicates that an unexpected error occurred.
     UnexpectedError,
 }
+
+/// A field of a request that is not valid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldError {
+    /// The name of the field, as sent by the client.
+    pub field: String,
+    /// Why the field is not valid.
+    pub message: String,
+}
+
+/// Errors of the OpenID Connect endpoints, reported with the error codes of RFC 6749
+/// and OpenID Connect Core rather than as `AuthAPIError`s.
+#[derive(Debug, PartialEq)]
+pub enum OAuthError {
+    /// Indicates that a parameter is missing, repeated or malformed.
+    InvalidRequest,
+    /// Indicates that the client is unknown or failed to authenticate.
+    InvalidClient,
+    /// Indicates that the authorization code is invalid, expired, already redeemed, or was
+    /// issued for another client, redirect URI or code verifier.
+    InvalidGrant,
+    /// Indicates that the grant type is not `authorization_code`.
+    UnsupportedGrantType,
+    /// Indicates that the response type is not `code`.
+    UnsupportedResponseType,
+    /// Indicates that the requested scope does not include `openid`.
+    InvalidScope,
+    /// Indicates that the user has not logged in to the auth service.
+    LoginRequired,
+    /// Indicates that the access token is missing, invalid, expired or revoked.
+    InvalidToken,
+    /// Indicates that an unexpected error occurred.
+    ServerError,
+}
@@ -1,14 +1,36 @@
 //! Domain models module
 //!
 
+mod authorization_code;
 mod email;
+mod email_change_token;
 mod login_attempt_id;
+mod oauth_client;
+mod passkey;
 mod password;
+mod password_hash;
+mod password_reset_token;
+mod password_strength;
+mod rate_limit_policy;
+mod recovery_code;
+mod refresh_token;
+mod totp_secret;
 mod two_fa_code;
 mod user;
 
+pub use authorization_code::*;
 pub use email::*;
+pub use email_change_token::*;
 pub use login_attempt_id::*;
+pub use oauth_client::*;
+pub use passkey::*;
 pub use password::*;
+pub use password_hash::*;
+pub use password_reset_token::*;
+pub use password_strength::*;
+pub use rate_limit_policy::*;
+pub use recovery_code::*;
+pub use refresh_token::*;
+pub use totp_secret::*;
 pub use two_fa_code::*;
 pub use user::*;
@@ -0,0 +1,143 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use rand::{Rng, distr::Alphanumeric};
+use sha2::{Digest, Sha256};
+
+use super::Email;
+
+// Same entropy as a refresh token, for a credential that lives only seconds.
+const AUTHORIZATION_CODE_LENGTH: usize = 43;
+
+/// A single-use code handed to a client through its redirect URI, which it trades for tokens.
+/// Stores only keep its hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorizationCode(String);
+
+impl AuthorizationCode {
+    /// Parses a code sent back by a client.
+    pub fn parse(code: String) -> Result<Self, String> {
+        if code.len() == AUTHORIZATION_CODE_LENGTH
+            && code.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(code))
+        } else {
+            Err("Invalid authorization code".to_owned())
+        }
+    }
+
+    /// The hex-encoded SHA-256 hash under which the code is stored.
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl Default for AuthorizationCode {
+    /// Generates a new random code.
+    fn default() -> Self {
+        let code = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(AUTHORIZATION_CODE_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(code)
+    }
+}
+
+impl AsRef<str> for AuthorizationCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The PKCE code challenge of an authorization request (RFC 7636). Only the `S256` method is
+/// supported: the challenge is the base64url SHA-256 of a verifier only the client knows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeChallenge(String);
+
+impl CodeChallenge {
+    /// Parses an `S256` challenge, which is always 43 base64url characters.
+    pub fn parse(challenge: String) -> Result<Self, String> {
+        match URL_SAFE_NO_PAD.decode(&challenge) {
+            Ok(digest) if digest.len() == 32 => Ok(Self(challenge)),
+            _ => Err("Invalid code challenge".to_owned()),
+        }
+    }
+
+    /// Checks the verifier presented with the code against the challenge.
+    pub fn verify(&self, code_verifier: &str) -> bool {
+        // 43 to 128 unreserved characters (RFC 7636, section 4.1).
+        let well_formed = (43..=128).contains(&code_verifier.len())
+            && code_verifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+        well_formed && URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())) == self.0
+    }
+}
+
+impl AsRef<str> for CodeChallenge {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What a store keeps about an issued authorization code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorizationCodeRecord {
+    /// The client the code was issued to.
+    pub client_id: String,
+    /// The redirect URI of the authorization request, which the token request must repeat.
+    pub redirect_uri: String,
+    /// The user who authorized the client.
+    pub email: Email,
+    /// The space-separated scopes that were granted.
+    pub scope: String,
+    /// Echoed in the ID token, so the client can tie it to its authorization request.
+    pub nonce: Option<String>,
+    pub code_challenge: CodeChallenge,
+    /// The code is refused after this time.
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of RFC 7636, appendix B.
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_default_codes_parse_and_differ() {
+        let code = AuthorizationCode::default();
+        assert_eq!(
+            AuthorizationCode::parse(code.as_ref().to_owned()),
+            Ok(code.clone())
+        );
+        assert_ne!(code, AuthorizationCode::default());
+        assert!(AuthorizationCode::parse("a".repeat(42)).is_err());
+    }
+
+    #[test]
+    fn test_challenge_accepts_its_verifier_only() {
+        let challenge = CodeChallenge::parse(CHALLENGE.to_owned()).unwrap();
+        assert!(challenge.verify(VERIFIER));
+        assert!(!challenge.verify(&VERIFIER.replace('d', "e")));
+        assert!(!challenge.verify(CHALLENGE));
+    }
+
+    #[test]
+    fn test_challenge_rejects_malformed_verifiers() {
+        // A short verifier is refused even when it matches the challenge.
+        let short = "abc";
+        let challenge =
+            CodeChallenge::parse(URL_SAFE_NO_PAD.encode(Sha256::digest(short.as_bytes()))).unwrap();
+        assert!(!challenge.verify(short));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_challenges() {
+        assert!(CodeChallenge::parse("".to_owned()).is_err());
+        assert!(CodeChallenge::parse(VERIFIER.to_owned() + "a").is_err());
+        assert!(CodeChallenge::parse("plain-text-challenge".to_owned()).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use rand::{Rng, distr::Alphanumeric};
+use sha2::{Digest, Sha256};
+
+// 43 alphanumeric characters carry a little over 256 bits of entropy.
+const EMAIL_CHANGE_TOKEN_LENGTH: usize = 43;
+
+/// An opaque token carried by the link confirming an email change. Stores only keep its
+/// hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmailChangeToken(String);
+
+impl EmailChangeToken {
+    /// Parses an email change token sent back from a link.
+    pub fn parse(token: String) -> Result<Self, String> {
+        if token.len() == EMAIL_CHANGE_TOKEN_LENGTH
+            && token.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(token))
+        } else {
+            Err("Invalid email change token".to_owned())
+        }
+    }
+
+    /// The hex-encoded SHA-256 hash under which the token is stored.
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl Default for EmailChangeToken {
+    /// Generates a new random token.
+    fn default() -> Self {
+        let token = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(EMAIL_CHANGE_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(token)
+    }
+}
+
+impl AsRef<str> for EmailChangeToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tokens_parse_and_differ() {
+        let token = EmailChangeToken::default();
+        assert_eq!(
+            EmailChangeToken::parse(token.as_ref().to_owned()),
+            Ok(token.clone())
+        );
+        assert_ne!(token, EmailChangeToken::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_tokens() {
+        assert!(EmailChangeToken::parse("".to_owned()).is_err());
+        assert!(EmailChangeToken::parse("a".repeat(44)).is_err());
+        assert!(EmailChangeToken::parse(format!("{}!", "a".repeat(42))).is_err());
+    }
+}
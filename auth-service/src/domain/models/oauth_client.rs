@@ -0,0 +1,89 @@
+use sha2::{Digest, Sha256};
+
+/// A third-party application registered to sign users in through the OpenID Connect flow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthClient {
+    /// The public identifier the client sends with every request.
+    pub client_id: String,
+    /// A human-readable name for the application.
+    pub name: String,
+    /// The only URIs authorization responses may be sent to, compared exactly.
+    pub redirect_uris: Vec<String>,
+    /// The hex-encoded SHA-256 hash of the client secret. Public clients, such as
+    /// single-page or native apps, have none and rely on PKCE alone.
+    pub client_secret_hash: Option<String>,
+}
+
+impl OAuthClient {
+    pub fn new(
+        client_id: String,
+        name: String,
+        redirect_uris: Vec<String>,
+        client_secret: Option<&str>,
+    ) -> Self {
+        Self {
+            client_id,
+            name,
+            redirect_uris,
+            // Client secrets are long random strings, so a fast hash is enough.
+            client_secret_hash: client_secret.map(hash_secret),
+        }
+    }
+
+    /// Checks that authorization responses may be sent to `redirect_uri`.
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    /// Checks the secret presented at the token endpoint. A public client must not present one.
+    pub fn authenticate(&self, client_secret: Option<&str>) -> bool {
+        match (&self.client_secret_hash, client_secret) {
+            (Some(hash), Some(secret)) => *hash == hash_secret(secret),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(client_secret: Option<&str>) -> OAuthClient {
+        OAuthClient::new(
+            "client".to_owned(),
+            "Client".to_owned(),
+            vec!["https://app.example.com/callback".to_owned()],
+            client_secret,
+        )
+    }
+
+    #[test]
+    fn test_redirect_uris_match_exactly() {
+        let client = client(None);
+        assert!(client.allows_redirect_uri("https://app.example.com/callback"));
+        assert!(!client.allows_redirect_uri("https://app.example.com/callback/"));
+        assert!(!client.allows_redirect_uri("https://app.example.com/callback?next=/"));
+        assert!(!client.allows_redirect_uri("https://evil.example.com/callback"));
+    }
+
+    #[test]
+    fn test_confidential_client_needs_its_secret() {
+        let client = client(Some("s3cr3t"));
+        assert_ne!(client.client_secret_hash.as_deref(), Some("s3cr3t"));
+        assert!(client.authenticate(Some("s3cr3t")));
+        assert!(!client.authenticate(Some("wrong")));
+        assert!(!client.authenticate(None));
+    }
+
+    #[test]
+    fn test_public_client_has_no_secret() {
+        let client = client(None);
+        assert!(client.authenticate(None));
+        assert!(!client.authenticate(Some("s3cr3t")));
+    }
+}
@@ -0,0 +1,331 @@
+use aws_lc_rs::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use ciborium::Value;
+use rand::Rng;
+
+use super::Email;
+
+// WebAuthn asks for at least 16 random bytes.
+const CHALLENGE_LENGTH: usize = 32;
+
+// COSE_Key labels (RFC 9052, section 7). The meaning of the negative ones depends on the key type.
+const KEY_TYPE: i128 = 1;
+const ALGORITHM: i128 = 3;
+const CURVE_OR_MODULUS: i128 = -1;
+const X_OR_EXPONENT: i128 = -2;
+const Y: i128 = -3;
+
+/// The COSE algorithm identifiers of the supported passkey signatures, in order of
+/// preference: ES256, EdDSA (Ed25519) and RS256.
+pub const PASSKEY_ALGORITHMS: [i64; 3] = [-7, -8, -257];
+
+/// A passkey registered to a user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasskeyCredential {
+    /// The ID the authenticator assigned to the credential, base64url-encoded.
+    pub credential_id: String,
+    pub email: Email,
+    pub public_key: CosePublicKey,
+    /// The signature counter of the latest authentication. Authenticators that keep no
+    /// counter, as most synced passkeys, always report 0.
+    pub sign_count: u32,
+}
+
+/// The public key of a passkey, in the COSE_Key encoding (RFC 9053) authenticators
+/// attest it in. Only the algorithms of [`PASSKEY_ALGORITHMS`] are accepted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CosePublicKey(Vec<u8>);
+
+impl CosePublicKey {
+    /// Parses a CBOR-encoded COSE_Key.
+    pub fn parse(bytes: Vec<u8>) -> Result<Self, String> {
+        decode(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    /// Checks a signature the authenticator produced over `message`.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match decode(&self.0) {
+            Ok(PublicKey::Es256(point)) => {
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point)
+                    .verify(message, signature)
+                    .is_ok()
+            }
+            Ok(PublicKey::Ed25519(key)) => UnparsedPublicKey::new(&signature::ED25519, key)
+                .verify(message, signature)
+                .is_ok(),
+            Ok(PublicKey::Rs256 { n, e }) => RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl AsRef<[u8]> for CosePublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// A public key in the form aws-lc-rs verifies signatures with.
+enum PublicKey {
+    /// An uncompressed P-256 point.
+    Es256(Vec<u8>),
+    Ed25519(Vec<u8>),
+    Rs256 {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+}
+
+fn decode(bytes: &[u8]) -> Result<PublicKey, String> {
+    let invalid = || "Invalid COSE public key".to_owned();
+    let fields = ciborium::from_reader::<Value, _>(bytes)
+        .ok()
+        .and_then(|value| value.into_map().ok())
+        .ok_or_else(invalid)?;
+
+    let field = |label: i128| {
+        fields
+            .iter()
+            .find(|(key, _)| key.as_integer().map(i128::from) == Some(label))
+            .map(|(_, value)| value)
+    };
+    let integer = |label| field(label).and_then(Value::as_integer).map(i128::from);
+    let bytes = |label, len: usize| {
+        field(label)
+            .and_then(Value::as_bytes)
+            .filter(|bytes| len == 0 || bytes.len() == len)
+            .cloned()
+            .ok_or_else(invalid)
+    };
+
+    // Key types and curves are from the IANA COSE registries: EC2 on P-256, OKP on
+    // Ed25519, and RSA.
+    match (
+        integer(KEY_TYPE),
+        integer(ALGORITHM),
+        integer(CURVE_OR_MODULUS),
+    ) {
+        (Some(2), Some(-7), Some(1)) => {
+            let mut point = vec![0x04];
+            point.extend(bytes(X_OR_EXPONENT, 32)?);
+            point.extend(bytes(Y, 32)?);
+            Ok(PublicKey::Es256(point))
+        }
+        (Some(1), Some(-8), Some(6)) => Ok(PublicKey::Ed25519(bytes(X_OR_EXPONENT, 32)?)),
+        (Some(3), Some(-257), None) => Ok(PublicKey::Rs256 {
+            n: bytes(CURVE_OR_MODULUS, 0)?,
+            e: bytes(X_OR_EXPONENT, 0)?,
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+/// A random challenge the authenticator signs during a WebAuthn ceremony, which proves its
+/// response is fresh. It travels base64url-encoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasskeyChallenge(String);
+
+impl PasskeyChallenge {
+    /// Parses a challenge echoed back in the client data of a ceremony.
+    pub fn parse(challenge: String) -> Result<Self, String> {
+        match URL_SAFE_NO_PAD.decode(&challenge) {
+            Ok(bytes) if bytes.len() == CHALLENGE_LENGTH => Ok(Self(challenge)),
+            _ => Err("Invalid passkey challenge".to_owned()),
+        }
+    }
+}
+
+impl Default for PasskeyChallenge {
+    /// Generates a new random challenge.
+    fn default() -> Self {
+        let mut bytes = [0u8; CHALLENGE_LENGTH];
+        rand::rng().fill(&mut bytes);
+        Self(URL_SAFE_NO_PAD.encode(bytes))
+    }
+}
+
+impl AsRef<str> for PasskeyChallenge {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The WebAuthn ceremony a challenge was issued for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasskeyCeremony {
+    Registration,
+    Authentication,
+}
+
+impl PasskeyCeremony {
+    /// The name under which stores keep the ceremony.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Authentication => "authentication",
+        }
+    }
+
+    /// Parses a stored ceremony name.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "registration" => Ok(Self::Registration),
+            "authentication" => Ok(Self::Authentication),
+            _ => Err("Invalid passkey ceremony".to_owned()),
+        }
+    }
+}
+
+/// What a store keeps about an issued challenge until the ceremony completes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasskeyChallengeRecord {
+    pub ceremony: PasskeyCeremony,
+    /// The user registering a passkey. Authentication challenges belong to no user yet:
+    /// the passkey used tells who is logging in.
+    pub email: Option<Email>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_lc_rs::{
+        rand::SystemRandom,
+        rsa::KeySize,
+        signature::{
+            ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, Ed25519KeyPair, KeyPair,
+            RSA_PKCS1_SHA256, RsaKeyPair,
+        },
+    };
+
+    use super::*;
+
+    fn cose_key(fields: Vec<(i64, Value)>) -> Vec<u8> {
+        let map = fields
+            .into_iter()
+            .map(|(label, value)| (Value::Integer(label.into()), value))
+            .collect();
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&Value::Map(map), &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_verifies_es256_signatures() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref()).unwrap();
+        let point = key_pair.public_key().as_ref();
+        let key = CosePublicKey::parse(cose_key(vec![
+            (1, Value::Integer(2.into())),
+            (3, Value::Integer((-7).into())),
+            (-1, Value::Integer(1.into())),
+            (-2, Value::Bytes(point[1..33].to_vec())),
+            (-3, Value::Bytes(point[33..].to_vec())),
+        ]))
+        .unwrap();
+
+        let signature = key_pair.sign(&rng, b"message").unwrap();
+        assert!(key.verify(b"message", signature.as_ref()));
+        assert!(!key.verify(b"other message", signature.as_ref()));
+    }
+
+    #[test]
+    fn test_verifies_ed25519_signatures() {
+        let key_pair = Ed25519KeyPair::generate().unwrap();
+        let key = CosePublicKey::parse(cose_key(vec![
+            (1, Value::Integer(1.into())),
+            (3, Value::Integer((-8).into())),
+            (-1, Value::Integer(6.into())),
+            (-2, Value::Bytes(key_pair.public_key().as_ref().to_vec())),
+        ]))
+        .unwrap();
+
+        let signature = key_pair.sign(b"message");
+        assert!(key.verify(b"message", signature.as_ref()));
+        assert!(!key.verify(b"other message", signature.as_ref()));
+    }
+
+    #[test]
+    fn test_verifies_rs256_signatures() {
+        let key_pair = RsaKeyPair::generate(KeySize::Rsa2048).unwrap();
+        let public_key = key_pair.public_key();
+        let key = CosePublicKey::parse(cose_key(vec![
+            (1, Value::Integer(3.into())),
+            (3, Value::Integer((-257).into())),
+            (
+                -1,
+                Value::Bytes(
+                    public_key
+                        .modulus()
+                        .big_endian_without_leading_zero()
+                        .to_vec(),
+                ),
+            ),
+            (
+                -2,
+                Value::Bytes(
+                    public_key
+                        .exponent()
+                        .big_endian_without_leading_zero()
+                        .to_vec(),
+                ),
+            ),
+        ]))
+        .unwrap();
+
+        let mut signature = vec![0; key_pair.public_modulus_len()];
+        key_pair
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                b"message",
+                &mut signature,
+            )
+            .unwrap();
+        assert!(key.verify(b"message", &signature));
+        assert!(!key.verify(b"other message", &signature));
+    }
+
+    #[test]
+    fn test_parse_rejects_unsupported_keys() {
+        // An EC2 key on P-384.
+        assert!(
+            CosePublicKey::parse(cose_key(vec![
+                (1, Value::Integer(2.into())),
+                (3, Value::Integer((-35).into())),
+                (-1, Value::Integer(2.into())),
+                (-2, Value::Bytes(vec![0; 48])),
+                (-3, Value::Bytes(vec![0; 48])),
+            ]))
+            .is_err()
+        );
+        // A P-256 key with a truncated coordinate.
+        assert!(
+            CosePublicKey::parse(cose_key(vec![
+                (1, Value::Integer(2.into())),
+                (3, Value::Integer((-7).into())),
+                (-1, Value::Integer(1.into())),
+                (-2, Value::Bytes(vec![0; 31])),
+                (-3, Value::Bytes(vec![0; 32])),
+            ]))
+            .is_err()
+        );
+        assert!(CosePublicKey::parse(b"not cbor".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_default_challenges_parse_and_differ() {
+        let challenge = PasskeyChallenge::default();
+        assert_eq!(
+            PasskeyChallenge::parse(challenge.as_ref().to_owned()),
+            Ok(challenge.clone())
+        );
+        assert_ne!(challenge, PasskeyChallenge::default());
+        assert!(PasskeyChallenge::parse("c2hvcnQ".to_owned()).is_err());
+    }
+}
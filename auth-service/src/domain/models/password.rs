@@ -1,22 +1,36 @@
+use std::fmt;
+
+use super::{Email, PasswordStrength};
 use crate::domain::error::*;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Password(String);
 
 impl Password {
-    /// Parses a string into an Email.
-    /// Returns an error if the string is not a valid email address.
-    pub fn parse(s: &str) -> Result<Self, AuthAPIError> {
-        if validate_password(s) {
+    /// Parses a password, which must be within the length bounds of `policy`.
+    /// Returns an error naming the failed rule otherwise. Passwords users choose are
+    /// checked against the whole policy with [`PasswordPolicy::check`] instead.
+    pub fn parse(s: &str, policy: &PasswordPolicy) -> Result<Self, AuthAPIError> {
+        let violations = policy.length_violations(s);
+        if violations.is_empty() {
             Ok(Password(s.to_string()))
         } else {
-            Err(AuthAPIError::InvalidPassword)
+            Err(AuthAPIError::InvalidPassword(violations))
         }
     }
-}
 
-fn validate_password(s: &str) -> bool {
-    s.len() >= 8
+    /// Parses a password given to prove who the user is, which is only held to the maximum
+    /// length of `policy` so that hashing it stays bounded. Whether it is right is for the
+    /// stored hash to tell, so passwords chosen under an older policy keep working.
+    pub fn parse_existing(s: &str, policy: &PasswordPolicy) -> Result<Self, AuthAPIError> {
+        if s.chars().count() > policy.max_length {
+            Err(AuthAPIError::InvalidPassword(vec![
+                PasswordRule::MaxLength(policy.max_length),
+            ]))
+        } else {
+            Ok(Password(s.to_string()))
+        }
+    }
 }
 
 impl AsRef<str> for Password {
@@ -24,22 +38,292 @@ impl AsRef<str> for Password {
         &self.0
     }
 }
+
+/// The rules new passwords must follow. Lengths are counted in characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// Bounds the work of hashing, which login attempts pay as well.
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    /// Requires a character that is neither a letter nor a digit.
+    pub require_symbol: bool,
+    /// The longest run of one repeated character allowed, or 0 for no limit.
+    pub max_repeated_chars: usize,
+    /// Refuses passwords containing the local part of the user's email address.
+    pub disallow_email_local_part: bool,
+    /// The lowest [`PasswordStrength`] score accepted, from 0, accepting any, to 4.
+    pub min_strength: u8,
+}
+
+impl Default for PasswordPolicy {
+    /// Length rules only, as NIST SP 800-63B recommends over composition rules.
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            max_repeated_chars: 0,
+            disallow_email_local_part: true,
+            min_strength: 0,
+        }
+    }
+}
+
+// Local parts shorter than this are too common to be worth refusing.
+const MIN_EMAIL_LOCAL_PART_LENGTH: usize = 3;
+
+impl PasswordPolicy {
+    /// Checks a password the user with this email chose, and returns it when it follows
+    /// every rule. Otherwise returns an error listing all the rules it fails. Without the
+    /// email, the rule on its local part is skipped.
+    pub fn check(&self, s: &str, email: Option<&Email>) -> Result<Password, AuthAPIError> {
+        let violations = self.violations(s, email);
+        if violations.is_empty() {
+            Ok(Password(s.to_string()))
+        } else {
+            Err(AuthAPIError::InvalidPassword(violations))
+        }
+    }
+
+    /// Lists the rules a password the user with this email chose fails.
+    pub fn violations(&self, s: &str, email: Option<&Email>) -> Vec<PasswordRule> {
+        let mut violations = self.length_violations(s);
+        let missing =
+            |required: bool, matches: fn(char) -> bool| required && !s.chars().any(matches);
+        if missing(self.require_lowercase, char::is_lowercase) {
+            violations.push(PasswordRule::Lowercase);
+        }
+        if missing(self.require_uppercase, char::is_uppercase) {
+            violations.push(PasswordRule::Uppercase);
+        }
+        if missing(self.require_digit, |c| c.is_ascii_digit()) {
+            violations.push(PasswordRule::Digit);
+        }
+        if missing(self.require_symbol, |c| !c.is_alphanumeric()) {
+            violations.push(PasswordRule::Symbol);
+        }
+        if self.max_repeated_chars > 0 && longest_run(s) > self.max_repeated_chars {
+            violations.push(PasswordRule::RepeatedChars(self.max_repeated_chars));
+        }
+        if let Some(email) = email.filter(|_| self.disallow_email_local_part) {
+            let local_part = email.as_ref().split('@').next().unwrap_or_default();
+            if local_part.chars().count() >= MIN_EMAIL_LOCAL_PART_LENGTH
+                && s.to_lowercase().contains(&local_part.to_lowercase())
+            {
+                violations.push(PasswordRule::EmailLocalPart);
+            }
+        }
+        if self.min_strength > 0 && Self::strength(s, email).score < self.min_strength {
+            violations.push(PasswordRule::Strength(self.min_strength));
+        }
+        violations
+    }
+
+    /// Estimates the strength of a password the user with this email chose, the words of
+    /// their email address being among the first an attacker would try.
+    pub fn strength(s: &str, email: Option<&Email>) -> PasswordStrength {
+        let local_part = email
+            .map(|email| email.as_ref().split('@').next().unwrap_or_default())
+            .unwrap_or_default();
+        let mut user_inputs = vec![local_part];
+        user_inputs.extend(local_part.split(|c: char| !c.is_alphanumeric()));
+        PasswordStrength::estimate(s, &user_inputs)
+    }
+
+    fn length_violations(&self, s: &str) -> Vec<PasswordRule> {
+        let length = s.chars().count();
+        if length < self.min_length {
+            vec![PasswordRule::MinLength(self.min_length)]
+        } else if length > self.max_length {
+            vec![PasswordRule::MaxLength(self.max_length)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+// The length of the longest run of one repeated character.
+fn longest_run(s: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in s.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+/// A password rule, as reported when a password fails it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PasswordRule {
+    /// The password has fewer characters than this.
+    MinLength(usize),
+    /// The password has more characters than this.
+    MaxLength(usize),
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+    /// The password repeats a character more than this many times in a row.
+    RepeatedChars(usize),
+    EmailLocalPart,
+    /// The password's strength scores below this.
+    Strength(u8),
+    /// The password appears in a known data breach.
+    Breached,
+}
+
+impl PasswordRule {
+    /// The stable code clients can match the rule on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MinLength(_) => "min_length",
+            Self::MaxLength(_) => "max_length",
+            Self::Lowercase => "lowercase",
+            Self::Uppercase => "uppercase",
+            Self::Digit => "digit",
+            Self::Symbol => "symbol",
+            Self::RepeatedChars(_) => "max_repeated_chars",
+            Self::EmailLocalPart => "email_local_part",
+            Self::Strength(_) => "min_strength",
+            Self::Breached => "breached",
+        }
+    }
+}
+
+impl fmt::Display for PasswordRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinLength(length) => write!(f, "Must be at least {length} characters long"),
+            Self::MaxLength(length) => write!(f, "Must be at most {length} characters long"),
+            Self::Lowercase => write!(f, "Must contain a lowercase letter"),
+            Self::Uppercase => write!(f, "Must contain an uppercase letter"),
+            Self::Digit => write!(f, "Must contain a digit"),
+            Self::Symbol => write!(f, "Must contain a character other than a letter or digit"),
+            Self::RepeatedChars(count) => {
+                write!(
+                    f,
+                    "Must not repeat a character more than {count} times in a row"
+                )
+            }
+            Self::EmailLocalPart => write!(f, "Must not contain your email address"),
+            Self::Strength(score) => {
+                write!(
+                    f,
+                    "Must be harder to guess, scoring at least {score} out of 4"
+                )
+            }
+            Self::Breached => write!(f, "Appears in a known data breach"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Password;
+    use super::{Password, PasswordPolicy, PasswordRule};
+    use crate::domain::{error::AuthAPIError, models::Email};
 
     use fake::Fake;
     use fake::faker::internet::en::Password as FakePassword;
 
+    fn email() -> Email {
+        Email::parse("jane.doe@example.com").unwrap()
+    }
+
     #[test]
     fn empty_string_is_rejected() {
         let password = "";
-        assert!(Password::parse(password).is_err());
+        assert!(Password::parse(password, &PasswordPolicy::default()).is_err());
     }
     #[test]
     fn string_less_than_8_characters_is_rejected() {
         let password = "1234567";
-        assert!(Password::parse(password).is_err());
+        assert!(Password::parse(password, &PasswordPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn lengths_are_counted_in_characters() {
+        let policy = PasswordPolicy {
+            max_length: 8,
+            ..Default::default()
+        };
+        assert!(Password::parse("éééééééé", &policy).is_ok());
+        assert!(matches!(
+            Password::parse("123456789", &policy),
+            Err(AuthAPIError::InvalidPassword(rules)) if rules == [PasswordRule::MaxLength(8)]
+        ));
+    }
+
+    #[test]
+    fn existing_passwords_are_only_checked_for_their_maximum_length() {
+        let policy = PasswordPolicy {
+            max_length: 8,
+            require_digit: true,
+            ..Default::default()
+        };
+        assert!(Password::parse_existing("abc", &policy).is_ok());
+        assert!(matches!(
+            Password::parse_existing("123456789", &policy),
+            Err(AuthAPIError::InvalidPassword(rules)) if rules == [PasswordRule::MaxLength(8)]
+        ));
+    }
+
+    #[test]
+    fn every_failed_rule_is_listed() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            max_repeated_chars: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.violations("JANE.DOEaaa", Some(&email())),
+            [
+                PasswordRule::MinLength(12),
+                PasswordRule::Digit,
+                PasswordRule::RepeatedChars(2),
+                PasswordRule::EmailLocalPart,
+            ]
+        );
+        assert_eq!(policy.violations("Correct-horse-9", Some(&email())), []);
+    }
+
+    #[test]
+    fn short_email_local_parts_are_allowed() {
+        let email = Email::parse("jo@example.com").unwrap();
+        assert!(
+            PasswordPolicy::default()
+                .check("jo-password", Some(&email))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn weak_passwords_are_rejected_below_the_minimum_strength() {
+        let policy = PasswordPolicy {
+            min_strength: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.violations("password123", None),
+            [PasswordRule::Strength(3)]
+        );
+        assert_eq!(
+            policy.violations("doejane2024", Some(&email())),
+            [PasswordRule::Strength(3)]
+        );
+        assert!(policy.check("correct horse battery staple", None).is_ok());
     }
 
     #[derive(Debug, Clone)]
@@ -53,6 +337,6 @@ mod tests {
     }
     #[quickcheck_macros::quickcheck]
     fn valid_passwords_are_parsed_successfully(valid_password: ValidPasswordFixture) -> bool {
-        Password::parse(&valid_password.0).is_ok()
+        Password::parse(&valid_password.0, &PasswordPolicy::default()).is_ok()
     }
 }
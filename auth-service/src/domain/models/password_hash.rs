@@ -0,0 +1,242 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version,
+    password_hash::{self, SaltString, rand_core::OsRng},
+};
+
+use super::Password;
+
+/// The Argon2id cost parameters used when hashing new passwords.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashingPolicy {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of iterations (time cost).
+    pub iterations: u32,
+    /// Degree of parallelism (lanes).
+    pub parallelism: u32,
+}
+
+impl Default for HashingPolicy {
+    /// OWASP recommended minimum for Argon2id: 19 MiB, 2 iterations, 1 lane.
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl HashingPolicy {
+    fn hasher(&self) -> Result<Argon2<'static>, PasswordHashError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|_| PasswordHashError::InvalidPolicy)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// An error that can occur when hashing or verifying a password.
+#[derive(Debug, PartialEq)]
+pub enum PasswordHashError {
+    /// Indicates that the stored hash is not a valid PHC string.
+    InvalidHash,
+    /// Indicates that the hashing policy parameters are out of range.
+    InvalidPolicy,
+    /// Indicates that the password does not match the hash.
+    IncorrectPassword,
+    /// Indicates that an unexpected error occurred.
+    UnexpectedError,
+}
+
+/// An Argon2id password hash, stored as a PHC string
+/// (e.g. `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordHash(String);
+
+impl PasswordHash {
+    /// Parses a PHC string loaded from storage into a PasswordHash.
+    /// Returns an error if the string is not a valid PHC string.
+    pub fn parse(s: String) -> Result<Self, PasswordHashError> {
+        password_hash::PasswordHash::new(&s).map_err(|_| PasswordHashError::InvalidHash)?;
+        Ok(Self(s))
+    }
+
+    /// Hashes the password with Argon2id and a random salt.
+    /// The work is done on the blocking thread pool to keep the async runtime responsive.
+    pub async fn compute(
+        password: &Password,
+        policy: &HashingPolicy,
+    ) -> Result<Self, PasswordHashError> {
+        let password = password.as_ref().to_owned();
+        let policy = *policy;
+
+        tokio::task::spawn_blocking(move || hash_password(&password, &policy).map(Self))
+            .await
+            .map_err(|_| PasswordHashError::UnexpectedError)?
+    }
+
+    /// Verifies the password against this hash using the parameters encoded in it.
+    /// The digest comparison is constant-time.
+    pub async fn verify(&self, password: &Password) -> Result<(), PasswordHashError> {
+        let password = password.as_ref().to_owned();
+        let phc = self.0.clone();
+
+        tokio::task::spawn_blocking(move || verify_password(&password, &phc))
+            .await
+            .map_err(|_| PasswordHashError::UnexpectedError)?
+    }
+
+    /// Verifies the password against a hash of no one's password, taking as long as
+    /// [`Self::verify`] against a hash made under the policy. Checking the password of an
+    /// account that does not exist this way does not reveal that it does not.
+    pub async fn verify_dummy(password: &Password, policy: &HashingPolicy) {
+        static DUMMY_HASH: OnceLock<Result<String, PasswordHashError>> = OnceLock::new();
+
+        let password = password.as_ref().to_owned();
+        let policy = *policy;
+
+        let _ = tokio::task::spawn_blocking(move || {
+            if let Ok(phc) = DUMMY_HASH.get_or_init(|| hash_password("dummy password", &policy)) {
+                let _ = verify_password(&password, phc);
+            }
+        })
+        .await;
+    }
+
+    /// Returns true if this hash was produced with a different algorithm or with
+    /// parameters weaker than the given policy.
+    pub fn needs_rehash(&self, policy: &HashingPolicy) -> bool {
+        let Ok(hash) = password_hash::PasswordHash::new(&self.0) else {
+            return true;
+        };
+        if hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+        match Params::try_from(&hash) {
+            Ok(params) => {
+                params.m_cost() < policy.memory_kib
+                    || params.t_cost() < policy.iterations
+                    || params.p_cost() < policy.parallelism
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+impl AsRef<str> for PasswordHash {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+fn hash_password(password: &str, policy: &HashingPolicy) -> Result<String, PasswordHashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    policy
+        .hasher()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| PasswordHashError::UnexpectedError)
+}
+
+fn verify_password(password: &str, phc: &str) -> Result<(), PasswordHashError> {
+    let hash = password_hash::PasswordHash::new(phc).map_err(|_| PasswordHashError::InvalidHash)?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .map_err(|e| match e {
+            password_hash::Error::Password => PasswordHashError::IncorrectPassword,
+            _ => PasswordHashError::UnexpectedError,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::PasswordPolicy;
+
+    fn weak_policy() -> HashingPolicy {
+        HashingPolicy {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compute_produces_argon2id_phc_string() {
+        let password = Password::parse("password123", &PasswordPolicy::default()).unwrap();
+        let hash = PasswordHash::compute(&password, &weak_policy())
+            .await
+            .unwrap();
+        assert!(hash.as_ref().starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(!hash.as_ref().contains("password123"));
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let password = Password::parse("password123", &PasswordPolicy::default()).unwrap();
+        let other_password = Password::parse("password124", &PasswordPolicy::default()).unwrap();
+        let hash = PasswordHash::compute(&password, &weak_policy())
+            .await
+            .unwrap();
+        assert_eq!(hash.verify(&password).await, Ok(()));
+        assert_eq!(
+            hash.verify(&other_password).await,
+            Err(PasswordHashError::IncorrectPassword)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_same_password_gets_different_salts() {
+        let password = Password::parse("password123", &PasswordPolicy::default()).unwrap();
+        let first = PasswordHash::compute(&password, &weak_policy())
+            .await
+            .unwrap();
+        let second = PasswordHash::compute(&password, &weak_policy())
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_needs_rehash() {
+        let password = Password::parse("password123", &PasswordPolicy::default()).unwrap();
+        let hash = PasswordHash::compute(&password, &weak_policy())
+            .await
+            .unwrap();
+        assert!(!hash.needs_rehash(&weak_policy()));
+        assert!(hash.needs_rehash(&HashingPolicy::default()));
+        assert!(hash.needs_rehash(&HashingPolicy {
+            iterations: 2,
+            ..weak_policy()
+        }));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(PasswordHash::parse("password123".to_owned()).is_err());
+        assert!(
+            PasswordHash::parse(
+                "$argon2id$v=19$m=1024,t=1,p=1$c29tZXNhbHQ$qLml5cbqFAO6YxVHhrSBHP0UWdxrIxkNcM8aMX3atkw"
+                    .to_owned()
+            )
+            .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_policy_is_rejected() {
+        let password = Password::parse("password123", &PasswordPolicy::default()).unwrap();
+        let policy = HashingPolicy {
+            memory_kib: 0,
+            ..weak_policy()
+        };
+        assert_eq!(
+            PasswordHash::compute(&password, &policy).await,
+            Err(PasswordHashError::InvalidPolicy)
+        );
+    }
+}
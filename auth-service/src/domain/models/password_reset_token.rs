@@ -0,0 +1,67 @@
+use rand::{Rng, distr::Alphanumeric};
+use sha2::{Digest, Sha256};
+
+// 43 alphanumeric characters carry a little over 256 bits of entropy.
+const PASSWORD_RESET_TOKEN_LENGTH: usize = 43;
+
+/// An opaque token carried by a password reset link. Stores only keep its hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordResetToken(String);
+
+impl PasswordResetToken {
+    /// Parses a reset token sent back from a link.
+    pub fn parse(token: String) -> Result<Self, String> {
+        if token.len() == PASSWORD_RESET_TOKEN_LENGTH
+            && token.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            Ok(Self(token))
+        } else {
+            Err("Invalid password reset token".to_owned())
+        }
+    }
+
+    /// The hex-encoded SHA-256 hash under which the token is stored.
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl Default for PasswordResetToken {
+    /// Generates a new random token.
+    fn default() -> Self {
+        let token = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(PASSWORD_RESET_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(token)
+    }
+}
+
+impl AsRef<str> for PasswordResetToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tokens_parse_and_differ() {
+        let token = PasswordResetToken::default();
+        assert_eq!(
+            PasswordResetToken::parse(token.as_ref().to_owned()),
+            Ok(token.clone())
+        );
+        assert_ne!(token, PasswordResetToken::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_tokens() {
+        assert!(PasswordResetToken::parse("".to_owned()).is_err());
+        assert!(PasswordResetToken::parse("a".repeat(44)).is_err());
+        assert!(PasswordResetToken::parse(format!("{}!", "a".repeat(42))).is_err());
+    }
+}
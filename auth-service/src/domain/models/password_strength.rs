@@ -0,0 +1,563 @@
+//! Password strength estimation in the manner of zxcvbn.
+//!
+//! A password is split into the patterns an attacker tries first: common passwords, words
+//! from the user's own details, repeated characters, sequences, rows of keys and years.
+//! Each pattern is worth a number of guesses, and whatever is left over is brute-forced.
+//! The strength is the number of guesses of the cheapest split.
+use std::collections::HashMap;
+
+use chrono::{Datelike, Utc};
+use lazy_static::lazy_static;
+
+// Longer passwords are only estimated on their start, which keeps estimation cheap.
+const MAX_ESTIMATED_LENGTH: usize = 100;
+// Guesses per second an attacker holding the password hashes manages against a slow hash.
+const GUESSES_PER_SECOND: f64 = 1e4;
+// Patterns that are not the whole password count for at least this many guesses, so that
+// splitting a password into many short patterns does not make it look weaker than it is.
+const MIN_SUBMATCH_GUESSES: f64 = 50.0;
+// Years are counted as at least this far from the current one.
+const MIN_YEAR_SPACE: i32 = 20;
+// Guessing each brute-forced character takes this many guesses.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+const KEYBOARD_ROWS: [&str; 4] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+lazy_static! {
+    // Common passwords, by rank, most common first.
+    static ref COMMON_PASSWORDS: HashMap<&'static str, usize> =
+        include_str!("common_passwords.txt")
+            .lines()
+            .enumerate()
+            .map(|(index, password)| (password, index + 1))
+            .collect();
+}
+
+/// How hard a password is to guess, with feedback for the user who chose it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordStrength {
+    /// From 0, guessed within a thousand guesses, to 4, not within ten billion.
+    pub score: u8,
+    /// The base-10 logarithm of the number of guesses needed.
+    pub guesses_log10: f64,
+    /// Why the password is weak, when it is.
+    pub warning: Option<&'static str>,
+    /// How to choose a stronger password.
+    pub suggestions: Vec<&'static str>,
+}
+
+impl PasswordStrength {
+    /// Estimates the strength of a password. `user_inputs` are words an attacker targeting
+    /// the user would try first, such as parts of their email address.
+    pub fn estimate(password: &str, user_inputs: &[&str]) -> Self {
+        let chars: Vec<char> = password.chars().take(MAX_ESTIMATED_LENGTH).collect();
+        let (guesses_log10, matches) = cheapest_split(&chars, user_inputs);
+        let score = match guesses_log10 {
+            g if g < 3.0 => 0,
+            g if g < 6.0 => 1,
+            g if g < 8.0 => 2,
+            g if g < 10.0 => 3,
+            _ => 4,
+        };
+        let (warning, suggestions) = feedback(score, chars.len(), &matches);
+        Self {
+            score,
+            guesses_log10,
+            warning,
+            suggestions,
+        }
+    }
+
+    /// How long an attacker holding the password hash would take to guess the password.
+    pub fn crack_time_seconds(&self) -> f64 {
+        10f64.powf(self.guesses_log10) / GUESSES_PER_SECOND
+    }
+
+    /// The crack time in words, such as "3 hours" or "centuries".
+    pub fn crack_time_display(&self) -> String {
+        const MINUTE: f64 = 60.0;
+        const HOUR: f64 = 60.0 * MINUTE;
+        const DAY: f64 = 24.0 * HOUR;
+        const MONTH: f64 = 31.0 * DAY;
+        const YEAR: f64 = 12.0 * MONTH;
+        const CENTURY: f64 = 100.0 * YEAR;
+
+        let seconds = self.crack_time_seconds();
+        let (count, unit) = match seconds {
+            s if s < 1.0 => return "less than a second".to_owned(),
+            s if s < MINUTE => (s, "second"),
+            s if s < HOUR => (s / MINUTE, "minute"),
+            s if s < DAY => (s / HOUR, "hour"),
+            s if s < MONTH => (s / DAY, "day"),
+            s if s < YEAR => (s / MONTH, "month"),
+            s if s < CENTURY => (s / YEAR, "year"),
+            _ => return "centuries".to_owned(),
+        };
+        let count = count.round() as u64;
+        match count {
+            1 => format!("1 {unit}"),
+            _ => format!("{count} {unit}s"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Pattern {
+    Dictionary {
+        rank: usize,
+        user_input: bool,
+        reversed: bool,
+        substituted: bool,
+        capitalized: bool,
+    },
+    Repeat,
+    Sequence,
+    Keyboard,
+    Year,
+    Bruteforce,
+}
+
+// A pattern found in the password, over the characters `start..end`.
+#[derive(Clone, Debug)]
+struct Match {
+    start: usize,
+    end: usize,
+    guesses_log10: f64,
+    pattern: Pattern,
+}
+
+// Finds the split of the password into patterns that takes the fewest guesses, returning
+// its guesses and patterns. A split of `k` patterns takes the product of their guesses
+// times `k!`, for the orders the attacker tries them in.
+fn cheapest_split(chars: &[char], user_inputs: &[&str]) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    let mut matches = find_patterns(chars, user_inputs);
+    for start in 0..n {
+        for end in start + 1..=n {
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: (end - start) as f64 * BRUTEFORCE_CARDINALITY.log10(),
+                pattern: Pattern::Bruteforce,
+            });
+        }
+    }
+    for m in matches.iter_mut() {
+        if m.end - m.start < n {
+            m.guesses_log10 = m.guesses_log10.max(MIN_SUBMATCH_GUESSES.log10());
+        }
+    }
+    let mut ending_at = vec![Vec::new(); n + 1];
+    for (index, m) in matches.iter().enumerate() {
+        ending_at[m.end].push(index);
+    }
+
+    // best[k][i]: the fewest guesses covering the first `i` characters with `k` patterns.
+    let mut best = vec![vec![f64::INFINITY; n + 1]; n + 1];
+    let mut previous = vec![vec![None; n + 1]; n + 1];
+    best[0][0] = 0.0;
+    for end in 1..=n {
+        for &index in &ending_at[end] {
+            let m = &matches[index];
+            for k in 1..=end {
+                let guesses_log10 = best[k - 1][m.start] + m.guesses_log10;
+                if guesses_log10 < best[k][end] {
+                    best[k][end] = guesses_log10;
+                    previous[k][end] = Some(index);
+                }
+            }
+        }
+    }
+
+    let mut factorial_log10 = 0.0;
+    let mut cheapest = (f64::INFINITY, 0);
+    for (k, guesses) in best.iter().enumerate().skip(1) {
+        factorial_log10 += (k as f64).log10();
+        let guesses_log10 = guesses[n] + factorial_log10;
+        if guesses_log10 < cheapest.0 {
+            cheapest = (guesses_log10, k);
+        }
+    }
+
+    let (guesses_log10, mut k) = cheapest;
+    let mut split = Vec::with_capacity(k);
+    let mut end = n;
+    while let Some(index) = previous[k][end] {
+        split.push(matches[index].clone());
+        end = matches[index].start;
+        k -= 1;
+    }
+    split.reverse();
+    (guesses_log10, split)
+}
+
+fn find_patterns(chars: &[char], user_inputs: &[&str]) -> Vec<Match> {
+    let mut matches = dictionary_matches(chars, user_inputs);
+    matches.extend(run_matches(
+        chars,
+        Pattern::Repeat,
+        |a, b| a == b,
+        |run| (cardinality(run[0]) * run.len() as f64).log10(),
+    ));
+    for step in [1, -1] {
+        matches.extend(run_matches(
+            chars,
+            Pattern::Sequence,
+            |a, b| same_class(a, b) && b as i64 - a as i64 == step,
+            |run| {
+                let start = if "aAzZ01".contains(run[0]) {
+                    4.0
+                } else {
+                    cardinality(run[0])
+                };
+                let descending = if step < 0 { 2.0 } else { 1.0 };
+                (start * run.len() as f64 * descending).log10()
+            },
+        ));
+    }
+    for row in KEYBOARD_ROWS {
+        let keys: Vec<char> = row.chars().collect();
+        let position = |c: char| keys.iter().position(|&key| key == c.to_ascii_lowercase());
+        for step in [1, -1] {
+            matches.extend(run_matches(
+                chars,
+                Pattern::Keyboard,
+                |a, b| match (position(a), position(b)) {
+                    (Some(a), Some(b)) => b as i64 - a as i64 == step,
+                    _ => false,
+                },
+                |run| {
+                    let keys = KEYBOARD_ROWS.iter().map(|row| row.len()).sum::<usize>();
+                    (keys as f64 * run.len() as f64).log10()
+                },
+            ));
+        }
+    }
+    matches.extend(year_matches(chars));
+    matches
+}
+
+// Matches common passwords and user inputs, whatever their case, reversed or not, and with
+// digits and symbols standing for the letters they resemble.
+fn dictionary_matches(chars: &[char], user_inputs: &[&str]) -> Vec<Match> {
+    let user_inputs: HashMap<String, usize> = user_inputs
+        .iter()
+        .map(|input| input.to_lowercase())
+        .filter(|input| input.chars().count() >= 3)
+        .enumerate()
+        .map(|(index, input)| (input, index + 1))
+        .collect();
+
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len() {
+            let word = &chars[start..end];
+            let lowercase: String = word.iter().flat_map(|c| c.to_lowercase()).collect();
+            let unsubstituted: String = lowercase.chars().map(unsubstitute).collect();
+            let capitalized = word.iter().any(|c| c.is_uppercase());
+            for reversed in [false, true] {
+                let candidates = [(&lowercase, false), (&unsubstituted, true)];
+                for (candidate, substituted) in candidates {
+                    if substituted && unsubstituted == lowercase {
+                        continue;
+                    }
+                    let candidate: String = match reversed {
+                        true => candidate.chars().rev().collect(),
+                        false => candidate.clone(),
+                    };
+                    let (rank, user_input) = match user_inputs.get(&candidate) {
+                        Some(&rank) => (rank, true),
+                        None => match COMMON_PASSWORDS.get(candidate.as_str()) {
+                            Some(&rank) => (rank, false),
+                            None => continue,
+                        },
+                    };
+                    let mut guesses = rank as f64 * uppercase_variations(word);
+                    if substituted {
+                        guesses *= 2f64.powi(substitutions(&lowercase));
+                    }
+                    if reversed {
+                        guesses *= 2.0;
+                    }
+                    matches.push(Match {
+                        start,
+                        end,
+                        guesses_log10: guesses.log10(),
+                        pattern: Pattern::Dictionary {
+                            rank,
+                            user_input,
+                            reversed,
+                            substituted,
+                            capitalized,
+                        },
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+// Finds the longest runs of at least 3 characters whose neighbours are all `linked`.
+fn run_matches(
+    chars: &[char],
+    pattern: Pattern,
+    linked: impl Fn(char, char) -> bool,
+    guesses_log10: impl Fn(&[char]) -> f64,
+) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    for end in 1..=chars.len() {
+        if end < chars.len() && linked(chars[end - 1], chars[end]) {
+            continue;
+        }
+        if end - start >= 3 {
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: guesses_log10(&chars[start..end]),
+                pattern: pattern.clone(),
+            });
+        }
+        start = end;
+    }
+    matches
+}
+
+fn year_matches(chars: &[char]) -> Vec<Match> {
+    let current_year = Utc::now().year();
+    (0..chars.len().saturating_sub(3))
+        .filter_map(|start| {
+            let digits = &chars[start..start + 4];
+            if !digits.iter().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let year: i32 = digits.iter().collect::<String>().parse().ok()?;
+            (1900..=2099).contains(&year).then(|| Match {
+                start,
+                end: start + 4,
+                guesses_log10: f64::from((year - current_year).abs().max(MIN_YEAR_SPACE)).log10(),
+                pattern: Pattern::Year,
+            })
+        })
+        .collect()
+}
+
+fn unsubstitute(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' | '|' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        c => c,
+    }
+}
+
+fn substitutions(lowercase: &str) -> i32 {
+    lowercase.chars().filter(|&c| unsubstitute(c) != c).count() as i32
+}
+
+// The ways of capitalizing a word an attacker tries before reaching this one: a capital
+// first or last letter, or all capitals, are tried first.
+fn uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_or_last =
+        upper == 1 && (word[0].is_uppercase() || word[word.len() - 1].is_uppercase());
+    if lower == 0 || first_or_last {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|k| binomial(upper + lower, k))
+        .sum()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |product, i| product * (n + 1 - i) as f64 / i as f64)
+}
+
+fn cardinality(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_alphabetic() {
+        26.0
+    } else {
+        33.0
+    }
+}
+
+fn same_class(a: char, b: char) -> bool {
+    (a.is_ascii_digit() && b.is_ascii_digit())
+        || (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+        || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+}
+
+const ADD_WORD: &str = "Add another word or two. Uncommon words are better.";
+
+// Explains a weak password from its longest pattern.
+fn feedback(
+    score: u8,
+    length: usize,
+    matches: &[Match],
+) -> (Option<&'static str>, Vec<&'static str>) {
+    if length == 0 {
+        return (
+            None,
+            vec![
+                "Use a few words, avoid common phrases",
+                "No need for symbols, digits, or uppercase letters",
+            ],
+        );
+    }
+    if score > 2 {
+        return (None, Vec::new());
+    }
+    let longest = matches
+        .iter()
+        .filter(|m| m.pattern != Pattern::Bruteforce)
+        .max_by_key(|m| m.end - m.start);
+    let Some(longest) = longest else {
+        return (None, vec![ADD_WORD]);
+    };
+
+    let mut suggestions = vec![ADD_WORD];
+    let warning = match &longest.pattern {
+        Pattern::Dictionary {
+            rank,
+            user_input,
+            reversed,
+            substituted,
+            capitalized,
+        } => {
+            if *capitalized {
+                suggestions.push("Capitalization doesn't help very much");
+            }
+            if *reversed {
+                suggestions.push("Reversed words aren't much harder to guess");
+            }
+            if *substituted {
+                suggestions
+                    .push("Predictable substitutions like '@' instead of 'a' don't help very much");
+            }
+            let whole = longest.end - longest.start == length;
+            match (user_input, whole, rank) {
+                (true, _, _) => "Contains words from your email address",
+                (false, true, 1..=10) => "This is a top-10 common password",
+                (false, true, 11..=100) => "This is a top-100 common password",
+                (false, true, _) => "This is a very common password",
+                (false, false, _) => "This is similar to a commonly used password",
+            }
+        }
+        Pattern::Repeat => {
+            suggestions.push("Avoid repeated words and characters");
+            "Repeats like \"aaa\" are easy to guess"
+        }
+        Pattern::Sequence => {
+            suggestions.push("Avoid sequences");
+            "Sequences like abc or 6543 are easy to guess"
+        }
+        Pattern::Keyboard => {
+            suggestions.push("Use a longer keyboard pattern with more turns");
+            "Straight rows of keys are easy to guess"
+        }
+        Pattern::Year => {
+            suggestions.push("Avoid recent years and years that are associated with you");
+            "Recent years are easy to guess"
+        }
+        Pattern::Bruteforce => unreachable!("brute-forced characters are filtered out"),
+    };
+    (Some(warning), suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordStrength;
+
+    #[test]
+    fn common_passwords_are_weakest() {
+        let strength = PasswordStrength::estimate("password", &[]);
+        assert_eq!(strength.score, 0);
+        assert_eq!(strength.warning, Some("This is a top-10 common password"));
+        assert_eq!(strength.crack_time_display(), "less than a second");
+    }
+
+    #[test]
+    fn substitutions_and_capitals_barely_help() {
+        let strength = PasswordStrength::estimate("P@ssw0rd", &[]);
+        assert_eq!(strength.score, 0);
+        assert!(
+            strength.suggestions.contains(
+                &"Predictable substitutions like '@' instead of 'a' don't help very much"
+            )
+        );
+        assert!(
+            strength
+                .suggestions
+                .contains(&"Capitalization doesn't help very much")
+        );
+    }
+
+    #[test]
+    fn patterns_are_recognized() {
+        for (password, warning) in [
+            ("aaaaaaaaaaaa", "Repeats like \"aaa\" are easy to guess"),
+            (
+                "abcdefghijkl",
+                "Sequences like abc or 6543 are easy to guess",
+            ),
+            ("qwertyuiop[]", "Straight rows of keys are easy to guess"),
+            ("jane.doe", "Contains words from your email address"),
+        ] {
+            let strength = PasswordStrength::estimate(password, &["jane.doe", "jane", "doe"]);
+            assert!(
+                strength.score <= 1,
+                "Scored {} for {password}",
+                strength.score
+            );
+            assert_eq!(strength.warning, Some(warning), "Failed for {password}");
+        }
+    }
+
+    #[test]
+    fn random_passphrases_are_strongest() {
+        let strength = PasswordStrength::estimate("correct horse battery staple", &[]);
+        assert_eq!(strength.score, 4);
+        assert_eq!(strength.warning, None);
+        assert!(strength.suggestions.is_empty());
+        assert_eq!(strength.crack_time_display(), "centuries");
+    }
+
+    #[test]
+    fn strength_grows_with_length() {
+        let scores: Vec<u8> = ["x7Kq", "x7Kq9vLm", "x7Kq9vLm2pRw"]
+            .iter()
+            .map(|password| PasswordStrength::estimate(password, &[]).score)
+            .collect();
+        assert!(scores.is_sorted(), "{scores:?}");
+        assert!(scores[0] < scores[2]);
+    }
+
+    #[test]
+    fn empty_password_asks_for_words() {
+        let strength = PasswordStrength::estimate("", &[]);
+        assert_eq!(strength.score, 0);
+        assert_eq!(strength.suggestions.len(), 2);
+    }
+}
@@ -0,0 +1,62 @@
+use std::{str::FromStr, time::Duration};
+
+/// A token-bucket quota: up to `capacity` requests at once, refilled evenly over `period`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Maximum number of requests allowed in a burst.
+    pub capacity: u32,
+    /// Time it takes for an empty bucket to refill completely.
+    pub period: Duration,
+}
+
+impl RateLimitPolicy {
+    /// Number of tokens added back to the bucket per second.
+    pub fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl FromStr for RateLimitPolicy {
+    type Err = String;
+
+    /// Parses a quota written as `<capacity>/<period in seconds>`, e.g. `10/60`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rate limit '{s}', expected <capacity>/<seconds>");
+        let (capacity, period) = s.split_once('/').ok_or_else(invalid)?;
+        let capacity: u32 = capacity.trim().parse().map_err(|_| invalid())?;
+        let period: u64 = period.trim().parse().map_err(|_| invalid())?;
+        if capacity == 0 || period == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            capacity,
+            period: Duration::from_secs(period),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "10/60".parse(),
+            Ok(RateLimitPolicy {
+                capacity: 10,
+                period: Duration::from_secs(60),
+            })
+        );
+        assert!("10".parse::<RateLimitPolicy>().is_err());
+        assert!("0/60".parse::<RateLimitPolicy>().is_err());
+        assert!("10/0".parse::<RateLimitPolicy>().is_err());
+        assert!("ten/60".parse::<RateLimitPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_refill_rate() {
+        let policy: RateLimitPolicy = "30/60".parse().unwrap();
+        assert_eq!(policy.refill_rate(), 0.5);
+    }
+}
@@ -0,0 +1,92 @@
+use rand::seq::IndexedRandom;
+use sha2::{Digest, Sha256};
+
+// Lowercase letters and digits, without those easily mistaken for one another (0/o, 1/i/l).
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+// Two groups of 5 characters carry a little over 49 bits of entropy.
+const RECOVERY_CODE_GROUP_LENGTH: usize = 5;
+
+/// A one-time code that stands in for the second factor when the user lost access to it,
+/// formatted as two groups of characters, e.g. `k7m2q-x9fpr`. Stores only keep its hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    /// Parses a recovery code typed in by a user, ignoring case, spaces and the dash
+    /// between the groups.
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let characters = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>();
+        if characters.len() != 2 * RECOVERY_CODE_GROUP_LENGTH
+            || !characters
+                .bytes()
+                .all(|c| RECOVERY_CODE_ALPHABET.contains(&c))
+        {
+            return Err("Invalid recovery code".to_owned());
+        }
+        let (first, second) = characters.split_at(RECOVERY_CODE_GROUP_LENGTH);
+        Ok(Self(format!("{first}-{second}")))
+    }
+
+    /// The hex-encoded SHA-256 hash under which the code is stored.
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl Default for RecoveryCode {
+    /// Generates a new random code.
+    fn default() -> Self {
+        let mut rng = rand::rng();
+        let mut group = || {
+            (0..RECOVERY_CODE_GROUP_LENGTH)
+                .map(|_| char::from(*RECOVERY_CODE_ALPHABET.choose(&mut rng).unwrap()))
+                .collect::<String>()
+        };
+        let first = group();
+        Self(format!("{first}-{}", group()))
+    }
+}
+
+impl AsRef<str> for RecoveryCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_codes_parse_and_differ() {
+        let code = RecoveryCode::default();
+        assert_eq!(code.as_ref().len(), 11);
+        assert_eq!(RecoveryCode::parse(code.as_ref()), Ok(code.clone()));
+        assert_ne!(code, RecoveryCode::default());
+    }
+
+    #[test]
+    fn test_parse_normalizes_typed_codes() {
+        let code = RecoveryCode::parse("k7m2q-x9fpr").unwrap();
+        assert_eq!(RecoveryCode::parse("K7M2QX9FPR"), Ok(code.clone()));
+        assert_eq!(RecoveryCode::parse(" k7m2q x9fpr "), Ok(code.clone()));
+        assert_eq!(
+            code.hash(),
+            RecoveryCode::parse("K7M2Q-X9FPR").unwrap().hash()
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_codes() {
+        assert!(RecoveryCode::parse("").is_err());
+        assert!(RecoveryCode::parse("123456").is_err());
+        assert!(RecoveryCode::parse("k7m2q-x9fp").is_err());
+        assert!(RecoveryCode::parse("k7m2q-x9fpr2").is_err());
+        // Confusable characters are never generated.
+        assert!(RecoveryCode::parse("k7m2q-x9fp0").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use rand::{Rng, distr::Alphanumeric};
+use sha2::{Digest, Sha256};
+
+use super::Email;
+
+// 43 alphanumeric characters carry a little over 256 bits of entropy.
+const REFRESH_TOKEN_LENGTH: usize = 43;
+
+/// An opaque refresh token handed to the client. Stores only keep its hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefreshToken(String);
+
+impl RefreshToken {
+    /// Parses a refresh token sent back by a client.
+    pub fn parse(token: String) -> Result<Self, String> {
+        if token.len() == REFRESH_TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Ok(Self(token))
+        } else {
+            Err("Invalid refresh token".to_owned())
+        }
+    }
+
+    /// The hex-encoded SHA-256 hash under which the token is stored.
+    pub fn hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl Default for RefreshToken {
+    /// Generates a new random token.
+    fn default() -> Self {
+        let token = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(REFRESH_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        Self(token)
+    }
+}
+
+impl AsRef<str> for RefreshToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What a store keeps about an issued refresh token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefreshTokenRecord {
+    /// The user the token was issued to.
+    pub email: Email,
+    /// Shared by every token rotated from the same login.
+    pub family_id: String,
+    /// The token is refused after this time.
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tokens_parse_and_differ() {
+        let token = RefreshToken::default();
+        assert_eq!(
+            RefreshToken::parse(token.as_ref().to_owned()),
+            Ok(token.clone())
+        );
+        assert_ne!(token, RefreshToken::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_tokens() {
+        assert!(RefreshToken::parse("".to_owned()).is_err());
+        assert!(RefreshToken::parse("a".repeat(42)).is_err());
+        assert!(RefreshToken::parse(format!("{}-", "a".repeat(42))).is_err());
+    }
+
+    #[test]
+    fn test_hash_is_stable_and_hides_the_token() {
+        let token = RefreshToken::default();
+        assert_eq!(token.hash(), token.hash());
+        assert_eq!(token.hash().len(), 64);
+        assert_ne!(token.hash(), token.as_ref());
+    }
+}
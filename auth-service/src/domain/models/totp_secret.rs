@@ -0,0 +1,167 @@
+use aws_lc_rs::{constant_time::verify_slices_are_equal, hmac};
+use chrono::{DateTime, Utc};
+use data_encoding::BASE32_NOPAD;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand::Rng;
+
+use super::{Email, TwoFACode};
+
+// 160 bits, the key length recommended for HMAC-SHA1 by RFC 4226.
+const SECRET_LENGTH: usize = 20;
+// RFC 4226 requires at least 128 bits.
+const MIN_SECRET_LENGTH: usize = 16;
+const DIGITS: u32 = 6;
+
+/// How long each TOTP code is valid, the default of RFC 6238 that authenticator apps assume.
+pub const TOTP_STEP_SECONDS: u64 = 30;
+
+/// The secret an authenticator app shares with the service to generate TOTP codes
+/// (RFC 6238, HMAC-SHA1, 6 digits, 30-second steps). It is kept in its unpadded base32
+/// form, which is also what users type into their app.
+#[derive(Clone, PartialEq)]
+pub struct TotpSecret(String);
+
+impl TotpSecret {
+    /// Parses a stored base32 secret.
+    pub fn parse(secret: String) -> Result<Self, String> {
+        match BASE32_NOPAD.decode(secret.as_bytes()) {
+            Ok(key) if key.len() >= MIN_SECRET_LENGTH => Ok(Self(secret)),
+            _ => Err("Invalid TOTP secret".to_owned()),
+        }
+    }
+
+    /// The code of the given time step, i.e. of the 30 seconds starting at `step * 30`.
+    pub fn code_at(&self, step: u64) -> String {
+        let key = BASE32_NOPAD
+            .decode(self.0.as_bytes())
+            .expect("secret is valid base32");
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, &key);
+        let tag = hmac::sign(&key, &step.to_be_bytes());
+        let digest = tag.as_ref();
+
+        // Dynamic truncation (RFC 4226, section 5.3).
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(DIGITS),
+            width = DIGITS as usize
+        )
+    }
+
+    /// Checks a code against the time step of `now` and the `skew_steps` steps on either
+    /// side of it, which absorb clock drift and slow typing. Returns the matching step.
+    pub fn verify(&self, code: &TwoFACode, now: DateTime<Utc>, skew_steps: u64) -> Option<u64> {
+        let current = now.timestamp().max(0) as u64 / TOTP_STEP_SECONDS;
+        (current.saturating_sub(skew_steps)..=current.saturating_add(skew_steps)).find(|&step| {
+            verify_slices_are_equal(self.code_at(step).as_bytes(), code.as_ref().as_bytes()).is_ok()
+        })
+    }
+
+    /// The `otpauth://` URI authenticator apps import the secret from, usually as a QR code.
+    pub fn otpauth_uri(&self, issuer: &str, account: &Email) -> String {
+        let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC);
+        let account = utf8_percent_encode(account.as_ref(), NON_ALPHANUMERIC);
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={TOTP_STEP_SECONDS}",
+            self.0
+        )
+    }
+}
+
+impl Default for TotpSecret {
+    /// Generates a new random secret.
+    fn default() -> Self {
+        let mut key = [0u8; SECRET_LENGTH];
+        rand::rng().fill(&mut key);
+        Self(BASE32_NOPAD.encode(&key))
+    }
+}
+
+impl AsRef<str> for TotpSecret {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Keeps the secret out of logs.
+impl std::fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TotpSecret(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The SHA-1 seed of RFC 6238, appendix B: the ASCII string "12345678901234567890".
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret::parse("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned()).unwrap()
+    }
+
+    fn at(timestamp: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn test_codes_match_rfc_test_vectors() {
+        // The last 6 of the 8 digits listed in the RFC.
+        let secret = rfc_secret();
+        for (timestamp, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(secret.code_at(timestamp as u64 / TOTP_STEP_SECONDS), code);
+        }
+    }
+
+    #[test]
+    fn test_verify_tolerates_the_skew_window_only() {
+        let secret = rfc_secret();
+        let code = TwoFACode::parse("005924".to_owned()).unwrap();
+        let step = 1234567890 / TOTP_STEP_SECONDS;
+
+        assert_eq!(secret.verify(&code, at(1234567890), 0), Some(step));
+        assert_eq!(secret.verify(&code, at(1234567890 + 30), 1), Some(step));
+        assert_eq!(secret.verify(&code, at(1234567890 - 30), 1), Some(step));
+        assert_eq!(secret.verify(&code, at(1234567890 + 30), 0), None);
+        assert_eq!(secret.verify(&code, at(1234567890 + 60), 1), None);
+    }
+
+    #[test]
+    fn test_default_secrets_parse_and_differ() {
+        let secret = TotpSecret::default();
+        assert_eq!(
+            TotpSecret::parse(secret.as_ref().to_owned()),
+            Ok(secret.clone())
+        );
+        assert_ne!(secret, TotpSecret::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_short_or_malformed_secrets() {
+        assert!(TotpSecret::parse("".to_owned()).is_err());
+        assert!(TotpSecret::parse("GEZDGNBVGY3TQOJQ".to_owned()).is_err());
+        assert!(TotpSecret::parse("not base32 at all!".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let email = Email::parse("user@example.com").unwrap();
+        assert_eq!(
+            rfc_secret().otpauth_uri("Auth Service", &email),
+            "otpauth://totp/Auth%20Service:user%40example%2Ecom\
+             ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Auth%20Service\
+             &algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use rand::Rng;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -20,7 +22,7 @@ fn is_valid_code(code: &str) -> bool {
     }
     // Check if all characters are digits.
     for c in code.chars() {
-        if !c.is_digit(10) {
+        if !c.is_ascii_digit() {
             return false;
         }
     }
@@ -55,34 +57,53 @@ impl AsRef<str> for TwoFACode {
     }
 }
 
+/// How long a 2FA code stays valid and how many wrong guesses it tolerates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwoFACodePolicy {
+    /// How long a code stays valid after it is issued.
+    pub lifetime: Duration,
+    /// Number of failed verifications after which the code is invalidated.
+    pub max_attempts: u32,
+}
+
+impl Default for TwoFACodePolicy {
+    /// Codes expire after 10 minutes or 5 wrong guesses.
+    fn default() -> Self {
+        Self {
+            lifetime: Duration::from_secs(600),
+            max_attempts: 5,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_valid_code() {
-        assert_eq!(is_valid_code("123456"), true);
-        assert_eq!(is_valid_code("987654"), true);
-        assert_eq!(is_valid_code("000000"), true);
+        assert!(is_valid_code("123456"));
+        assert!(is_valid_code("987654"));
+        assert!(is_valid_code("000000"));
     }
 
     #[test]
     fn test_invalid_code_too_short() {
-        assert_eq!(is_valid_code("12345"), false);
-        assert_eq!(is_valid_code("1234"), false);
+        assert!(!is_valid_code("12345"));
+        assert!(!is_valid_code("1234"));
     }
 
     #[test]
     fn test_invalid_code_too_long() {
-        assert_eq!(is_valid_code("1234567"), false);
-        assert_eq!(is_valid_code("12345678"), false);
+        assert!(!is_valid_code("1234567"));
+        assert!(!is_valid_code("12345678"));
     }
 
     #[test]
     fn test_invalid_code_non_digit() {
-        assert_eq!(is_valid_code("123a56"), false);
-        assert_eq!(is_valid_code("12345-6"), false);
-        assert_eq!(is_valid_code("12345.6"), false);
+        assert!(!is_valid_code("123a56"));
+        assert!(!is_valid_code("12345-6"));
+        assert!(!is_valid_code("12345.6"));
     }
 
     #[test]
@@ -90,7 +111,7 @@ mod tests {
         let code = generate_code();
         assert_eq!(code.len(), 6);
         for c in code.chars() {
-            assert!(c.is_digit(10));
+            assert!(c.is_ascii_digit());
         }
     }
 
@@ -1,22 +1,157 @@
-use super::{Email, Password};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::{Email, PasswordHash, TotpSecret};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct User {
     /// The user's email address.
     pub email: Email,
-    /// The user's password.
-    pub password: Password,
-    /// Indicates if two-factor authentication is required.
-    pub requires_2fa: bool,
+    /// The Argon2id hash of the user's password.
+    pub password_hash: PasswordHash,
+    /// Whether the user proved they own the mailbox, through the link emailed at signup.
+    /// Logins are refused until then.
+    pub email_verified: bool,
+    /// The second factor required at login, if any.
+    pub two_fa_method: TwoFAMethod,
+    /// A TOTP secret handed out for enrollment, until the user confirms it with a first code.
+    pub pending_totp_secret: Option<TotpSecret>,
+    /// The last time step whose TOTP code was accepted, so that no code is accepted twice.
+    pub totp_last_used_step: Option<u64>,
+    /// The number of consecutive failed logins since the last success or lockout.
+    pub failed_login_attempts: u32,
+    /// Logins are refused until this time after too many failed attempts.
+    pub locked_until: Option<DateTime<Utc>>,
+    /// The roles granted to the user, carried in the `roles` claim of their tokens.
+    pub roles: Vec<String>,
 }
 
 impl User {
     // add a constructor function called `new`
-    pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
+    pub fn new(email: Email, password_hash: PasswordHash, two_fa_method: TwoFAMethod) -> Self {
         Self {
             email,
-            password,
-            requires_2fa,
+            password_hash,
+            email_verified: false,
+            two_fa_method,
+            pending_totp_secret: None,
+            totp_last_used_step: None,
+            failed_login_attempts: 0,
+            locked_until: None,
+            roles: Vec::new(),
+        }
+    }
+
+    /// Returns how much longer the account stays locked as of `now`, if it is locked.
+    pub fn locked_for(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.locked_until
+            .and_then(|locked_until| (locked_until - now).to_std().ok())
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// The second factor a user proves at login, on top of their password.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TwoFAMethod {
+    /// The password is enough.
+    None,
+    /// A one-time code is emailed at every login.
+    Email,
+    /// A code is read from an authenticator app holding this secret.
+    Totp(TotpSecret),
+}
+
+impl TwoFAMethod {
+    /// The name under which stores keep the method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Email => "email",
+            Self::Totp(_) => "totp",
+        }
+    }
+
+    /// The TOTP secret, for the `Totp` method.
+    pub fn totp_secret(&self) -> Option<&TotpSecret> {
+        match self {
+            Self::Totp(secret) => Some(secret),
+            _ => None,
+        }
+    }
+
+    /// Rebuilds a stored method from its name and the user's TOTP secret.
+    pub fn parse(name: &str, totp_secret: Option<TotpSecret>) -> Result<Self, String> {
+        match (name, totp_secret) {
+            ("none", _) => Ok(Self::None),
+            ("email", _) => Ok(Self::Email),
+            ("totp", Some(secret)) => Ok(Self::Totp(secret)),
+            _ => Err(format!("Invalid 2FA method {name}")),
+        }
+    }
+}
+
+/// How many failed logins lock an account, and for how long.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Number of consecutive failed logins after which the account is locked.
+    pub max_failed_attempts: u32,
+    /// How long the account stays locked before it unlocks on its own.
+    pub lockout_duration: Duration,
+}
+
+impl Default for LockoutPolicy {
+    /// Lock for 15 minutes after 5 failed logins.
+    fn default() -> Self {
+        Self {
+            max_failed_attempts: 5,
+            lockout_duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> User {
+        User::new(
+            Email::parse("user@example.com").unwrap(),
+            PasswordHash::parse(
+                "$argon2id$v=19$m=1024,t=1,p=1$c29tZXNhbHQ$YWFhYWFhYWFhYWFhYWFhYQ".to_owned(),
+            )
+            .unwrap(),
+            TwoFAMethod::None,
+        )
+    }
+
+    #[test]
+    fn test_locked_for() {
+        let now = Utc::now();
+        let mut user = user();
+        assert_eq!(user.locked_for(now), None);
+
+        user.locked_until = Some(now + chrono::Duration::minutes(15));
+        assert_eq!(user.locked_for(now), Some(Duration::from_secs(15 * 60)));
+
+        // The lock lifts on its own once the window has passed.
+        assert_eq!(user.locked_for(now + chrono::Duration::minutes(15)), None);
+        assert_eq!(user.locked_for(now + chrono::Duration::minutes(16)), None);
+    }
+
+    #[test]
+    fn test_two_fa_method_round_trips_through_its_name() {
+        let secret = TotpSecret::default();
+        for method in [
+            TwoFAMethod::None,
+            TwoFAMethod::Email,
+            TwoFAMethod::Totp(secret.clone()),
+        ] {
+            let totp_secret = method.totp_secret().cloned();
+            assert_eq!(TwoFAMethod::parse(method.name(), totp_secret), Ok(method));
         }
+        // A TOTP user without a secret could never log in.
+        assert!(TwoFAMethod::parse("totp", None).is_err());
+        assert!(TwoFAMethod::parse("sms", Some(secret)).is_err());
     }
 }
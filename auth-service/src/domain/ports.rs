@@ -1,5 +1,6 @@
 use super::models::*;
-use std::future::Future;
+use chrono::{DateTime, Utc};
+use std::{future::Future, time::Duration};
 
 /// A trait for a user store.
 pub trait UserStore: Send + Sync + Clone + 'static {
@@ -9,25 +10,176 @@ pub trait UserStore: Send + Sync + Clone + 'static {
     /// Gets a user from the store.
     fn get_user(&self, email: &Email) -> impl Future<Output = Result<User, UserStoreError>> + Send;
 
-    /// Validates a user.
-    fn validate_user(
+    /// Replaces the password hash of an existing user, dropping any email change pending.
+    fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: PasswordHash,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Moves the user, with their recovery codes, to another email address. Fails with
+    /// `UserAlreadyExists` when the address belongs to another user.
+    fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Deletes the user along with their recovery codes, pending password reset token and
+    /// pending email change.
+    fn delete_user(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Records that the user proved they own their email address.
+    fn mark_email_verified(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Counts a failed login and returns the number of consecutive failures.
+    fn record_failed_login(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<u32, UserStoreError>> + Send;
+
+    /// Refuses logins until `locked_until` and clears the failure count.
+    fn lock_user(
+        &mut self,
+        email: &Email,
+        locked_until: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Clears the failure count and any lock after a successful login.
+    fn reset_failed_logins(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Keeps a TOTP secret until the user confirms it with a first code, replacing any
+    /// secret pending already. The current 2FA method stays in force meanwhile.
+    fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Switches the user to another 2FA method and drops any pending TOTP secret.
+    fn update_two_fa_method(
+        &mut self,
+        email: &Email,
+        method: TwoFAMethod,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Atomically records that the TOTP code of time step `step` was used, unless a code of
+    /// this or a later step was used already. Returns whether it was recorded, so that a
+    /// code is accepted at most once.
+    fn record_totp_use(
+        &mut self,
+        email: &Email,
+        step: u64,
+    ) -> impl Future<Output = Result<bool, UserStoreError>> + Send;
+
+    /// Replaces the user's recovery codes with `codes`, invalidating the previous ones.
+    fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: &[RecoveryCode],
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Tells whether `code` is one of the user's recovery codes, without using it up.
+    fn has_recovery_code(
+        &self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> impl Future<Output = Result<bool, UserStoreError>> + Send;
+
+    /// Atomically removes `code` from the user's recovery codes. Returns whether it was one
+    /// of them, so that a code is accepted at most once.
+    fn consume_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> impl Future<Output = Result<bool, UserStoreError>> + Send;
+
+    /// Counts the recovery codes the user has left.
+    fn count_recovery_codes(
         &self,
         email: &Email,
-        password: &Password,
+    ) -> impl Future<Output = Result<usize, UserStoreError>> + Send;
+
+    /// Stores a password reset token for the user until `expires_at`, replacing any token
+    /// pending already.
+    fn set_password_reset_token(
+        &mut self,
+        email: &Email,
+        token: &PasswordResetToken,
+        expires_at: DateTime<Utc>,
     ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Returns the user an unexpired password reset token was issued to, leaving the token in
+    /// place. Returns `None` for unknown and expired tokens.
+    fn get_password_reset_email(
+        &self,
+        token: &PasswordResetToken,
+    ) -> impl Future<Output = Result<Option<Email>, UserStoreError>> + Send;
+
+    /// Atomically removes an unexpired password reset token and returns the user it was
+    /// issued to, so that a token is accepted at most once. Returns `None` for unknown and
+    /// expired tokens, which are removed as well.
+    fn consume_password_reset_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> impl Future<Output = Result<Option<Email>, UserStoreError>> + Send;
+
+    /// Stores a token moving the user to `new_email` until `expires_at`, replacing any email
+    /// change pending already.
+    fn set_email_change_token(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+        token: &EmailChangeToken,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), UserStoreError>> + Send;
+
+    /// Atomically removes an unexpired email change token and returns the user it was issued
+    /// to with the address they move to, so that a token is accepted at most once. Returns
+    /// `None` for unknown and expired tokens, which are removed as well.
+    fn consume_email_change_token(
+        &mut self,
+        token: &EmailChangeToken,
+    ) -> impl Future<Output = Result<Option<(Email, Email)>, UserStoreError>> + Send;
 }
 
-/// A trait for a banned store.
+/// A trait for a banned store. Tokens are identified by their `jti` claim, or banned
+/// all at once by their `sub` claim.
 pub trait BannedStore: Send + Sync + Clone + 'static {
-    /// Checks if an email is banned.
-    fn is_banned(&self, token: &str)
-    -> impl Future<Output = Result<bool, BannedStoreError>> + Send;
+    /// Checks if the token with this ID is banned.
+    fn is_banned(&self, jti: &str) -> impl Future<Output = Result<bool, BannedStoreError>> + Send;
 
-    /// Adds a token to the banned store.
+    /// Bans the token with this ID until `expires_at`, after which the
+    /// token can no longer be validated and the entry may be dropped.
     fn add_token(
         &mut self,
-        token: &str,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> impl Future<Output = Result<(), BannedStoreError>> + Send;
+
+    /// Bans every token issued to `sub` up to `issued_until`, until `expires_at`, after
+    /// which none of them can be validated and the entry may be dropped.
+    fn ban_subject(
+        &mut self,
+        sub: &str,
+        issued_until: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
     ) -> impl Future<Output = Result<(), BannedStoreError>> + Send;
+
+    /// Gets the time up to which the tokens issued to `sub` are banned, if they are.
+    fn subject_banned_until(
+        &self,
+        sub: &str,
+    ) -> impl Future<Output = Result<Option<DateTime<Utc>>, BannedStoreError>> + Send;
 }
 
 /// An error that can occur when interacting with the user store.
@@ -66,15 +218,194 @@ pub trait TwoFACodeStore: Send + Sync + Clone + 'static {
         &self,
         email: &Email,
     ) -> impl Future<Output = Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>> + Send;
+    /// Atomically checks the pending code for `email` and removes it when it matches,
+    /// so that a code can be used at most once. Expired codes are removed as well.
+    /// A wrong code for the pending login attempt counts as a failed attempt, and the
+    /// code is removed once the attempts run out.
+    fn consume_code(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+    ) -> impl Future<Output = Result<(), TwoFACodeStoreError>> + Send;
+    /// Settles the pending login attempt like `consume_code`, for a code the store does not
+    /// hold, such as a TOTP code the caller checked itself: the attempt is removed when
+    /// `verified`, and counts as a failed attempt otherwise.
+    fn consume_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        verified: bool,
+    ) -> impl Future<Output = Result<(), TwoFACodeStoreError>> + Send;
 }
 
 #[derive(Debug, PartialEq)]
 pub enum TwoFACodeStoreError {
     LoginAttemptIdNotFound,
+    /// Indicates that the login attempt ID or code does not match the pending one.
+    IncorrectCode,
+    /// Indicates that the pending code outlived its lifetime.
+    Expired,
+    /// Indicates that the pending code was invalidated after too many failed attempts.
+    TooManyAttempts,
+    UnexpectedError,
+}
+
+// This trait represents the interface all concrete refresh token stores should implement
+pub trait RefreshTokenStore: Send + Sync + Clone + 'static {
+    /// Stores a newly issued token.
+    fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> impl Future<Output = Result<(), RefreshTokenStoreError>> + Send;
+    /// Gets the record of a token, whether it was used already or not.
+    fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> impl Future<Output = Result<RefreshTokenRecord, RefreshTokenStoreError>> + Send;
+    /// Atomically marks an unused, unexpired token as used, so that it can be rotated once.
+    /// Presenting a used token again fails with `TokenReused`.
+    fn use_token(
+        &mut self,
+        token: &RefreshToken,
+    ) -> impl Future<Output = Result<RefreshTokenRecord, RefreshTokenStoreError>> + Send;
+    /// Revokes every token of a family.
+    fn revoke_family(
+        &mut self,
+        family_id: &str,
+    ) -> impl Future<Output = Result<(), RefreshTokenStoreError>> + Send;
+    /// Revokes every token issued to a user, whatever their family.
+    fn revoke_user(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<(), RefreshTokenStoreError>> + Send;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RefreshTokenStoreError {
+    TokenNotFound,
+    TokenExpired,
+    /// Indicates that a used token was presented again, which means it leaked.
+    TokenReused {
+        family_id: String,
+    },
+    UnexpectedError,
+}
+
+/// A store of the OAuth clients registered for the OpenID Connect flow, and of the
+/// authorization codes issued to them.
+pub trait OAuthClientStore: Send + Sync + Clone + 'static {
+    /// Registers a client, replacing any client with the same ID.
+    fn add_client(
+        &mut self,
+        client: OAuthClient,
+    ) -> impl Future<Output = Result<(), OAuthClientStoreError>> + Send;
+    fn get_client(
+        &self,
+        client_id: &str,
+    ) -> impl Future<Output = Result<OAuthClient, OAuthClientStoreError>> + Send;
+    /// Stores a newly issued authorization code.
+    fn add_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+        record: AuthorizationCodeRecord,
+    ) -> impl Future<Output = Result<(), OAuthClientStoreError>> + Send;
+    /// Atomically removes an unexpired code and returns its record, so that it can be
+    /// redeemed at most once. Expired codes are removed as well.
+    fn consume_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> impl Future<Output = Result<AuthorizationCodeRecord, OAuthClientStoreError>> + Send;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OAuthClientStoreError {
+    ClientNotFound,
+    CodeNotFound,
+    CodeExpired,
+    UnexpectedError,
+}
+
+// This trait represents the interface all concrete passkey stores should implement
+pub trait PasskeyStore: Send + Sync + Clone + 'static {
+    /// Registers a passkey. Fails if its credential ID is already registered.
+    fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> impl Future<Output = Result<(), PasskeyStoreError>> + Send;
+    /// Moves the passkeys registered to a user over to their new email address.
+    fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> impl Future<Output = Result<(), PasskeyStoreError>> + Send;
+    /// Deletes every passkey registered to a user.
+    fn delete_credentials(
+        &mut self,
+        email: &Email,
+    ) -> impl Future<Output = Result<(), PasskeyStoreError>> + Send;
+    /// Lists the passkeys registered to a user.
+    fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> impl Future<Output = Result<Vec<PasskeyCredential>, PasskeyStoreError>> + Send;
+    fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> impl Future<Output = Result<PasskeyCredential, PasskeyStoreError>> + Send;
+    /// Records the signature counter of an authentication, unless the stored counter is
+    /// already as high, which betrays a replayed assertion or a cloned authenticator.
+    /// Returns whether the counter was recorded.
+    fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> impl Future<Output = Result<bool, PasskeyStoreError>> + Send;
+    /// Stores a newly issued challenge.
+    fn add_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+        record: PasskeyChallengeRecord,
+    ) -> impl Future<Output = Result<(), PasskeyStoreError>> + Send;
+    /// Atomically removes an unexpired challenge and returns its record, so that each
+    /// ceremony completes at most once. Expired challenges are removed as well.
+    fn consume_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+    ) -> impl Future<Output = Result<PasskeyChallengeRecord, PasskeyStoreError>> + Send;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasskeyStoreError {
+    CredentialAlreadyExists,
+    CredentialNotFound,
+    ChallengeNotFound,
+    ChallengeExpired,
+    UnexpectedError,
+}
+
+// This trait represents the interface all concrete rate limiters should implement
+pub trait RateLimiter: Send + Sync + Clone + 'static {
+    /// Takes one token from the bucket identified by `key`, which is refilled
+    /// according to `policy`.
+    fn check(
+        &mut self,
+        key: &str,
+        policy: &RateLimitPolicy,
+    ) -> impl Future<Output = Result<(), RateLimiterError>> + Send;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimiterError {
+    /// Indicates that the bucket is empty, and when the next token becomes available.
+    Limited {
+        retry_after: Duration,
+    },
     UnexpectedError,
 }
 
-///
+/// A trait for an email client.
 pub trait EmailClient: Send + Sync + Clone + 'static {
     fn send_email(
         &self,
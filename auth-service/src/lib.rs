@@ -9,9 +9,11 @@ pub mod api;
 pub mod domain;
 pub mod services;
 
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 use tokio::sync::RwLock;
 use domain::ports::{BannedStore, UserStore, TwoFACodeStore, EmailClient};
+use redis::aio::ConnectionManager;
+use sqlx::{PgPool, SqlitePool, postgres::PgPoolOptions, sqlite::SqliteConnectOptions};
 
 pub use api::{
     AppState, Application,
@@ -26,4 +28,29 @@ pub type BannedStoreType = Arc<RwLock<dyn BannedStore>>;
 
 pub type TwoFACodeStoreType = Arc<RwLock<dyn TwoFACodeStore>>;
 
-pub type EmailClientType = Arc<RwLock<dyn EmailClient>>;
\ No newline at end of file
+pub type EmailClientType = Arc<RwLock<dyn EmailClient>>;
+
+/// Opens a SQLite connection pool, creating the database file if needed,
+/// and applies the embedded migrations from `migrations/sqlite`.
+pub async fn get_sqlite_pool(url: &str) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    let pool = SqlitePool::connect_with(options).await?;
+    sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+    Ok(pool)
+}
+
+/// Opens a PostgreSQL connection pool and applies the embedded migrations
+/// from `migrations/postgres`.
+pub async fn get_postgres_pool(url: &str, max_connections: u32) -> Result<PgPool, sqlx::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(url)
+        .await?;
+    sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+    Ok(pool)
+}
+
+/// Opens a Redis connection that transparently reconnects when the server drops it.
+pub async fn get_redis_connection(url: &str) -> Result<ConnectionManager, redis::RedisError> {
+    redis::Client::open(url)?.get_connection_manager().await
+}
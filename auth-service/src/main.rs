@@ -1,29 +1,185 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::sync::RwLock;
+use tracing_subscriber::EnvFilter;
 
 use auth_service::{
-    AppState, Application, prod
+    AppState, Application,
+    api::utils::constants::{
+        BANNED_TOKEN_SWEEP_INTERVAL_SECONDS, DATABASE_MAX_CONNECTIONS, DATABASE_URL, JWT_KEY_RING,
+        OIDC_CLIENTS, RATE_LIMIT_CONFIG, REDIS_URL, TWO_FA_CODE_POLICY,
+    },
+    domain::ports::{
+        BannedStore, OAuthClientStore, PasskeyStore, RefreshTokenStore, TwoFACodeStore, UserStore,
+    },
+    get_postgres_pool, get_redis_connection, get_sqlite_pool, prod,
 };
 
 use auth_service::services::{
-    banned_user_store::HashSetBannedStore,
-    hashmap_two_fa_code_store::HashmapTwoFACodeStore,
-    hashmap_user_store::HashmapUserStore,
-    mock_email_client::MockEmailClient,
+    banned_user_store::HashSetBannedStore, hashmap_oauth_client_store::HashmapOAuthClientStore,
+    hashmap_passkey_store::HashmapPasskeyStore, hashmap_rate_limiter::HashmapRateLimiter,
+    hashmap_refresh_token_store::HashmapRefreshTokenStore,
+    hashmap_two_fa_code_store::HashmapTwoFACodeStore, hashmap_user_store::HashmapUserStore,
+    mock_email_client::MockEmailClient, postgres_oauth_client_store::PostgresOAuthClientStore,
+    postgres_passkey_store::PostgresPasskeyStore,
+    postgres_refresh_token_store::PostgresRefreshTokenStore,
+    postgres_two_fa_code_store::PostgresTwoFACodeStore, postgres_user_store::PostgresUserStore,
+    redis_banned_store::RedisBannedStore, redis_two_fa_code_store::RedisTwoFACodeStore,
+    sqlite_user_store::SqliteUserStore,
 };
 
 #[tokio::main]
 async fn main() {
-    let user_store = Arc::new(RwLock::new(HashmapUserStore::default()));
-    let banned_store = Arc::new(RwLock::new(HashSetBannedStore::default()));
-    let two_fa_store = Arc::new(RwLock::new(HashmapTwoFACodeStore::default()));
-    let email_client = Arc::new(RwLock::new(MockEmailClient::default()));
+    // Logs at `info` and above unless RUST_LOG says otherwise.
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
 
-    let app_state = AppState::new(user_store, banned_store, two_fa_store, email_client);
+    // Fail at startup rather than on the first login if the signing keys are misconfigured.
+    lazy_static::initialize(&JWT_KEY_RING);
+    #[cfg(unix)]
+    auth_service::api::utils::auth::spawn_key_ring_reloader().expect("Failed to listen for SIGHUP");
 
-    let app = Application::build(app_state, prod::APP_ADDRESS)
-        .await
-        .expect("Failed to build app");
+    // Select the store backends from the DATABASE_URL scheme.
+    match DATABASE_URL.as_deref() {
+        None => {
+            with_redis(
+                HashmapUserStore::default(),
+                HashmapTwoFACodeStore::new(*TWO_FA_CODE_POLICY),
+                HashmapRefreshTokenStore::default(),
+                HashmapOAuthClientStore::default(),
+                HashmapPasskeyStore::default(),
+            )
+            .await
+        }
+        Some(url) if url.starts_with("sqlite:") => {
+            let pool = get_sqlite_pool(url)
+                .await
+                .expect("Failed to create SQLite connection pool");
+            with_redis(
+                SqliteUserStore::new(pool),
+                HashmapTwoFACodeStore::new(*TWO_FA_CODE_POLICY),
+                HashmapRefreshTokenStore::default(),
+                HashmapOAuthClientStore::default(),
+                HashmapPasskeyStore::default(),
+            )
+            .await
+        }
+        Some(url) if url.starts_with("postgres:") || url.starts_with("postgresql:") => {
+            let pool = get_postgres_pool(url, *DATABASE_MAX_CONNECTIONS)
+                .await
+                .expect("Failed to create Postgres connection pool");
+            with_redis(
+                PostgresUserStore::new(pool.clone()),
+                PostgresTwoFACodeStore::new(pool.clone(), *TWO_FA_CODE_POLICY),
+                PostgresRefreshTokenStore::new(pool.clone()),
+                PostgresOAuthClientStore::new(pool.clone()),
+                PostgresPasskeyStore::new(pool),
+            )
+            .await
+        }
+        Some(url) => panic!("Unsupported DATABASE_URL: {url}"),
+    }
+}
+
+// When REDIS_URL is set, Redis takes over the banned tokens and 2FA codes.
+async fn with_redis<
+    S: UserStore,
+    T: TwoFACodeStore,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    user_store: S,
+    two_fa_store: T,
+    refresh_token_store: R,
+    oauth_client_store: O,
+    passkey_store: P,
+) {
+    match REDIS_URL.as_deref() {
+        None => {
+            // Without Redis eviction, banned tokens are pruned in process.
+            let banned_store = Arc::new(RwLock::new(HashSetBannedStore::default()));
+            HashSetBannedStore::spawn_sweeper(
+                banned_store.clone(),
+                Duration::from_secs(*BANNED_TOKEN_SWEEP_INTERVAL_SECONDS),
+            );
+            run(
+                user_store,
+                banned_store,
+                two_fa_store,
+                refresh_token_store,
+                oauth_client_store,
+                passkey_store,
+            )
+            .await
+        }
+        Some(url) => {
+            let connection = get_redis_connection(url)
+                .await
+                .expect("Failed to connect to Redis");
+            run(
+                user_store,
+                Arc::new(RwLock::new(RedisBannedStore::new(connection.clone()))),
+                RedisTwoFACodeStore::new(connection, *TWO_FA_CODE_POLICY),
+                refresh_token_store,
+                oauth_client_store,
+                passkey_store,
+            )
+            .await
+        }
+    }
+}
+
+async fn run<
+    S: UserStore,
+    B: BannedStore,
+    T: TwoFACodeStore,
+    R: RefreshTokenStore,
+    O: OAuthClientStore,
+    P: PasskeyStore,
+>(
+    user_store: S,
+    banned_store: Arc<RwLock<B>>,
+    two_fa_store: T,
+    refresh_token_store: R,
+    mut oauth_client_store: O,
+    passkey_store: P,
+) {
+    // Clients are registered anew on every start, picking up changes to OIDC_CLIENTS_FILE.
+    for client in OIDC_CLIENTS.iter() {
+        oauth_client_store
+            .add_client(client.clone())
+            .await
+            .expect("Failed to register OIDC client");
+    }
+
+    let user_store = Arc::new(RwLock::new(user_store));
+    let two_fa_store = Arc::new(RwLock::new(two_fa_store));
+    let email_client = Arc::new(RwLock::new(MockEmailClient));
+    let refresh_token_store = Arc::new(RwLock::new(refresh_token_store));
+    let oauth_client_store = Arc::new(RwLock::new(oauth_client_store));
+    let passkey_store = Arc::new(RwLock::new(passkey_store));
+
+    let app_state = AppState::new(
+        user_store,
+        banned_store,
+        two_fa_store,
+        email_client,
+        refresh_token_store,
+        oauth_client_store,
+        passkey_store,
+    );
+
+    let rate_limiter = Arc::new(RwLock::new(HashmapRateLimiter::default()));
+
+    let app = Application::build(
+        app_state,
+        rate_limiter,
+        *RATE_LIMIT_CONFIG,
+        prod::APP_ADDRESS,
+    )
+    .await
+    .expect("Failed to build app");
 
     app.run().await.expect("Failed to run app");
 }
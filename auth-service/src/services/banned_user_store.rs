@@ -1,42 +1,198 @@
 use crate::domain::ports::{BannedStore, BannedStoreError};
-use std::collections::HashSet;
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::RwLock, task::JoinHandle};
 
-/// A store for banned tokens using a HashSet.
+/// A store for banned token IDs using a HashMap.
 #[derive(Default, Clone)]
 pub struct HashSetBannedStore {
-    /// A set of banned tokens.
-    banned_tokens: HashSet<String>,
+    /// The IDs of the banned tokens with the time after which they can be dropped.
+    banned_tokens: HashMap<String, DateTime<Utc>>,
+    /// The subjects whose tokens are banned, with the time up to which they were issued
+    /// and the time after which the entry can be dropped.
+    banned_subjects: HashMap<String, (DateTime<Utc>, DateTime<Utc>)>,
+    /// The number of expired entries pruned since the store was created.
+    pruned_total: u64,
+}
+
+impl HashSetBannedStore {
+    /// Drops the entries that expired before `now` and returns how many were removed.
+    pub fn prune_expired(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.len();
+        self.banned_tokens.retain(|_, expires_at| *expires_at > now);
+        self.banned_subjects
+            .retain(|_, (_, expires_at)| *expires_at > now);
+        let pruned = before - self.len();
+        self.pruned_total += pruned as u64;
+        pruned
+    }
+
+    /// The number of expired entries pruned since the store was created.
+    pub fn pruned_total(&self) -> u64 {
+        self.pruned_total
+    }
+
+    /// The number of tokens and subjects currently held.
+    pub fn len(&self) -> usize {
+        self.banned_tokens.len() + self.banned_subjects.len()
+    }
+
+    /// Returns true if no tokens or subjects are held.
+    pub fn is_empty(&self) -> bool {
+        self.banned_tokens.is_empty() && self.banned_subjects.is_empty()
+    }
+
+    /// Spawns a background task pruning expired entries every `interval`.
+    pub fn spawn_sweeper(store: Arc<RwLock<Self>>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let mut store = store.write().await;
+                let pruned = store.prune_expired(Utc::now());
+                if pruned > 0 {
+                    tracing::info!(
+                        pruned,
+                        remaining = store.len(),
+                        pruned_total = store.pruned_total(),
+                        "pruned expired banned tokens"
+                    );
+                }
+            }
+        })
+    }
 }
 
 impl BannedStore for HashSetBannedStore {
-    /// Checks if a token is banned.
-    async fn is_banned(&self, token: &str) -> Result<bool, BannedStoreError> {
-        Ok(self.banned_tokens.contains(token))
+    /// Checks if the token with this ID is banned.
+    async fn is_banned(&self, jti: &str) -> Result<bool, BannedStoreError> {
+        Ok(self.banned_tokens.contains_key(jti))
+    }
+
+    /// Bans the token with this ID until it expires.
+    async fn add_token(
+        &mut self,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BannedStoreError> {
+        self.banned_tokens.insert(jti.to_owned(), expires_at);
+        Ok(())
     }
 
-    /// Adds a token to the banned store.
-    async fn add_token(&mut self, token: &str) -> Result<(), BannedStoreError> {
-        self.banned_tokens.insert(token.to_owned());
+    /// Bans the tokens issued to this subject so far.
+    async fn ban_subject(
+        &mut self,
+        sub: &str,
+        issued_until: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BannedStoreError> {
+        self.banned_subjects
+            .insert(sub.to_owned(), (issued_until, expires_at));
         Ok(())
     }
+
+    /// Gets the time up to which the tokens of this subject are banned.
+    async fn subject_banned_until(
+        &self,
+        sub: &str,
+    ) -> Result<Option<DateTime<Utc>>, BannedStoreError> {
+        Ok(self
+            .banned_subjects
+            .get(sub)
+            .map(|(issued_until, _)| *issued_until))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeDelta;
+
+    fn in_a_minute() -> DateTime<Utc> {
+        Utc::now() + TimeDelta::seconds(60)
+    }
 
     #[tokio::test]
     async fn test_add_token() {
         let mut banned_store = HashSetBannedStore::default();
-        banned_store.add_token("test_token").await.unwrap();
+        banned_store
+            .add_token("test_token", in_a_minute())
+            .await
+            .unwrap();
         assert!(banned_store.is_banned("test_token").await.unwrap());
     }
 
     #[tokio::test]
     async fn test_is_banned() {
         let mut banned_store = HashSetBannedStore::default();
-        banned_store.add_token("banned_token").await.unwrap();
+        banned_store
+            .add_token("banned_token", in_a_minute())
+            .await
+            .unwrap();
         assert!(!banned_store.is_banned("not_banned_token").await.unwrap());
         assert!(banned_store.is_banned("banned_token").await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_prune_expired() {
+        let mut banned_store = HashSetBannedStore::default();
+        let now = Utc::now();
+        banned_store
+            .add_token("expired_token", now - TimeDelta::seconds(1))
+            .await
+            .unwrap();
+        banned_store
+            .add_token("live_token", now + TimeDelta::seconds(60))
+            .await
+            .unwrap();
+
+        assert_eq!(banned_store.prune_expired(now), 1);
+        assert_eq!(banned_store.pruned_total(), 1);
+        assert_eq!(banned_store.len(), 1);
+        assert!(!banned_store.is_banned("expired_token").await.unwrap());
+        assert!(banned_store.is_banned("live_token").await.unwrap());
+
+        assert_eq!(banned_store.prune_expired(now + TimeDelta::seconds(61)), 1);
+        assert_eq!(banned_store.pruned_total(), 2);
+        assert!(banned_store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ban_subject() {
+        let mut banned_store = HashSetBannedStore::default();
+        let now = Utc::now();
+        assert_eq!(banned_store.subject_banned_until("user").await, Ok(None));
+
+        banned_store
+            .ban_subject("user", now, now + TimeDelta::seconds(60))
+            .await
+            .unwrap();
+        assert_eq!(
+            banned_store.subject_banned_until("user").await,
+            Ok(Some(now))
+        );
+        assert_eq!(banned_store.subject_banned_until("other").await, Ok(None));
+
+        assert_eq!(banned_store.prune_expired(now + TimeDelta::seconds(61)), 1);
+        assert_eq!(banned_store.subject_banned_until("user").await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_prunes_expired_tokens() {
+        let store = Arc::new(RwLock::new(HashSetBannedStore::default()));
+        store
+            .write()
+            .await
+            .add_token("expired_token", Utc::now() - TimeDelta::seconds(1))
+            .await
+            .unwrap();
+
+        let sweeper = HashSetBannedStore::spawn_sweeper(store.clone(), Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.abort();
+
+        let store = store.read().await;
+        assert!(store.is_empty());
+        assert_eq!(store.pruned_total(), 1);
+    }
 }
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{
+    models::{AuthorizationCode, AuthorizationCodeRecord, OAuthClient},
+    ports::{OAuthClientStore, OAuthClientStoreError},
+};
+
+/// An in-memory OAuth client store. Authorization codes are keyed by their hash.
+#[derive(Default, Clone)]
+pub struct HashmapOAuthClientStore {
+    clients: HashMap<String, OAuthClient>,
+    codes: HashMap<String, AuthorizationCodeRecord>,
+}
+
+impl OAuthClientStore for HashmapOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    async fn add_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+        record: AuthorizationCodeRecord,
+    ) -> Result<(), OAuthClientStoreError> {
+        // Codes nobody redeemed in time are dropped on the way.
+        let now = Utc::now();
+        self.codes.retain(|_, record| record.expires_at > now);
+        self.codes.insert(code.hash(), record);
+        Ok(())
+    }
+
+    async fn consume_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeRecord, OAuthClientStoreError> {
+        let record = self
+            .codes
+            .remove(&code.hash())
+            .ok_or(OAuthClientStoreError::CodeNotFound)?;
+        if record.expires_at <= Utc::now() {
+            return Err(OAuthClientStoreError::CodeExpired);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{CodeChallenge, Email};
+
+    fn client() -> OAuthClient {
+        OAuthClient::new(
+            "client".to_owned(),
+            "Client".to_owned(),
+            vec!["https://app.example.com/callback".to_owned()],
+            None,
+        )
+    }
+
+    fn record() -> AuthorizationCodeRecord {
+        AuthorizationCodeRecord {
+            client_id: "client".to_owned(),
+            redirect_uri: "https://app.example.com/callback".to_owned(),
+            email: Email::parse("user@example.com").unwrap(),
+            scope: "openid".to_owned(),
+            nonce: Some("nonce".to_owned()),
+            code_challenge: CodeChallenge::parse(
+                "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM".to_owned(),
+            )
+            .unwrap(),
+            expires_at: Utc::now() + chrono::Duration::minutes(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_client() {
+        let mut store = HashmapOAuthClientStore::default();
+        assert_eq!(store.add_client(client()).await, Ok(()));
+        assert_eq!(store.get_client("client").await, Ok(client()));
+        assert_eq!(
+            store.get_client("other").await,
+            Err(OAuthClientStoreError::ClientNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_client_replaces_existing_client() {
+        let mut store = HashmapOAuthClientStore::default();
+        let _ = store.add_client(client()).await;
+        let renamed = OAuthClient {
+            name: "Renamed".to_owned(),
+            ..client()
+        };
+        let _ = store.add_client(renamed.clone()).await;
+        assert_eq!(store.get_client("client").await, Ok(renamed));
+    }
+
+    #[tokio::test]
+    async fn test_consume_code_only_once() {
+        let mut store = HashmapOAuthClientStore::default();
+        let code = AuthorizationCode::default();
+        let record = record();
+        let _ = store.add_authorization_code(&code, record.clone()).await;
+
+        assert_eq!(store.consume_authorization_code(&code).await, Ok(record));
+        assert_eq!(
+            store.consume_authorization_code(&code).await,
+            Err(OAuthClientStoreError::CodeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_code_cannot_be_consumed() {
+        let mut store = HashmapOAuthClientStore::default();
+        let code = AuthorizationCode::default();
+        let expired = AuthorizationCodeRecord {
+            expires_at: Utc::now() - chrono::Duration::seconds(1),
+            ..record()
+        };
+        store.codes.insert(code.hash(), expired);
+
+        assert_eq!(
+            store.consume_authorization_code(&code).await,
+            Err(OAuthClientStoreError::CodeExpired)
+        );
+        assert!(store.codes.is_empty());
+    }
+}
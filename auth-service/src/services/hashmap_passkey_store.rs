@@ -0,0 +1,271 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use chrono::Utc;
+
+use crate::domain::{
+    models::{Email, PasskeyChallenge, PasskeyChallengeRecord, PasskeyCredential},
+    ports::{PasskeyStore, PasskeyStoreError},
+};
+
+/// An in-memory passkey store. Credentials are keyed by their ID.
+#[derive(Default, Clone)]
+pub struct HashmapPasskeyStore {
+    credentials: HashMap<String, PasskeyCredential>,
+    challenges: HashMap<String, PasskeyChallengeRecord>,
+}
+
+impl PasskeyStore for HashmapPasskeyStore {
+    async fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> Result<(), PasskeyStoreError> {
+        match self.credentials.entry(credential.credential_id.clone()) {
+            Entry::Occupied(_) => Err(PasskeyStoreError::CredentialAlreadyExists),
+            Entry::Vacant(entry) => {
+                entry.insert(credential);
+                Ok(())
+            }
+        }
+    }
+
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), PasskeyStoreError> {
+        self.credentials
+            .values_mut()
+            .filter(|credential| credential.email == *email)
+            .for_each(|credential| credential.email = new_email.clone());
+        Ok(())
+    }
+
+    async fn delete_credentials(&mut self, email: &Email) -> Result<(), PasskeyStoreError> {
+        self.credentials
+            .retain(|_, credential| credential.email != *email);
+        Ok(())
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<PasskeyCredential>, PasskeyStoreError> {
+        Ok(self
+            .credentials
+            .values()
+            .filter(|credential| credential.email == *email)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyCredential, PasskeyStoreError> {
+        self.credentials
+            .get(credential_id)
+            .cloned()
+            .ok_or(PasskeyStoreError::CredentialNotFound)
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<bool, PasskeyStoreError> {
+        let credential = self
+            .credentials
+            .get_mut(credential_id)
+            .ok_or(PasskeyStoreError::CredentialNotFound)?;
+        if credential.sign_count >= sign_count {
+            return Ok(false);
+        }
+        credential.sign_count = sign_count;
+        Ok(true)
+    }
+
+    async fn add_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+        record: PasskeyChallengeRecord,
+    ) -> Result<(), PasskeyStoreError> {
+        // Ceremonies nobody completed in time are dropped on the way.
+        let now = Utc::now();
+        self.challenges.retain(|_, record| record.expires_at > now);
+        self.challenges
+            .insert(challenge.as_ref().to_owned(), record);
+        Ok(())
+    }
+
+    async fn consume_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+    ) -> Result<PasskeyChallengeRecord, PasskeyStoreError> {
+        let record = self
+            .challenges
+            .remove(challenge.as_ref())
+            .ok_or(PasskeyStoreError::ChallengeNotFound)?;
+        if record.expires_at <= Utc::now() {
+            return Err(PasskeyStoreError::ChallengeExpired);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{CosePublicKey, PasskeyCeremony};
+
+    // An Ed25519 COSE_Key with an all-zero public key.
+    fn public_key() -> CosePublicKey {
+        let mut bytes = vec![0xa4, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06, 0x21, 0x58, 0x20];
+        bytes.extend([0; 32]);
+        CosePublicKey::parse(bytes).unwrap()
+    }
+
+    fn credential(credential_id: &str, email: &str) -> PasskeyCredential {
+        PasskeyCredential {
+            credential_id: credential_id.to_owned(),
+            email: Email::parse(email).unwrap(),
+            public_key: public_key(),
+            sign_count: 0,
+        }
+    }
+
+    fn record() -> PasskeyChallengeRecord {
+        PasskeyChallengeRecord {
+            ceremony: PasskeyCeremony::Registration,
+            email: Some(Email::parse("user@example.com").unwrap()),
+            expires_at: Utc::now() + chrono::Duration::minutes(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_credentials() {
+        let mut store = HashmapPasskeyStore::default();
+        let first = credential("first", "user@example.com");
+        let second = credential("second", "user@example.com");
+        let _ = store.add_credential(first.clone()).await;
+        let _ = store.add_credential(second.clone()).await;
+        let _ = store
+            .add_credential(credential("other", "other@example.com"))
+            .await;
+
+        assert_eq!(store.get_credential("first").await, Ok(first.clone()));
+        let mut credentials = store.get_credentials(&first.email).await.unwrap();
+        credentials.sort_by(|a, b| a.credential_id.cmp(&b.credential_id));
+        assert_eq!(credentials, vec![first, second]);
+        assert_eq!(
+            store.get_credential("unknown").await,
+            Err(PasskeyStoreError::CredentialNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_email() {
+        let mut store = HashmapPasskeyStore::default();
+        let _ = store
+            .add_credential(credential("first", "user@example.com"))
+            .await;
+        let _ = store
+            .add_credential(credential("other", "other@example.com"))
+            .await;
+
+        let new_email = Email::parse("new@example.com").unwrap();
+        assert_eq!(
+            store
+                .update_email(&Email::parse("user@example.com").unwrap(), &new_email)
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            store.get_credentials(&new_email).await,
+            Ok(vec![credential("first", "new@example.com")])
+        );
+        assert_eq!(
+            store.get_credential("other").await.map(|c| c.email),
+            Ok(Email::parse("other@example.com").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_credentials() {
+        let mut store = HashmapPasskeyStore::default();
+        let _ = store
+            .add_credential(credential("first", "user@example.com"))
+            .await;
+        let _ = store
+            .add_credential(credential("other", "other@example.com"))
+            .await;
+
+        let email = Email::parse("user@example.com").unwrap();
+        assert_eq!(store.delete_credentials(&email).await, Ok(()));
+        assert_eq!(store.get_credentials(&email).await, Ok(vec![]));
+        assert!(store.get_credential("other").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_add_credential_refuses_duplicate_ids() {
+        let mut store = HashmapPasskeyStore::default();
+        let _ = store
+            .add_credential(credential("id", "user@example.com"))
+            .await;
+        assert_eq!(
+            store
+                .add_credential(credential("id", "other@example.com"))
+                .await,
+            Err(PasskeyStoreError::CredentialAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_count_only_increases() {
+        let mut store = HashmapPasskeyStore::default();
+        let _ = store
+            .add_credential(credential("id", "user@example.com"))
+            .await;
+
+        assert_eq!(store.update_sign_count("id", 5).await, Ok(true));
+        assert_eq!(store.update_sign_count("id", 5).await, Ok(false));
+        assert_eq!(store.update_sign_count("id", 3).await, Ok(false));
+        assert_eq!(store.get_credential("id").await.unwrap().sign_count, 5);
+        assert_eq!(
+            store.update_sign_count("unknown", 1).await,
+            Err(PasskeyStoreError::CredentialNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_challenge_only_once() {
+        let mut store = HashmapPasskeyStore::default();
+        let challenge = PasskeyChallenge::default();
+        let record = record();
+        let _ = store.add_challenge(&challenge, record.clone()).await;
+
+        assert_eq!(store.consume_challenge(&challenge).await, Ok(record));
+        assert_eq!(
+            store.consume_challenge(&challenge).await,
+            Err(PasskeyStoreError::ChallengeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenge_cannot_be_consumed() {
+        let mut store = HashmapPasskeyStore::default();
+        let challenge = PasskeyChallenge::default();
+        let expired = PasskeyChallengeRecord {
+            expires_at: Utc::now() - chrono::Duration::seconds(1),
+            ..record()
+        };
+        store
+            .challenges
+            .insert(challenge.as_ref().to_owned(), expired);
+
+        assert_eq!(
+            store.consume_challenge(&challenge).await,
+            Err(PasskeyStoreError::ChallengeExpired)
+        );
+        assert!(store.challenges.is_empty());
+    }
+}
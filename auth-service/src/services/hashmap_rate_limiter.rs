@@ -0,0 +1,164 @@
+use std::{collections::HashMap, time::Duration, time::Instant};
+
+use crate::domain::{
+    models::RateLimitPolicy,
+    ports::{RateLimiter, RateLimiterError},
+};
+
+// Number of buckets above which refilled buckets are dropped before adding new ones.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// The state of one token bucket.
+#[derive(Clone)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    /// When the bucket will be full again, at which point it can be forgotten.
+    full_at: Instant,
+}
+
+/// An in-memory token-bucket rate limiter, local to this process.
+#[derive(Default, Clone)]
+pub struct HashmapRateLimiter {
+    buckets: HashMap<String, Bucket>,
+}
+
+impl HashmapRateLimiter {
+    /// Takes one token from the bucket identified by `key` as of `now`.
+    pub fn check_at(
+        &mut self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: Instant,
+    ) -> Result<(), RateLimiterError> {
+        if self.buckets.len() >= PRUNE_THRESHOLD && !self.buckets.contains_key(key) {
+            self.prune_full(now);
+        }
+
+        let capacity = policy.capacity as f64;
+        let refill_rate = policy.refill_rate();
+        let bucket = self.buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+            full_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * refill_rate).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(RateLimiterError::Limited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / refill_rate),
+            });
+        }
+        bucket.tokens -= 1.0;
+        bucket.full_at = now + Duration::from_secs_f64((capacity - bucket.tokens) / refill_rate);
+        Ok(())
+    }
+
+    /// Drops the buckets that are full again as of `now` and returns how many were removed.
+    pub fn prune_full(&mut self, now: Instant) -> usize {
+        let before = self.buckets.len();
+        self.buckets.retain(|_, bucket| bucket.full_at > now);
+        before - self.buckets.len()
+    }
+
+    /// The number of buckets currently held.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns true if no buckets are held.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+impl RateLimiter for HashmapRateLimiter {
+    async fn check(&mut self, key: &str, policy: &RateLimitPolicy) -> Result<(), RateLimiterError> {
+        self.check_at(key, policy, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_after_secs(result: Result<(), RateLimiterError>) -> f64 {
+        match result {
+            Err(RateLimiterError::Limited { retry_after }) => retry_after.as_secs_f64().round(),
+            _ => panic!("expected the request to be limited"),
+        }
+    }
+
+    fn policy() -> RateLimitPolicy {
+        // One token every 10 seconds, bursts of 3.
+        "3/30".parse().unwrap()
+    }
+
+    #[test]
+    fn test_allows_a_burst_up_to_capacity() {
+        let mut limiter = HashmapRateLimiter::default();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at("key", &policy(), now), Ok(()));
+        }
+        assert_eq!(
+            retry_after_secs(limiter.check_at("key", &policy(), now)),
+            10.0
+        );
+    }
+
+    #[test]
+    fn test_refills_over_time() {
+        let mut limiter = HashmapRateLimiter::default();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at("key", &policy(), now).is_ok());
+        }
+
+        let later = now + Duration::from_secs(4);
+        assert_eq!(
+            retry_after_secs(limiter.check_at("key", &policy(), later)),
+            6.0
+        );
+        assert!(
+            limiter
+                .check_at("key", &policy(), now + Duration::from_secs(11))
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_at("key", &policy(), now + Duration::from_secs(11))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_keys_are_independent() {
+        let mut limiter = HashmapRateLimiter::default();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at("first", &policy(), now).is_ok());
+        }
+        assert!(limiter.check_at("first", &policy(), now).is_err());
+        assert!(limiter.check_at("second", &policy(), now).is_ok());
+    }
+
+    #[test]
+    fn test_prune_full() {
+        let mut limiter = HashmapRateLimiter::default();
+        let now = Instant::now();
+        assert!(limiter.check_at("once", &policy(), now).is_ok());
+        for _ in 0..3 {
+            assert!(limiter.check_at("thrice", &policy(), now).is_ok());
+        }
+        assert_eq!(limiter.len(), 2);
+
+        assert_eq!(limiter.prune_full(now + Duration::from_secs(11)), 1);
+        assert_eq!(limiter.len(), 1);
+        assert_eq!(limiter.prune_full(now + Duration::from_secs(31)), 1);
+        assert!(limiter.is_empty());
+    }
+}
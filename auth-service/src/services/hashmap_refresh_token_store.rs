@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{
+    models::{Email, RefreshToken, RefreshTokenRecord},
+    ports::{RefreshTokenStore, RefreshTokenStoreError},
+};
+
+/// An in-memory refresh token store, keyed by token hash.
+#[derive(Default, Clone)]
+pub struct HashmapRefreshTokenStore {
+    /// The issued tokens and whether they were used already.
+    tokens: HashMap<String, (RefreshTokenRecord, bool)>,
+}
+
+impl RefreshTokenStore for HashmapRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        // Expired tokens can no longer be used or reused, so they are dropped on the way.
+        let now = Utc::now();
+        self.tokens.retain(|_, (record, _)| record.expires_at > now);
+        self.tokens.insert(token.hash(), (record, false));
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        self.tokens
+            .get(&token.hash())
+            .map(|(record, _)| record.clone())
+            .ok_or(RefreshTokenStoreError::TokenNotFound)
+    }
+
+    async fn use_token(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        let (record, used) = self
+            .tokens
+            .get_mut(&token.hash())
+            .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        if *used {
+            return Err(RefreshTokenStoreError::TokenReused {
+                family_id: record.family_id.clone(),
+            });
+        }
+        if record.expires_at <= Utc::now() {
+            return Err(RefreshTokenStoreError::TokenExpired);
+        }
+        *used = true;
+        Ok(record.clone())
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        self.tokens
+            .retain(|_, (record, _)| record.family_id != family_id);
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.retain(|_, (record, _)| &record.email != email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(family_id: &str) -> RefreshTokenRecord {
+        RefreshTokenRecord {
+            email: Email::parse("user@example.com").unwrap(),
+            family_id: family_id.to_owned(),
+            expires_at: Utc::now() + chrono::Duration::days(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_token() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let token = RefreshToken::default();
+        let record = record("family");
+        assert_eq!(store.add_token(&token, record.clone()).await, Ok(()));
+        assert_eq!(store.get_token(&token).await, Ok(record));
+        assert_eq!(
+            store.get_token(&RefreshToken::default()).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_use_token_only_once() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let token = RefreshToken::default();
+        let _ = store.add_token(&token, record("family")).await;
+
+        assert!(store.use_token(&token).await.is_ok());
+        assert_eq!(
+            store.use_token(&token).await,
+            Err(RefreshTokenStoreError::TokenReused {
+                family_id: "family".to_owned()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_cannot_be_used() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let token = RefreshToken::default();
+        let expired = RefreshTokenRecord {
+            expires_at: Utc::now() - chrono::Duration::seconds(1),
+            ..record("family")
+        };
+        store.tokens.insert(token.hash(), (expired, false));
+
+        assert_eq!(
+            store.use_token(&token).await,
+            Err(RefreshTokenStoreError::TokenExpired)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let first = RefreshToken::default();
+        let second = RefreshToken::default();
+        let other = RefreshToken::default();
+        let _ = store.add_token(&first, record("family")).await;
+        let _ = store.add_token(&second, record("family")).await;
+        let _ = store.add_token(&other, record("other")).await;
+
+        assert_eq!(store.revoke_family("family").await, Ok(()));
+        assert_eq!(
+            store.use_token(&first).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.use_token(&second).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert!(store.use_token(&other).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let first = RefreshToken::default();
+        let second = RefreshToken::default();
+        let other = RefreshToken::default();
+        let _ = store.add_token(&first, record("family")).await;
+        let _ = store.add_token(&second, record("another")).await;
+        let other_record = RefreshTokenRecord {
+            email: Email::parse("other@example.com").unwrap(),
+            ..record("other")
+        };
+        let _ = store.add_token(&other, other_record).await;
+
+        assert_eq!(
+            store
+                .revoke_user(&Email::parse("user@example.com").unwrap())
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            store.use_token(&first).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.use_token(&second).await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert!(store.use_token(&other).await.is_ok());
+    }
+}
@@ -1,16 +1,84 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
+
 use crate::domain::{
-    models::{Email, LoginAttemptId, TwoFACode},
+    models::{Email, LoginAttemptId, TwoFACode, TwoFACodePolicy},
     ports::{TwoFACodeStore, TwoFACodeStoreError},
 };
 
-#[derive(Default, Clone)]
+// A code waiting to be verified.
+#[derive(Clone)]
+struct PendingCode {
+    login_attempt_id: LoginAttemptId,
+    code: TwoFACode,
+    created_at: DateTime<Utc>,
+    failed_attempts: u32,
+}
+
+#[derive(Clone, Default)]
 pub struct HashmapTwoFACodeStore {
-    codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+    /// The pending codes, keyed by the user they were sent to.
+    codes: HashMap<Email, PendingCode>,
+    /// The lifetime and attempt limit applied to every code.
+    policy: TwoFACodePolicy,
+}
+
+impl HashmapTwoFACodeStore {
+    /// Creates an empty store that applies the given policy to its codes.
+    pub fn new(policy: TwoFACodePolicy) -> Self {
+        Self {
+            codes: HashMap::new(),
+            policy,
+        }
+    }
+
+    fn is_expired(&self, pending: &PendingCode) -> bool {
+        Utc::now()
+            .signed_duration_since(pending.created_at)
+            .to_std()
+            .unwrap_or_default()
+            >= self.policy.lifetime
+    }
+
+    // Settles the pending login attempt, with `matches` telling whether the code presented
+    // for it is the right one.
+    fn consume(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        matches: impl FnOnce(&TwoFACode) -> bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let pending = self
+            .codes
+            .get(email)
+            .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+        if self.is_expired(pending) {
+            self.codes.remove(email);
+            return Err(TwoFACodeStoreError::Expired);
+        }
+        // Guesses against another login attempt are not counted, so that they cannot
+        // be used to invalidate the pending code.
+        if &pending.login_attempt_id != login_attempt_id {
+            return Err(TwoFACodeStoreError::IncorrectCode);
+        }
+        if !matches(&pending.code) {
+            let max_attempts = self.policy.max_attempts;
+            let pending = self.codes.get_mut(email).expect("pending code exists");
+            pending.failed_attempts += 1;
+            if pending.failed_attempts >= max_attempts {
+                self.codes.remove(email);
+                return Err(TwoFACodeStoreError::TooManyAttempts);
+            }
+            return Err(TwoFACodeStoreError::IncorrectCode);
+        }
+
+        self.codes.remove(email);
+        Ok(())
+    }
 }
 
-// TODO: implement TwoFACodeStore for HashmapTwoFACodeStore
 impl TwoFACodeStore for HashmapTwoFACodeStore {
     async fn add_code(
         &mut self,
@@ -18,12 +86,20 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
-        self.codes.insert(email, (login_attempt_id, code));
+        self.codes.insert(
+            email,
+            PendingCode {
+                login_attempt_id,
+                code,
+                created_at: Utc::now(),
+                failed_attempts: 0,
+            },
+        );
         Ok(())
     }
 
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
-        self.codes.remove(&email);
+        self.codes.remove(email);
         Ok(())
     }
 
@@ -31,17 +107,36 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         &self,
         email: &Email,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
-        match self.codes.get(&email) {
-            Some((login_attempt_id, two_fa_code)) => {
-                Ok((login_attempt_id.to_owned(), two_fa_code.to_owned()))
-            }
-            _ => Err(TwoFACodeStoreError::UnexpectedError),
+        match self.codes.get(email) {
+            Some(pending) if self.is_expired(pending) => Err(TwoFACodeStoreError::Expired),
+            Some(pending) => Ok((pending.login_attempt_id.to_owned(), pending.code.to_owned())),
+            _ => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
         }
     }
+
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, |pending| pending == code)
+    }
+
+    async fn consume_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        verified: bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, |_| verified)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
 
     #[tokio::test]
@@ -79,16 +174,20 @@ mod tests {
         let valid_email = Email::parse("email@example.com").unwrap();
         let delete_email = Email::parse("email2@example.com").unwrap();
         let mut store = HashmapTwoFACodeStore::default();
-        let _ = store.add_code(
-            valid_email.clone(),
-            LoginAttemptId::default(),
-            TwoFACode::default(),
-        );
-        let _ = store.add_code(
-            delete_email.clone(),
-            LoginAttemptId::default(),
-            TwoFACode::default(),
-        );
+        let _ = store
+            .add_code(
+                valid_email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await;
+        let _ = store
+            .add_code(
+                delete_email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await;
 
         let delete_code = store.remove_code(&delete_email).await;
         assert!(delete_code.is_ok());
@@ -96,4 +195,188 @@ mod tests {
         let deleted_code = store.get_code(&delete_email).await;
         assert!(deleted_code.is_err());
     }
+
+    #[tokio::test]
+    async fn test_consume_code_is_single_use() {
+        let email = Email::parse("email@example.com").unwrap();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        let mut store = HashmapTwoFACodeStore::default();
+        let _ = store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await;
+
+        let wrong_code = TwoFACode::parse(
+            if code.as_ref() == "000000" {
+                "111111"
+            } else {
+                "000000"
+            }
+            .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(
+            store
+                .consume_code(&email, &login_attempt_id, &wrong_code)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store
+                .consume_code(&email, &LoginAttemptId::default(), &code)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_code_is_rejected_and_removed() {
+        let email = Email::parse("email@example.com").unwrap();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        let mut store = HashmapTwoFACodeStore::new(TwoFACodePolicy {
+            lifetime: Duration::ZERO,
+            ..Default::default()
+        });
+        let _ = store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await;
+
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::Expired)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::Expired)
+        );
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_is_invalidated_after_max_attempts() {
+        let email = Email::parse("email@example.com").unwrap();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::parse("123456".to_owned()).unwrap();
+        let wrong_code = TwoFACode::parse("654321".to_owned()).unwrap();
+        let mut store = HashmapTwoFACodeStore::new(TwoFACodePolicy {
+            max_attempts: 3,
+            ..Default::default()
+        });
+        let _ = store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await;
+
+        // Guesses against another login attempt do not count.
+        for _ in 0..5 {
+            assert_eq!(
+                store
+                    .consume_code(&email, &LoginAttemptId::default(), &wrong_code)
+                    .await,
+                Err(TwoFACodeStoreError::IncorrectCode)
+            );
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                store
+                    .consume_code(&email, &login_attempt_id, &wrong_code)
+                    .await,
+                Err(TwoFACodeStoreError::IncorrectCode)
+            );
+        }
+        assert_eq!(
+            store
+                .consume_code(&email, &login_attempt_id, &wrong_code)
+                .await,
+            Err(TwoFACodeStoreError::TooManyAttempts)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+
+        // A new login starts over.
+        let login_attempt_id = LoginAttemptId::default();
+        let _ = store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await;
+        assert_eq!(
+            store
+                .consume_code(&email, &login_attempt_id, &wrong_code)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_attempt_shares_the_attempt_limit() {
+        let email = Email::parse("email@example.com").unwrap();
+        let login_attempt_id = LoginAttemptId::default();
+        let mut store = HashmapTwoFACodeStore::new(TwoFACodePolicy {
+            max_attempts: 2,
+            ..Default::default()
+        });
+        let _ = store
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                TwoFACode::default(),
+            )
+            .await;
+
+        assert_eq!(
+            store
+                .consume_attempt(&email, &LoginAttemptId::default(), true)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store
+                .consume_attempt(&email, &login_attempt_id, false)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store.consume_attempt(&email, &login_attempt_id, true).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_attempt(&email, &login_attempt_id, true).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+
+        let login_attempt_id = LoginAttemptId::default();
+        let _ = store
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                TwoFACode::default(),
+            )
+            .await;
+        let _ = store
+            .consume_attempt(&email, &login_attempt_id, false)
+            .await;
+        assert_eq!(
+            store
+                .consume_attempt(&email, &login_attempt_id, false)
+                .await,
+            Err(TwoFACodeStoreError::TooManyAttempts)
+        );
+    }
 }
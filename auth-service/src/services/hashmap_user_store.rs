@@ -1,13 +1,23 @@
 use crate::domain::{
-    models::{Email, Password, User},
+    models::{
+        Email, EmailChangeToken, PasswordHash, PasswordResetToken, RecoveryCode, TotpSecret,
+        TwoFAMethod, User,
+    },
     ports::{UserStore, UserStoreError},
 };
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Default, Clone)]
 pub struct HashmapUserStore {
     /// A hashmap to store users by their email.
     users: HashMap<String, User>,
+    /// The hashes of each user's recovery codes, by email.
+    recovery_codes: HashMap<String, HashSet<String>>,
+    /// The pending password reset tokens by hash, with their user and expiry.
+    password_reset_tokens: HashMap<String, (Email, DateTime<Utc>)>,
+    /// The pending email change tokens by hash, with their user, new address and expiry.
+    email_change_tokens: HashMap<String, (Email, Email, DateTime<Utc>)>,
 }
 
 impl UserStore for HashmapUserStore {
@@ -28,28 +38,291 @@ impl UserStore for HashmapUserStore {
         }
     }
 
-    /// Validates a user.
-    async fn validate_user(
+    /// Replaces the password hash of a user.
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: PasswordHash,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.password_hash = password_hash;
+        // A change asked for under the previous password must not go through.
+        self.email_change_tokens
+            .retain(|_, (owner, _, _)| owner != email);
+        Ok(())
+    }
+
+    /// Moves a user to another email address.
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        if self.users.contains_key(new_email.as_ref()) {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+        let mut user = self
+            .users
+            .remove(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.email = new_email.clone();
+        self.users.insert(new_email.as_ref().to_owned(), user);
+        if let Some(hashes) = self.recovery_codes.remove(email.as_ref()) {
+            self.recovery_codes
+                .insert(new_email.as_ref().to_owned(), hashes);
+        }
+        // A reset link sent to the previous address must not take over the account.
+        self.password_reset_tokens
+            .retain(|_, (owner, _)| owner != email);
+        self.email_change_tokens
+            .retain(|_, (owner, _, _)| owner != email);
+        Ok(())
+    }
+
+    /// Deletes a user and everything kept about them.
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        self.users
+            .remove(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        self.recovery_codes.remove(email.as_ref());
+        self.password_reset_tokens
+            .retain(|_, (owner, _)| owner != email);
+        self.email_change_tokens
+            .retain(|_, (owner, _, _)| owner != email);
+        Ok(())
+    }
+
+    /// Marks the email of a user as verified.
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.email_verified = true;
+        Ok(())
+    }
+
+    /// Counts a failed login for a user.
+    async fn record_failed_login(&mut self, email: &Email) -> Result<u32, UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.failed_login_attempts += 1;
+        Ok(user.failed_login_attempts)
+    }
+
+    /// Locks a user out until the given time.
+    async fn lock_user(
+        &mut self,
+        email: &Email,
+        locked_until: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.failed_login_attempts = 0;
+        user.locked_until = Some(locked_until);
+        Ok(())
+    }
+
+    /// Clears the failed logins and lock of a user.
+    async fn reset_failed_logins(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.failed_login_attempts = 0;
+        user.locked_until = None;
+        Ok(())
+    }
+
+    /// Keeps a TOTP secret waiting for confirmation.
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.pending_totp_secret = Some(secret);
+        Ok(())
+    }
+
+    /// Switches a user to another 2FA method.
+    async fn update_two_fa_method(
+        &mut self,
+        email: &Email,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.two_fa_method = method;
+        user.pending_totp_secret = None;
+        Ok(())
+    }
+
+    /// Records the use of a TOTP code unless it, or a later one, was used already.
+    async fn record_totp_use(&mut self, email: &Email, step: u64) -> Result<bool, UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email.as_ref())
+            .ok_or(UserStoreError::UserNotFound)?;
+        if user.totp_last_used_step.is_some_and(|last| last >= step) {
+            return Ok(false);
+        }
+        user.totp_last_used_step = Some(step);
+        Ok(true)
+    }
+
+    /// Replaces the recovery codes of a user.
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: &[RecoveryCode],
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        let hashes = codes.iter().map(RecoveryCode::hash).collect();
+        self.recovery_codes
+            .insert(email.as_ref().to_owned(), hashes);
+        Ok(())
+    }
+
+    /// Checks a recovery code of a user, leaving it in place.
+    async fn has_recovery_code(
+        &self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self
+            .recovery_codes
+            .get(email.as_ref())
+            .is_some_and(|hashes| hashes.contains(&code.hash())))
+    }
+
+    /// Removes a recovery code of a user, if it is one of theirs.
+    async fn consume_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self
+            .recovery_codes
+            .get_mut(email.as_ref())
+            .is_some_and(|hashes| hashes.remove(&code.hash())))
+    }
+
+    /// Counts the recovery codes a user has left.
+    async fn count_recovery_codes(&self, email: &Email) -> Result<usize, UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        Ok(self
+            .recovery_codes
+            .get(email.as_ref())
+            .map_or(0, HashSet::len))
+    }
+
+    /// Stores a password reset token of a user, replacing their pending one.
+    async fn set_password_reset_token(
+        &mut self,
+        email: &Email,
+        token: &PasswordResetToken,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        // Expired tokens can no longer be used, so they are dropped on the way.
+        let now = Utc::now();
+        self.password_reset_tokens
+            .retain(|_, (owner, expires_at)| owner != email && *expires_at > now);
+        self.password_reset_tokens
+            .insert(token.hash(), (email.clone(), expires_at));
+        Ok(())
+    }
+
+    /// Returns the user of a password reset token, unless it expired.
+    async fn get_password_reset_email(
         &self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        Ok(self
+            .password_reset_tokens
+            .get(&token.hash())
+            .filter(|(_, expires_at)| *expires_at > Utc::now())
+            .map(|(email, _)| email.clone()))
+    }
+
+    /// Removes a password reset token and returns its user, unless it expired.
+    async fn consume_password_reset_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        Ok(self
+            .password_reset_tokens
+            .remove(&token.hash())
+            .filter(|(_, expires_at)| *expires_at > Utc::now())
+            .map(|(email, _)| email))
+    }
+
+    /// Stores an email change token of a user, replacing their pending one.
+    async fn set_email_change_token(
+        &mut self,
         email: &Email,
-        password: &Password,
+        new_email: &Email,
+        token: &EmailChangeToken,
+        expires_at: DateTime<Utc>,
     ) -> Result<(), UserStoreError> {
-        match self.users.get(email.as_ref()) {
-            Some(user) => {
-                if user.password.as_ref() == password.as_ref() {
-                    Ok(())
-                } else {
-                    Err(UserStoreError::InvalidCredentials)
-                }
-            }
-            None => Err(UserStoreError::UserNotFound),
+        if !self.users.contains_key(email.as_ref()) {
+            return Err(UserStoreError::UserNotFound);
         }
+        // Expired tokens can no longer be used, so they are dropped on the way.
+        let now = Utc::now();
+        self.email_change_tokens
+            .retain(|_, (owner, _, expires_at)| owner != email && *expires_at > now);
+        self.email_change_tokens
+            .insert(token.hash(), (email.clone(), new_email.clone(), expires_at));
+        Ok(())
+    }
+
+    /// Removes an email change token and returns its user and new address, unless it
+    /// expired.
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailChangeToken,
+    ) -> Result<Option<(Email, Email)>, UserStoreError> {
+        Ok(self
+            .email_change_tokens
+            .remove(&token.hash())
+            .filter(|(_, _, expires_at)| *expires_at > Utc::now())
+            .map(|(email, new_email, _)| (email, new_email)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::SubsecRound;
 
     fn default_email(email: &'static str) -> Email {
         Email::parse(email)
@@ -59,10 +332,10 @@ mod tests {
             .unwrap()
     }
 
-    fn default_password(password: &'static str) -> Password {
-        Password::parse(password)
+    fn default_password_hash(hash: &'static str) -> PasswordHash {
+        PasswordHash::parse(format!("$argon2id$v=19$m=1024,t=1,p=1$c29tZXNhbHQ${hash}"))
             .map_err(move |_| {
-                panic!("Failed to create password");
+                panic!("Failed to create password hash");
             })
             .unwrap()
     }
@@ -71,8 +344,8 @@ mod tests {
     async fn test_add_user() {
         let mut store = HashmapUserStore::default();
         let email = default_email("user@example.com");
-        let password = default_password("password123");
-        let user = User::new(email, password, false);
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email, password_hash, TwoFAMethod::None);
         let result = store.add_user(&user).await;
         assert_eq!(result, Ok(()));
         let result = store.add_user(&user).await;
@@ -83,8 +356,8 @@ mod tests {
     async fn test_get_user() {
         let mut store = HashmapUserStore::default();
         let email = default_email("user@example.com");
-        let password = default_password("password123");
-        let user: User = User::new(email.clone(), password, false);
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user: User = User::new(email.clone(), password_hash, TwoFAMethod::None);
         let _ = store.add_user(&user).await;
         let result = store.get_user(&email).await;
         assert!(result.is_ok());
@@ -95,19 +368,371 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_validate_user() {
+    async fn test_update_password() {
         let mut store = HashmapUserStore::default();
         let email = default_email("user@example.com");
         let another_email = default_email("user2@example.com");
-        let another_password = default_password("password123");
-        let password = default_password("password124");
-        let user: User = User::new(email.clone(), password.clone(), false);
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let new_password_hash = default_password_hash("YmJiYmJiYmJiYmJiYmJiYg");
+        let user: User = User::new(email.clone(), password_hash, TwoFAMethod::None);
         let _ = store.add_user(&user).await;
-        let result = store.validate_user(&email, &password).await;
+        let result = store
+            .update_password(&email, new_password_hash.clone())
+            .await;
         assert_eq!(result, Ok(()));
-        let result = store.validate_user(&email, &another_password).await;
-        assert_eq!(result, Err(UserStoreError::InvalidCredentials));
-        let result = store.validate_user(&another_email, &password).await;
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.password_hash, new_password_hash);
+        let result = store
+            .update_password(&another_email, new_password_hash)
+            .await;
         assert_eq!(result, Err(UserStoreError::UserNotFound));
     }
+
+    #[tokio::test]
+    async fn test_mark_email_verified() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        let _ = store.add_user(&user).await;
+        assert!(!store.get_user(&email).await.unwrap().email_verified);
+
+        assert_eq!(store.mark_email_verified(&email).await, Ok(()));
+        assert!(store.get_user(&email).await.unwrap().email_verified);
+        assert_eq!(
+            store.mark_email_verified(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_logins_and_lock() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        let _ = store.add_user(&user).await;
+
+        assert_eq!(store.record_failed_login(&email).await, Ok(1));
+        assert_eq!(store.record_failed_login(&email).await, Ok(2));
+
+        let locked_until = Utc::now().trunc_subsecs(0) + chrono::Duration::minutes(15);
+        assert_eq!(store.lock_user(&email, locked_until).await, Ok(()));
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.failed_login_attempts, 0);
+        assert_eq!(result.locked_until, Some(locked_until));
+
+        assert_eq!(store.record_failed_login(&email).await, Ok(1));
+        assert_eq!(store.reset_failed_logins(&email).await, Ok(()));
+        assert_eq!(store.get_user(&email).await, Ok(user));
+
+        assert_eq!(
+            store.record_failed_login(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.lock_user(&another_email, locked_until).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.reset_failed_logins(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_totp_enrollment() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        let _ = store.add_user(&user).await;
+
+        // A pending secret leaves the current method in force.
+        let secret = TotpSecret::default();
+        assert_eq!(
+            store.set_pending_totp_secret(&email, secret.clone()).await,
+            Ok(())
+        );
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.two_fa_method, TwoFAMethod::Email);
+        assert_eq!(result.pending_totp_secret, Some(secret.clone()));
+
+        let method = TwoFAMethod::Totp(secret);
+        assert_eq!(
+            store.update_two_fa_method(&email, method.clone()).await,
+            Ok(())
+        );
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.two_fa_method, method);
+        assert_eq!(result.pending_totp_secret, None);
+
+        assert_eq!(
+            store
+                .set_pending_totp_secret(&another_email, TotpSecret::default())
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store
+                .update_two_fa_method(&another_email, TwoFAMethod::None)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_totp_use_accepts_each_step_once() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let _ = store
+            .add_user(&User::new(email.clone(), password_hash, TwoFAMethod::None))
+            .await;
+
+        assert_eq!(store.record_totp_use(&email, 100).await, Ok(true));
+        assert_eq!(store.record_totp_use(&email, 100).await, Ok(false));
+        assert_eq!(store.record_totp_use(&email, 99).await, Ok(false));
+        assert_eq!(store.record_totp_use(&email, 101).await, Ok(true));
+        assert_eq!(
+            store.get_user(&email).await.unwrap().totp_last_used_step,
+            Some(101)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recovery_codes_are_consumed_once() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let _ = store
+            .add_user(&User::new(email.clone(), password_hash, TwoFAMethod::Email))
+            .await;
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(0));
+
+        let codes = [RecoveryCode::default(), RecoveryCode::default()];
+        assert_eq!(store.set_recovery_codes(&email, &codes).await, Ok(()));
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(2));
+        assert_eq!(store.has_recovery_code(&email, &codes[0]).await, Ok(true));
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[0]).await,
+            Ok(true)
+        );
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[0]).await,
+            Ok(false)
+        );
+        assert_eq!(store.has_recovery_code(&email, &codes[0]).await, Ok(false));
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(1));
+
+        // New codes replace the previous ones.
+        let new_codes = [RecoveryCode::default()];
+        assert_eq!(store.set_recovery_codes(&email, &new_codes).await, Ok(()));
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[1]).await,
+            Ok(false)
+        );
+        assert_eq!(
+            store.consume_recovery_code(&email, &new_codes[0]).await,
+            Ok(true)
+        );
+
+        assert_eq!(
+            store.set_recovery_codes(&another_email, &codes).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.consume_recovery_code(&another_email, &codes[0]).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_tokens_are_consumed_once() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let _ = store
+            .add_user(&User::new(email.clone(), password_hash, TwoFAMethod::None))
+            .await;
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(60);
+
+        let token = PasswordResetToken::default();
+        assert_eq!(
+            store
+                .set_password_reset_token(&email, &token, expires_at)
+                .await,
+            Ok(())
+        );
+        // Looking the token up leaves it in place.
+        assert_eq!(
+            store.get_password_reset_email(&token).await,
+            Ok(Some(email.clone()))
+        );
+        assert_eq!(
+            store.consume_password_reset_token(&token).await,
+            Ok(Some(email.clone()))
+        );
+        assert_eq!(store.consume_password_reset_token(&token).await, Ok(None));
+        assert_eq!(store.get_password_reset_email(&token).await, Ok(None));
+
+        // A new token replaces the pending one.
+        let first = PasswordResetToken::default();
+        let second = PasswordResetToken::default();
+        let _ = store
+            .set_password_reset_token(&email, &first, expires_at)
+            .await;
+        let _ = store
+            .set_password_reset_token(&email, &second, expires_at)
+            .await;
+        assert_eq!(store.consume_password_reset_token(&first).await, Ok(None));
+        assert_eq!(
+            store.consume_password_reset_token(&second).await,
+            Ok(Some(email.clone()))
+        );
+
+        let expired = PasswordResetToken::default();
+        let _ = store
+            .set_password_reset_token(&email, &expired, Utc::now())
+            .await;
+        assert_eq!(store.get_password_reset_email(&expired).await, Ok(None));
+        assert_eq!(store.consume_password_reset_token(&expired).await, Ok(None));
+
+        assert_eq!(
+            store
+                .set_password_reset_token(&default_email("user2@example.com"), &token, expires_at)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_change_tokens_are_consumed_once() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let new_email = default_email("new@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let _ = store
+            .add_user(&User::new(
+                email.clone(),
+                password_hash.clone(),
+                TwoFAMethod::None,
+            ))
+            .await;
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(60);
+
+        let token = EmailChangeToken::default();
+        assert_eq!(
+            store
+                .set_email_change_token(&email, &new_email, &token, expires_at)
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_email_change_token(&token).await,
+            Ok(Some((email.clone(), new_email.clone())))
+        );
+        assert_eq!(store.consume_email_change_token(&token).await, Ok(None));
+
+        // A new token replaces the pending one.
+        let first = EmailChangeToken::default();
+        let second = EmailChangeToken::default();
+        let _ = store
+            .set_email_change_token(&email, &new_email, &first, expires_at)
+            .await;
+        let _ = store
+            .set_email_change_token(&email, &new_email, &second, expires_at)
+            .await;
+        assert_eq!(store.consume_email_change_token(&first).await, Ok(None));
+
+        // Changing the password drops the pending change.
+        let _ = store.update_password(&email, password_hash).await;
+        assert_eq!(store.consume_email_change_token(&second).await, Ok(None));
+
+        let expired = EmailChangeToken::default();
+        let _ = store
+            .set_email_change_token(&email, &new_email, &expired, Utc::now())
+            .await;
+        assert_eq!(store.consume_email_change_token(&expired).await, Ok(None));
+
+        // Deleting the user drops the pending change.
+        let _ = store
+            .set_email_change_token(&email, &new_email, &token, expires_at)
+            .await;
+        let _ = store.delete_user(&email).await;
+        assert_eq!(store.consume_email_change_token(&token).await, Ok(None));
+        assert_eq!(
+            store
+                .set_email_change_token(&email, &new_email, &token, expires_at)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_email() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let new_email = default_email("new@example.com");
+        let taken_email = default_email("taken@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        for email in [&email, &taken_email] {
+            let user = User::new(email.clone(), password_hash.clone(), TwoFAMethod::Email);
+            store.add_user(&user).await.unwrap();
+        }
+        let codes = [RecoveryCode::default()];
+        store.set_recovery_codes(&email, &codes).await.unwrap();
+
+        assert_eq!(
+            store.update_email(&email, &taken_email).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+        assert_eq!(store.update_email(&email, &new_email).await, Ok(()));
+        assert_eq!(
+            store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(store.get_user(&new_email).await.unwrap().email, new_email);
+        assert_eq!(store.count_recovery_codes(&new_email).await, Ok(1));
+        assert_eq!(
+            store.update_email(&email, &new_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let mut store = HashmapUserStore::default();
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        store.add_user(&user).await.unwrap();
+        let codes = [RecoveryCode::default()];
+        store.set_recovery_codes(&email, &codes).await.unwrap();
+        let token = PasswordResetToken::default();
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(600);
+        store
+            .set_password_reset_token(&email, &token, expires_at)
+            .await
+            .unwrap();
+
+        assert_eq!(store.delete_user(&email).await, Ok(()));
+        assert_eq!(
+            store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.delete_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+
+        // Someone signing up again with the address inherits nothing.
+        store.add_user(&user).await.unwrap();
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(0));
+        assert_eq!(store.consume_password_reset_token(&token).await, Ok(None));
+    }
 }
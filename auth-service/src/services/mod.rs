@@ -1,4 +1,16 @@
 pub mod banned_user_store;
+pub mod hashmap_oauth_client_store;
+pub mod hashmap_passkey_store;
+pub mod hashmap_rate_limiter;
+pub mod hashmap_refresh_token_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
 pub mod mock_email_client;
+pub mod postgres_oauth_client_store;
+pub mod postgres_passkey_store;
+pub mod postgres_refresh_token_store;
+pub mod postgres_two_fa_code_store;
+pub mod postgres_user_store;
+pub mod redis_banned_store;
+pub mod redis_two_fa_code_store;
+pub mod sqlite_user_store;
@@ -0,0 +1,129 @@
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+use crate::domain::{
+    models::{AuthorizationCode, AuthorizationCodeRecord, CodeChallenge, Email, OAuthClient},
+    ports::{OAuthClientStore, OAuthClientStoreError},
+};
+
+/// An OAuth client store backed by a PostgreSQL database, shared by every service replica.
+#[derive(Clone)]
+pub struct PostgresOAuthClientStore {
+    /// The connection pool to the PostgreSQL database.
+    pool: PgPool,
+}
+
+impl PostgresOAuthClientStore {
+    /// Creates a new store on top of an already migrated connection pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl OAuthClientStore for PostgresOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        sqlx::query(
+            "INSERT INTO oauth_clients (client_id, name, redirect_uris, client_secret_hash)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (client_id) DO UPDATE
+             SET name = EXCLUDED.name,
+                 redirect_uris = EXCLUDED.redirect_uris,
+                 client_secret_hash = EXCLUDED.client_secret_hash",
+        )
+        .bind(&client.client_id)
+        .bind(&client.name)
+        .bind(&client.redirect_uris)
+        .bind(&client.client_secret_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        let row = sqlx::query(
+            "SELECT client_id, name, redirect_uris, client_secret_hash
+             FROM oauth_clients WHERE client_id = $1",
+        )
+        .bind(client_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?
+        .ok_or(OAuthClientStoreError::ClientNotFound)?;
+
+        Ok(OAuthClient {
+            client_id: row.get("client_id"),
+            name: row.get("name"),
+            redirect_uris: row.get("redirect_uris"),
+            client_secret_hash: row.get("client_secret_hash"),
+        })
+    }
+
+    async fn add_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+        record: AuthorizationCodeRecord,
+    ) -> Result<(), OAuthClientStoreError> {
+        // Codes nobody redeemed in time are dropped on the way.
+        sqlx::query("DELETE FROM oauth_authorization_codes WHERE expires_at <= now()")
+            .execute(&self.pool)
+            .await
+            .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO oauth_authorization_codes
+                 (code_hash, client_id, redirect_uri, email, scope, nonce, code_challenge, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(code.hash())
+        .bind(&record.client_id)
+        .bind(&record.redirect_uri)
+        .bind(record.email.as_ref())
+        .bind(&record.scope)
+        .bind(&record.nonce)
+        .bind(record.code_challenge.as_ref())
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn consume_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeRecord, OAuthClientStoreError> {
+        // Deleting the row lets only one of several concurrent requests redeem the code.
+        let row = sqlx::query(
+            "DELETE FROM oauth_authorization_codes WHERE code_hash = $1
+             RETURNING client_id, redirect_uri, email, scope, nonce, code_challenge, expires_at,
+                       expires_at <= now() AS expired",
+        )
+        .bind(code.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?
+        .ok_or(OAuthClientStoreError::CodeNotFound)?;
+
+        if row.get("expired") {
+            return Err(OAuthClientStoreError::CodeExpired);
+        }
+        record_from_row(&row)
+    }
+}
+
+// Map an `oauth_authorization_codes` row back into the domain model.
+fn record_from_row(row: &PgRow) -> Result<AuthorizationCodeRecord, OAuthClientStoreError> {
+    let email =
+        Email::parse(row.get("email")).map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+    let code_challenge = CodeChallenge::parse(row.get("code_challenge"))
+        .map_err(|_| OAuthClientStoreError::UnexpectedError)?;
+    Ok(AuthorizationCodeRecord {
+        client_id: row.get("client_id"),
+        redirect_uri: row.get("redirect_uri"),
+        email,
+        scope: row.get("scope"),
+        nonce: row.get("nonce"),
+        code_challenge,
+        expires_at: row.get("expires_at"),
+    })
+}
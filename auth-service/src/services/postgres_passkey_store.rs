@@ -0,0 +1,201 @@
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+use crate::domain::{
+    models::{
+        CosePublicKey, Email, PasskeyCeremony, PasskeyChallenge, PasskeyChallengeRecord,
+        PasskeyCredential,
+    },
+    ports::{PasskeyStore, PasskeyStoreError},
+};
+
+/// A passkey store backed by a PostgreSQL database, shared by every service replica.
+#[derive(Clone)]
+pub struct PostgresPasskeyStore {
+    /// The connection pool to the PostgreSQL database.
+    pool: PgPool,
+}
+
+impl PostgresPasskeyStore {
+    /// Creates a new store on top of an already migrated connection pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl PasskeyStore for PostgresPasskeyStore {
+    async fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> Result<(), PasskeyStoreError> {
+        sqlx::query(
+            "INSERT INTO passkey_credentials (credential_id, email, public_key, sign_count)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&credential.credential_id)
+        .bind(credential.email.as_ref())
+        .bind(credential.public_key.as_ref())
+        .bind(i64::from(credential.sign_count))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => {
+                PasskeyStoreError::CredentialAlreadyExists
+            }
+            _ => PasskeyStoreError::UnexpectedError,
+        })?;
+        Ok(())
+    }
+
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), PasskeyStoreError> {
+        sqlx::query("UPDATE passkey_credentials SET email = $1 WHERE email = $2")
+            .bind(new_email.as_ref())
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn delete_credentials(&mut self, email: &Email) -> Result<(), PasskeyStoreError> {
+        sqlx::query("DELETE FROM passkey_credentials WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<PasskeyCredential>, PasskeyStoreError> {
+        let rows = sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count
+             FROM passkey_credentials WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+
+        rows.iter().map(credential_from_row).collect()
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyCredential, PasskeyStoreError> {
+        let row = sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count
+             FROM passkey_credentials WHERE credential_id = $1",
+        )
+        .bind(credential_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?
+        .ok_or(PasskeyStoreError::CredentialNotFound)?;
+
+        credential_from_row(&row)
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<bool, PasskeyStoreError> {
+        // The guard makes concurrent uses of the same assertion race for a single update.
+        let result = sqlx::query(
+            "UPDATE passkey_credentials SET sign_count = $2
+             WHERE credential_id = $1 AND sign_count < $2",
+        )
+        .bind(credential_id)
+        .bind(i64::from(sign_count))
+        .execute(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            // Tell an unknown credential apart from a counter that did not increase.
+            self.get_credential(credential_id).await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    async fn add_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+        record: PasskeyChallengeRecord,
+    ) -> Result<(), PasskeyStoreError> {
+        // Ceremonies nobody completed in time are dropped on the way.
+        sqlx::query("DELETE FROM passkey_challenges WHERE expires_at <= now()")
+            .execute(&self.pool)
+            .await
+            .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO passkey_challenges (challenge, ceremony, email, expires_at)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(challenge.as_ref())
+        .bind(record.ceremony.name())
+        .bind(record.email.as_ref().map(AsRef::<str>::as_ref))
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn consume_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+    ) -> Result<PasskeyChallengeRecord, PasskeyStoreError> {
+        // Deleting the row lets only one of several concurrent requests answer the challenge.
+        let row = sqlx::query(
+            "DELETE FROM passkey_challenges WHERE challenge = $1
+             RETURNING ceremony, email, expires_at, expires_at <= now() AS expired",
+        )
+        .bind(challenge.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?
+        .ok_or(PasskeyStoreError::ChallengeNotFound)?;
+
+        if row.get("expired") {
+            return Err(PasskeyStoreError::ChallengeExpired);
+        }
+
+        let ceremony = PasskeyCeremony::parse(row.get("ceremony"))
+            .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+        let email = row
+            .get::<Option<&str>, _>("email")
+            .map(Email::parse)
+            .transpose()
+            .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+        Ok(PasskeyChallengeRecord {
+            ceremony,
+            email,
+            expires_at: row.get("expires_at"),
+        })
+    }
+}
+
+// Map a `passkey_credentials` row back into the domain model.
+fn credential_from_row(row: &PgRow) -> Result<PasskeyCredential, PasskeyStoreError> {
+    let email = Email::parse(row.get("email")).map_err(|_| PasskeyStoreError::UnexpectedError)?;
+    let public_key = CosePublicKey::parse(row.get("public_key"))
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+    let sign_count = u32::try_from(row.get::<i64, _>("sign_count"))
+        .map_err(|_| PasskeyStoreError::UnexpectedError)?;
+    Ok(PasskeyCredential {
+        credential_id: row.get("credential_id"),
+        email,
+        public_key,
+        sign_count,
+    })
+}
@@ -0,0 +1,121 @@
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+use crate::domain::{
+    models::{Email, RefreshToken, RefreshTokenRecord},
+    ports::{RefreshTokenStore, RefreshTokenStoreError},
+};
+
+/// A refresh token store backed by a PostgreSQL database, shared by every service replica.
+#[derive(Clone)]
+pub struct PostgresRefreshTokenStore {
+    /// The connection pool to the PostgreSQL database.
+    pool: PgPool,
+}
+
+impl PostgresRefreshTokenStore {
+    /// Creates a new store on top of an already migrated connection pool.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RefreshTokenStore for PostgresRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        // Expired tokens can no longer be used or reused, so they are dropped on the way.
+        sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= now()")
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, email, family_id, expires_at)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(token.hash())
+        .bind(record.email.as_ref())
+        .bind(&record.family_id)
+        .bind(record.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        sqlx::query("SELECT email, family_id, expires_at FROM refresh_tokens WHERE token_hash = $1")
+            .bind(token.hash())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+            .ok_or(RefreshTokenStoreError::TokenNotFound)
+            .and_then(|row| record_from_row(&row))
+    }
+
+    async fn use_token(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        // The conditional UPDATE lets only one of several concurrent requests use the token.
+        let used = sqlx::query(
+            "UPDATE refresh_tokens SET used = TRUE
+             WHERE token_hash = $1 AND NOT used AND expires_at > now()
+             RETURNING email, family_id, expires_at",
+        )
+        .bind(token.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        if let Some(row) = used {
+            return record_from_row(&row);
+        }
+
+        let row = sqlx::query("SELECT family_id, used FROM refresh_tokens WHERE token_hash = $1")
+            .bind(token.hash())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+            .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+        match row.get("used") {
+            true => Err(RefreshTokenStoreError::TokenReused {
+                family_id: row.get("family_id"),
+            }),
+            false => Err(RefreshTokenStoreError::TokenExpired),
+        }
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE family_id = $1")
+            .bind(family_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+        Ok(())
+    }
+}
+
+// Map a `refresh_tokens` row back into the domain model.
+fn record_from_row(row: &PgRow) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+    let email =
+        Email::parse(row.get("email")).map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+    Ok(RefreshTokenRecord {
+        email,
+        family_id: row.get("family_id"),
+        expires_at: row.get("expires_at"),
+    })
+}
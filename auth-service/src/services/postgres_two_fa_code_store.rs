@@ -0,0 +1,181 @@
+use sqlx::{PgPool, Row};
+
+use crate::domain::{
+    models::{Email, LoginAttemptId, TwoFACode, TwoFACodePolicy},
+    ports::{TwoFACodeStore, TwoFACodeStoreError},
+};
+
+/// A 2FA code store backed by a PostgreSQL database, shared by every service replica.
+#[derive(Clone)]
+pub struct PostgresTwoFACodeStore {
+    /// The connection pool to the PostgreSQL database.
+    pool: PgPool,
+    /// The lifetime and attempt limit applied to every code.
+    policy: TwoFACodePolicy,
+}
+
+impl PostgresTwoFACodeStore {
+    /// Creates a new store on top of an already migrated connection pool.
+    pub fn new(pool: PgPool, policy: TwoFACodePolicy) -> Self {
+        Self { pool, policy }
+    }
+
+    // Tells a code pending for another login attempt apart from no code at all.
+    async fn pending_code_error(&self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        let pending = sqlx::query("SELECT 1 FROM two_fa_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        match pending {
+            Some(_) => Err(TwoFACodeStoreError::IncorrectCode),
+            None => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+
+    // Settles the pending login attempt. The presented code is either compared in SQL, or
+    // vouched for by the caller through `verified` when the store does not hold it.
+    async fn consume(
+        &self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: Option<&TwoFACode>,
+        verified: bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        // A single DELETE removes either a matching code or an expired one, so two
+        // concurrent requests can never both consume the same code.
+        let consumed = sqlx::query(
+            "DELETE FROM two_fa_codes
+             WHERE email = $1
+               AND ((login_attempt_id = $2 AND (code = $3 OR $5))
+                    OR created_at <= now() - make_interval(secs => $4))
+             RETURNING created_at <= now() - make_interval(secs => $4) AS expired",
+        )
+        .bind(email.as_ref())
+        .bind(login_attempt_id.as_ref())
+        .bind(code.map(AsRef::<str>::as_ref))
+        .bind(self.policy.lifetime.as_secs_f64())
+        .bind(verified)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+        match consumed {
+            Some(row) if row.get::<bool, _>("expired") => Err(TwoFACodeStoreError::Expired),
+            Some(_) => Ok(()),
+            None => {
+                // Count the guess against the pending code of this login attempt only, so
+                // that guesses against another attempt cannot invalidate it.
+                let failed = sqlx::query(
+                    "UPDATE two_fa_codes SET failed_attempts = failed_attempts + 1
+                     WHERE email = $1 AND login_attempt_id = $2
+                     RETURNING failed_attempts",
+                )
+                .bind(email.as_ref())
+                .bind(login_attempt_id.as_ref())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+                match failed {
+                    Some(row)
+                        if row.get::<i32, _>("failed_attempts") as u32
+                            >= self.policy.max_attempts =>
+                    {
+                        sqlx::query(
+                            "DELETE FROM two_fa_codes WHERE email = $1 AND login_attempt_id = $2",
+                        )
+                        .bind(email.as_ref())
+                        .bind(login_attempt_id.as_ref())
+                        .execute(&self.pool)
+                        .await
+                        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+                        Err(TwoFACodeStoreError::TooManyAttempts)
+                    }
+                    Some(_) => Err(TwoFACodeStoreError::IncorrectCode),
+                    None => self.pending_code_error(email).await,
+                }
+            }
+        }
+    }
+}
+
+impl TwoFACodeStore for PostgresTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        // A new login replaces any pending code for the same user.
+        sqlx::query(
+            "INSERT INTO two_fa_codes (email, login_attempt_id, code, created_at)
+             VALUES ($1, $2, $3, now())
+             ON CONFLICT (email) DO UPDATE
+             SET login_attempt_id = EXCLUDED.login_attempt_id, code = EXCLUDED.code,
+                 created_at = EXCLUDED.created_at, failed_attempts = 0",
+        )
+        .bind(email.as_ref())
+        .bind(login_attempt_id.as_ref())
+        .bind(code.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        sqlx::query("DELETE FROM two_fa_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok(())
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        let row = sqlx::query(
+            "SELECT login_attempt_id, code,
+                    created_at <= now() - make_interval(secs => $2) AS expired
+             FROM two_fa_codes WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .bind(self.policy.lifetime.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?
+        .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+        if row.get::<bool, _>("expired") {
+            return Err(TwoFACodeStoreError::Expired);
+        }
+
+        let login_attempt_id = LoginAttemptId::parse(row.get("login_attempt_id"))
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        let code =
+            TwoFACode::parse(row.get("code")).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok((login_attempt_id, code))
+    }
+
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, Some(code), false)
+            .await
+    }
+
+    async fn consume_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        verified: bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, None, verified).await
+    }
+}
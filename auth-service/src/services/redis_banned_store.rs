@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use redis::{AsyncCommands, aio::ConnectionManager};
+
+use crate::domain::ports::{BannedStore, BannedStoreError};
+
+const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const BANNED_SUBJECT_KEY_PREFIX: &str = "banned_subject:";
+
+/// A store for banned token IDs backed by Redis, shared by every service replica.
+/// Entries are evicted by Redis once the token can no longer be validated.
+#[derive(Clone)]
+pub struct RedisBannedStore {
+    /// The connection to the Redis server.
+    connection: ConnectionManager,
+}
+
+impl RedisBannedStore {
+    /// Creates a new store on top of a Redis connection.
+    pub fn new(connection: ConnectionManager) -> Self {
+        Self { connection }
+    }
+}
+
+impl BannedStore for RedisBannedStore {
+    /// Checks if the token with this ID is banned.
+    async fn is_banned(&self, jti: &str) -> Result<bool, BannedStoreError> {
+        self.connection
+            .clone()
+            .exists(get_key(jti))
+            .await
+            .map_err(|_| BannedStoreError::UnexpectedError)
+    }
+
+    /// Bans the token with this ID, letting Redis evict the entry at `expires_at`.
+    async fn add_token(
+        &mut self,
+        jti: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BannedStoreError> {
+        let ttl = (expires_at - Utc::now()).num_seconds();
+
+        // A token past its expiry can no longer be validated, so there is nothing to ban.
+        if ttl <= 0 {
+            return Ok(());
+        }
+
+        self.connection
+            .set_ex::<_, _, ()>(get_key(jti), true, ttl as u64)
+            .await
+            .map_err(|_| BannedStoreError::UnexpectedError)
+    }
+
+    /// Bans the tokens issued to this subject so far, letting Redis evict the entry at
+    /// `expires_at`. The entry holds the issue time as a Unix timestamp.
+    async fn ban_subject(
+        &mut self,
+        sub: &str,
+        issued_until: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BannedStoreError> {
+        let ttl = (expires_at - Utc::now()).num_seconds();
+
+        // Every token issued so far expired already, so there is nothing to ban.
+        if ttl <= 0 {
+            return Ok(());
+        }
+
+        self.connection
+            .set_ex::<_, _, ()>(get_subject_key(sub), issued_until.timestamp(), ttl as u64)
+            .await
+            .map_err(|_| BannedStoreError::UnexpectedError)
+    }
+
+    /// Gets the time up to which the tokens of this subject are banned.
+    async fn subject_banned_until(
+        &self,
+        sub: &str,
+    ) -> Result<Option<DateTime<Utc>>, BannedStoreError> {
+        let issued_until: Option<i64> = self
+            .connection
+            .clone()
+            .get(get_subject_key(sub))
+            .await
+            .map_err(|_| BannedStoreError::UnexpectedError)?;
+        issued_until
+            .map(|timestamp| {
+                DateTime::from_timestamp(timestamp, 0).ok_or(BannedStoreError::UnexpectedError)
+            })
+            .transpose()
+    }
+}
+
+fn get_key(jti: &str) -> String {
+    format!("{BANNED_TOKEN_KEY_PREFIX}{jti}")
+}
+
+fn get_subject_key(sub: &str) -> String {
+    format!("{BANNED_SUBJECT_KEY_PREFIX}{sub}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_redis_connection;
+    use chrono::TimeDelta;
+
+    async fn store() -> RedisBannedStore {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".to_owned());
+        let connection = get_redis_connection(&url)
+            .await
+            .expect("Failed to connect to Redis");
+        RedisBannedStore::new(connection)
+    }
+
+    fn token() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_add_token() {
+        let mut banned_store = store().await;
+        let token = token();
+        banned_store
+            .add_token(&token, Utc::now() + TimeDelta::seconds(600))
+            .await
+            .unwrap();
+        assert!(banned_store.is_banned(&token).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_is_banned() {
+        let mut banned_store = store().await;
+        let banned_token = token();
+        banned_store
+            .add_token(&banned_token, Utc::now() + TimeDelta::seconds(600))
+            .await
+            .unwrap();
+        assert!(!banned_store.is_banned(&token()).await.unwrap());
+        assert!(banned_store.is_banned(&banned_token).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_entries_expire_with_the_token() {
+        let mut banned_store = store().await;
+        let token = token();
+        banned_store
+            .add_token(&token, Utc::now() + TimeDelta::seconds(600))
+            .await
+            .unwrap();
+        assert!(banned_store.is_banned(&token).await.unwrap());
+        let ttl: i64 = banned_store.connection.ttl(get_key(&token)).await.unwrap();
+        assert!(ttl > 0 && ttl <= 600);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_ban_subject() {
+        let mut banned_store = store().await;
+        let subject = token();
+        let issued_until = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        assert_eq!(banned_store.subject_banned_until(&subject).await, Ok(None));
+
+        banned_store
+            .ban_subject(&subject, issued_until, Utc::now() + TimeDelta::seconds(600))
+            .await
+            .unwrap();
+        assert_eq!(
+            banned_store.subject_banned_until(&subject).await,
+            Ok(Some(issued_until))
+        );
+        let ttl: i64 = banned_store
+            .connection
+            .ttl(get_subject_key(&subject))
+            .await
+            .unwrap();
+        assert!(ttl > 0 && ttl <= 600);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_expired_token_is_not_stored() {
+        let mut banned_store = store().await;
+        let token = token();
+        banned_store
+            .add_token(&token, Utc::now() - TimeDelta::seconds(1))
+            .await
+            .unwrap();
+        assert!(!banned_store.is_banned(&token).await.unwrap());
+    }
+}
@@ -0,0 +1,406 @@
+use chrono::{DateTime, Utc};
+use redis::{AsyncCommands, Script, aio::ConnectionManager};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    models::{Email, LoginAttemptId, TwoFACode, TwoFACodePolicy},
+    ports::{TwoFACodeStore, TwoFACodeStoreError},
+};
+
+const TWO_FA_CODE_KEY_PREFIX: &str = "two_fa_code:";
+const FAILED_ATTEMPTS_KEY_PREFIX: &str = "two_fa_failed_attempts:";
+
+// Deletes the key only if it still holds the value that was checked, so that a code
+// replaced or consumed in the meantime is left alone.
+const COMPARE_AND_DELETE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// A 2FA code store backed by Redis, shared by every service replica.
+/// Codes are kept for twice their lifetime, so that late attempts are reported
+/// as expired rather than unknown, and then evicted by Redis.
+#[derive(Clone)]
+pub struct RedisTwoFACodeStore {
+    /// The connection to the Redis server.
+    connection: ConnectionManager,
+    /// The lifetime and attempt limit applied to every code.
+    policy: TwoFACodePolicy,
+}
+
+impl RedisTwoFACodeStore {
+    /// Creates a new store on top of a Redis connection.
+    pub fn new(connection: ConnectionManager, policy: TwoFACodePolicy) -> Self {
+        Self { connection, policy }
+    }
+
+    // Both codes and failed-attempt counters outlive the code lifetime by as much again.
+    fn ttl_seconds(&self) -> u64 {
+        (self.policy.lifetime.as_secs() * 2).max(1)
+    }
+
+    // Deletes the code only if it still holds the value that was read.
+    async fn compare_and_delete(
+        &mut self,
+        email: &Email,
+        value: String,
+    ) -> Result<bool, TwoFACodeStoreError> {
+        let deleted: i64 = Script::new(COMPARE_AND_DELETE_SCRIPT)
+            .key(get_key(email))
+            .arg(value)
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok(deleted == 1)
+    }
+
+    // Returns the raw stored value along with the decoded record.
+    async fn get_record(
+        &self,
+        email: &Email,
+    ) -> Result<(String, TwoFARecord), TwoFACodeStoreError> {
+        let value: String = self
+            .connection
+            .clone()
+            .get::<_, Option<String>>(get_key(email))
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?
+            .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+        let record =
+            serde_json::from_str(&value).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok((value, record))
+    }
+
+    fn is_expired(&self, record: &TwoFARecord) -> bool {
+        Utc::now()
+            .signed_duration_since(record.created_at)
+            .to_std()
+            .unwrap_or_default()
+            >= self.policy.lifetime
+    }
+
+    // Settles the pending login attempt, with `matches` telling whether the code presented
+    // for it is the right one.
+    async fn consume(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        matches: impl FnOnce(&str) -> bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let (value, record) = self.get_record(email).await?;
+        if self.is_expired(&record) {
+            self.compare_and_delete(email, value).await?;
+            return Err(TwoFACodeStoreError::Expired);
+        }
+        // Guesses against another login attempt are not counted, so that they cannot
+        // be used to invalidate the pending code.
+        if record.login_attempt_id != login_attempt_id.as_ref() {
+            return Err(TwoFACodeStoreError::IncorrectCode);
+        }
+        if !matches(&record.code) {
+            let key = get_failed_attempts_key(login_attempt_id);
+            let failed_attempts: u32 = self
+                .connection
+                .incr(&key, 1)
+                .await
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+            let ttl_seconds = self.ttl_seconds() as i64;
+            self.connection
+                .expire::<_, ()>(&key, ttl_seconds)
+                .await
+                .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+            if failed_attempts >= self.policy.max_attempts {
+                self.compare_and_delete(email, value).await?;
+                return Err(TwoFACodeStoreError::TooManyAttempts);
+            }
+            return Err(TwoFACodeStoreError::IncorrectCode);
+        }
+
+        match self.compare_and_delete(email, value).await? {
+            true => Ok(()),
+            // Another request consumed or replaced the code first.
+            false => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+}
+
+// The value stored under each email key.
+#[derive(Serialize, Deserialize)]
+struct TwoFARecord {
+    login_attempt_id: String,
+    code: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TwoFACodeStore for RedisTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let value = serde_json::to_string(&TwoFARecord {
+            login_attempt_id: login_attempt_id.as_ref().to_owned(),
+            code: code.as_ref().to_owned(),
+            created_at: Utc::now(),
+        })
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        let ttl_seconds = self.ttl_seconds();
+
+        self.connection
+            .set_ex::<_, _, ()>(get_key(&email), value, ttl_seconds)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        self.connection
+            .del::<_, ()>(get_key(email))
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        let (_, record) = self.get_record(email).await?;
+        if self.is_expired(&record) {
+            return Err(TwoFACodeStoreError::Expired);
+        }
+
+        let login_attempt_id = LoginAttemptId::parse(record.login_attempt_id)
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        let code =
+            TwoFACode::parse(record.code).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok((login_attempt_id, code))
+    }
+
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, |pending| pending == code.as_ref())
+            .await
+    }
+
+    async fn consume_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        verified: bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        self.consume(email, login_attempt_id, |_| verified).await
+    }
+}
+
+fn get_key(email: &Email) -> String {
+    format!("{TWO_FA_CODE_KEY_PREFIX}{}", email.as_ref())
+}
+
+fn get_failed_attempts_key(login_attempt_id: &LoginAttemptId) -> String {
+    format!("{FAILED_ATTEMPTS_KEY_PREFIX}{}", login_attempt_id.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::get_redis_connection;
+
+    async fn store() -> RedisTwoFACodeStore {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".to_owned());
+        let connection = get_redis_connection(&url)
+            .await
+            .expect("Failed to connect to Redis");
+        RedisTwoFACodeStore::new(connection, TwoFACodePolicy::default())
+    }
+
+    fn email() -> Email {
+        Email::parse(&format!("{}@example.com", uuid::Uuid::new_v4())).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_get_code() {
+        let mut store = store().await;
+        let valid_email = email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        store
+            .add_code(valid_email.clone(), login_attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        let valid_code = store.get_code(&valid_email).await;
+        assert_eq!(valid_code, Ok((login_attempt_id, code)));
+
+        let invalid_code = store.get_code(&email()).await;
+        assert_eq!(
+            invalid_code,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_remove_code() {
+        let mut store = store().await;
+        let delete_email = email();
+        store
+            .add_code(
+                delete_email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(store.remove_code(&delete_email).await.is_ok());
+        assert!(store.get_code(&delete_email).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_code_has_ttl() {
+        let mut store = store().await;
+        let email = email();
+        store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await
+            .unwrap();
+        let ttl: i64 = store.connection.ttl(get_key(&email)).await.unwrap();
+        assert!(ttl > 600 && ttl <= 1200);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_consume_code_is_single_use() {
+        let mut store = store().await;
+        let email = email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store
+                .consume_code(&email, &LoginAttemptId::default(), &code)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_expired_code_is_rejected_and_removed() {
+        let mut store = store().await;
+        store.policy.lifetime = Duration::ZERO;
+        let email = email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+        store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::Expired)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::Expired)
+        );
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_code_is_invalidated_after_max_attempts() {
+        let mut store = store().await;
+        store.policy.max_attempts = 3;
+        let email = email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::parse("123456".to_owned()).unwrap();
+        let wrong_code = TwoFACode::parse("654321".to_owned()).unwrap();
+        store
+            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            assert_eq!(
+                store
+                    .consume_code(&email, &login_attempt_id, &wrong_code)
+                    .await,
+                Err(TwoFACodeStoreError::IncorrectCode)
+            );
+        }
+        assert_eq!(
+            store
+                .consume_code(&email, &login_attempt_id, &wrong_code)
+                .await,
+            Err(TwoFACodeStoreError::TooManyAttempts)
+        );
+        assert_eq!(
+            store.consume_code(&email, &login_attempt_id, &code).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_consume_attempt_shares_the_attempt_limit() {
+        let mut store = store().await;
+        store.policy.max_attempts = 2;
+        let email = email();
+        let login_attempt_id = LoginAttemptId::default();
+        store
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                TwoFACode::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store
+                .consume_attempt(&email, &login_attempt_id, false)
+                .await,
+            Err(TwoFACodeStoreError::IncorrectCode)
+        );
+        assert_eq!(
+            store.consume_attempt(&email, &login_attempt_id, true).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_attempt(&email, &login_attempt_id, true).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+}
@@ -0,0 +1,963 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool, sqlite::SqliteRow};
+
+use crate::domain::{
+    models::{
+        Email, EmailChangeToken, PasswordHash, PasswordResetToken, RecoveryCode, TotpSecret,
+        TwoFAMethod, User,
+    },
+    ports::{UserStore, UserStoreError},
+};
+
+/// A user store backed by a SQLite database.
+#[derive(Clone)]
+pub struct SqliteUserStore {
+    /// The connection pool to the SQLite database.
+    pool: SqlitePool,
+}
+
+impl SqliteUserStore {
+    /// Creates a new store on top of an already migrated connection pool.
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserStore for SqliteUserStore {
+    /// Adds a user to the store.
+    async fn add_user(&mut self, user: &User) -> Result<(), UserStoreError> {
+        sqlx::query(
+            "INSERT INTO users
+                 (email, password_hash, email_verified, two_fa_method, totp_secret, roles)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(user.email.as_ref())
+        .bind(user.password_hash.as_ref())
+        .bind(user.email_verified)
+        .bind(user.two_fa_method.name())
+        .bind(user.two_fa_method.totp_secret().map(AsRef::<str>::as_ref))
+        .bind(serde_json::to_string(&user.roles).map_err(|_| UserStoreError::UnexpectedError)?)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => {
+                UserStoreError::UserAlreadyExists
+            }
+            _ => UserStoreError::UnexpectedError,
+        })?;
+        Ok(())
+    }
+
+    /// Gets a user from the store.
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        sqlx::query(
+            "SELECT email, password_hash, email_verified, two_fa_method, totp_secret,
+                    pending_totp_secret, totp_last_used_step, failed_login_attempts, locked_until,
+                    roles
+             FROM users WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)
+        .and_then(|row| user_from_row(&row))
+    }
+
+    /// Replaces the password hash of a user.
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: PasswordHash,
+    ) -> Result<(), UserStoreError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        let result = sqlx::query("UPDATE users SET password_hash = $1 WHERE email = $2")
+            .bind(password_hash.as_ref())
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        // A change asked for under the previous password must not go through.
+        sqlx::query("DELETE FROM email_change_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        transaction
+            .commit()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Moves a user to another email address.
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        let result = sqlx::query("UPDATE users SET email = $1 WHERE email = $2")
+            .bind(new_email.as_ref())
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(e) if e.is_unique_violation() => {
+                    UserStoreError::UserAlreadyExists
+                }
+                _ => UserStoreError::UnexpectedError,
+            })?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        sqlx::query("UPDATE recovery_codes SET email = $1 WHERE email = $2")
+            .bind(new_email.as_ref())
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        // A reset link sent to the previous address must not take over the account.
+        sqlx::query("DELETE FROM password_reset_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        sqlx::query("DELETE FROM email_change_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        transaction
+            .commit()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Deletes a user and everything kept about them.
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        sqlx::query("DELETE FROM recovery_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        sqlx::query("DELETE FROM password_reset_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        sqlx::query("DELETE FROM email_change_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        let result = sqlx::query("DELETE FROM users WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+        transaction
+            .commit()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Marks the email of a user as verified.
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = sqlx::query("UPDATE users SET email_verified = TRUE WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a failed login for a user.
+    async fn record_failed_login(&mut self, email: &Email) -> Result<u32, UserStoreError> {
+        // Incrementing in SQL keeps concurrent failures from being lost.
+        let row = sqlx::query(
+            "UPDATE users SET failed_login_attempts = failed_login_attempts + 1
+             WHERE email = $1 RETURNING failed_login_attempts",
+        )
+        .bind(email.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)?;
+        Ok(row.get::<i32, _>("failed_login_attempts") as u32)
+    }
+
+    /// Locks a user out until the given time.
+    async fn lock_user(
+        &mut self,
+        email: &Email,
+        locked_until: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query(
+            "UPDATE users SET failed_login_attempts = 0, locked_until = $1 WHERE email = $2",
+        )
+        .bind(locked_until)
+        .bind(email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Clears the failed logins and lock of a user.
+    async fn reset_failed_logins(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = sqlx::query(
+            "UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Keeps a TOTP secret waiting for confirmation.
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query("UPDATE users SET pending_totp_secret = $1 WHERE email = $2")
+            .bind(secret.as_ref())
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Switches a user to another 2FA method.
+    async fn update_two_fa_method(
+        &mut self,
+        email: &Email,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query(
+            "UPDATE users SET two_fa_method = $1, totp_secret = $2, pending_totp_secret = NULL
+             WHERE email = $3",
+        )
+        .bind(method.name())
+        .bind(method.totp_secret().map(AsRef::<str>::as_ref))
+        .bind(email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the use of a TOTP code unless it, or a later one, was used already.
+    async fn record_totp_use(&mut self, email: &Email, step: u64) -> Result<bool, UserStoreError> {
+        // Comparing in SQL keeps two concurrent logins from both using the same code.
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_used_step = $1
+             WHERE email = $2 AND (totp_last_used_step IS NULL OR totp_last_used_step < $1)",
+        )
+        .bind(step as i64)
+        .bind(email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+        // Tell a used code apart from an unknown user.
+        self.get_user(email).await.map(|_| false)
+    }
+
+    /// Replaces the recovery codes of a user.
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: &[RecoveryCode],
+    ) -> Result<(), UserStoreError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        sqlx::query("SELECT 1 FROM users WHERE email = $1")
+            .bind(email.as_ref())
+            .fetch_optional(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?
+            .ok_or(UserStoreError::UserNotFound)?;
+        sqlx::query("DELETE FROM recovery_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *transaction)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        for code in codes {
+            sqlx::query("INSERT INTO recovery_codes (email, code_hash) VALUES ($1, $2)")
+                .bind(email.as_ref())
+                .bind(code.hash())
+                .execute(&mut *transaction)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+        }
+        transaction
+            .commit()
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Checks a recovery code of a user, leaving it in place.
+    async fn has_recovery_code(
+        &self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        let row = sqlx::query(
+            "SELECT EXISTS (SELECT 1 FROM recovery_codes WHERE email = $1 AND code_hash = $2)
+                    AS found
+             FROM users WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .bind(code.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)?;
+        Ok(row.get::<bool, _>("found"))
+    }
+
+    /// Removes a recovery code of a user, if it is one of theirs.
+    async fn consume_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        // Deleting the row settles concurrent uses of the same code: only one gets it.
+        let result = sqlx::query("DELETE FROM recovery_codes WHERE email = $1 AND code_hash = $2")
+            .bind(email.as_ref())
+            .bind(code.hash())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+        self.get_user(email).await.map(|_| false)
+    }
+
+    /// Counts the recovery codes a user has left.
+    async fn count_recovery_codes(&self, email: &Email) -> Result<usize, UserStoreError> {
+        let row = sqlx::query(
+            "SELECT (SELECT COUNT(*) FROM recovery_codes WHERE email = $1) AS count
+             FROM users WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)?;
+        Ok(row.get::<i64, _>("count") as usize)
+    }
+
+    /// Stores a password reset token of a user, replacing their pending one.
+    async fn set_password_reset_token(
+        &mut self,
+        email: &Email,
+        token: &PasswordResetToken,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        // Expired tokens can no longer be used, so they are dropped on the way.
+        sqlx::query("DELETE FROM password_reset_tokens WHERE expires_at <= $1")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query(
+            "INSERT INTO password_reset_tokens (email, token_hash, expires_at)
+             SELECT email, $2, $3 FROM users WHERE email = $1
+             ON CONFLICT (email)
+             DO UPDATE SET token_hash = excluded.token_hash, expires_at = excluded.expires_at",
+        )
+        .bind(email.as_ref())
+        .bind(token.hash())
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the user of a password reset token, unless it expired.
+    async fn get_password_reset_email(
+        &self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        let Some(row) = sqlx::query(
+            "SELECT email, expires_at FROM password_reset_tokens WHERE token_hash = $1",
+        )
+        .bind(token.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        else {
+            return Ok(None);
+        };
+        if row.get::<DateTime<Utc>, _>("expires_at") <= Utc::now() {
+            return Ok(None);
+        }
+        Email::parse(row.get("email"))
+            .map(Some)
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Removes a password reset token and returns its user, unless it expired.
+    async fn consume_password_reset_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        // Deleting the row settles concurrent uses of the same token: only one gets it.
+        let Some(row) = sqlx::query(
+            "DELETE FROM password_reset_tokens WHERE token_hash = $1 RETURNING email, expires_at",
+        )
+        .bind(token.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        else {
+            return Ok(None);
+        };
+        if row.get::<DateTime<Utc>, _>("expires_at") <= Utc::now() {
+            return Ok(None);
+        }
+        Email::parse(row.get("email"))
+            .map(Some)
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+
+    /// Stores an email change token of a user, replacing their pending one.
+    async fn set_email_change_token(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+        token: &EmailChangeToken,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        // Expired tokens can no longer be used, so they are dropped on the way.
+        sqlx::query("DELETE FROM email_change_tokens WHERE expires_at <= $1")
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query(
+            "INSERT INTO email_change_tokens (email, new_email, token_hash, expires_at)
+             SELECT email, $2, $3, $4 FROM users WHERE email = $1
+             ON CONFLICT (email)
+             DO UPDATE SET new_email = excluded.new_email, token_hash = excluded.token_hash,
+                 expires_at = excluded.expires_at",
+        )
+        .bind(email.as_ref())
+        .bind(new_email.as_ref())
+        .bind(token.hash())
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+        match result.rows_affected() {
+            0 => Err(UserStoreError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes an email change token and returns its user and new address, unless it
+    /// expired.
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailChangeToken,
+    ) -> Result<Option<(Email, Email)>, UserStoreError> {
+        // Deleting the row settles concurrent uses of the same token: only one gets it.
+        let Some(row) = sqlx::query(
+            "DELETE FROM email_change_tokens WHERE token_hash = $1
+             RETURNING email, new_email, expires_at",
+        )
+        .bind(token.hash())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        else {
+            return Ok(None);
+        };
+        if row.get::<DateTime<Utc>, _>("expires_at") <= Utc::now() {
+            return Ok(None);
+        }
+        let email = Email::parse(row.get("email")).map_err(|_| UserStoreError::UnexpectedError)?;
+        let new_email =
+            Email::parse(row.get("new_email")).map_err(|_| UserStoreError::UnexpectedError)?;
+        Ok(Some((email, new_email)))
+    }
+}
+
+// Map a `users` row back into the domain model, re-validating the stored values.
+fn user_from_row(row: &SqliteRow) -> Result<User, UserStoreError> {
+    let email = Email::parse(row.get("email")).map_err(|_| UserStoreError::UnexpectedError)?;
+    let password_hash = PasswordHash::parse(row.get("password_hash"))
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+    let totp_secret = |column| {
+        row.get::<Option<String>, _>(column)
+            .map(TotpSecret::parse)
+            .transpose()
+            .map_err(|_| UserStoreError::UnexpectedError)
+    };
+    let two_fa_method = TwoFAMethod::parse(row.get("two_fa_method"), totp_secret("totp_secret")?)
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+    let roles =
+        serde_json::from_str(row.get("roles")).map_err(|_| UserStoreError::UnexpectedError)?;
+    Ok(User {
+        email_verified: row.get("email_verified"),
+        failed_login_attempts: row.get::<i32, _>("failed_login_attempts") as u32,
+        locked_until: row.get("locked_until"),
+        roles,
+        pending_totp_secret: totp_secret("pending_totp_secret")?,
+        totp_last_used_step: row
+            .get::<Option<i64>, _>("totp_last_used_step")
+            .map(|step| step as u64),
+        ..User::new(email, password_hash, two_fa_method)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_sqlite_pool;
+    use chrono::SubsecRound;
+
+    // The returned directory must be kept alive for as long as the store is used.
+    async fn store() -> (SqliteUserStore, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let url = format!("sqlite://{}", dir.path().join("users.db").display());
+        let pool = get_sqlite_pool(&url)
+            .await
+            .expect("Failed to create SQLite pool");
+        (SqliteUserStore::new(pool), dir)
+    }
+
+    fn default_email(email: &'static str) -> Email {
+        Email::parse(email).expect("Failed to create email")
+    }
+
+    fn default_password_hash(hash: &'static str) -> PasswordHash {
+        PasswordHash::parse(format!("$argon2id$v=19$m=1024,t=1,p=1$c29tZXNhbHQ${hash}"))
+            .expect("Failed to create password hash")
+    }
+
+    #[tokio::test]
+    async fn test_add_user() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email, password_hash, TwoFAMethod::None);
+        let result = store.add_user(&user).await;
+        assert_eq!(result, Ok(()));
+        let result = store.add_user(&user).await;
+        assert_eq!(result, Err(UserStoreError::UserAlreadyExists));
+    }
+
+    #[tokio::test]
+    async fn test_get_user() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        let _ = store.add_user(&user).await;
+        let result = store.get_user(&email).await;
+        assert_eq!(result, Ok(user));
+        let fake_user = default_email("user2@example.com");
+        let result = store.get_user(&fake_user).await;
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_roles_round_trip() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User {
+            roles: vec!["admin".to_owned(), "support".to_owned()],
+            ..User::new(email.clone(), password_hash, TwoFAMethod::None)
+        };
+        store.add_user(&user).await.unwrap();
+        assert_eq!(store.get_user(&email).await.unwrap().roles, user.roles);
+    }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let new_password_hash = default_password_hash("YmJiYmJiYmJiYmJiYmJiYg");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        let _ = store.add_user(&user).await;
+        let result = store
+            .update_password(&email, new_password_hash.clone())
+            .await;
+        assert_eq!(result, Ok(()));
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.password_hash, new_password_hash);
+        let result = store
+            .update_password(&another_email, new_password_hash)
+            .await;
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_mark_email_verified() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        let _ = store.add_user(&user).await;
+        assert!(!store.get_user(&email).await.unwrap().email_verified);
+
+        assert_eq!(store.mark_email_verified(&email).await, Ok(()));
+        assert!(store.get_user(&email).await.unwrap().email_verified);
+        assert_eq!(
+            store.mark_email_verified(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_logins_and_lock() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        let _ = store.add_user(&user).await;
+
+        assert_eq!(store.record_failed_login(&email).await, Ok(1));
+        assert_eq!(store.record_failed_login(&email).await, Ok(2));
+
+        let locked_until = Utc::now().trunc_subsecs(0) + chrono::Duration::minutes(15);
+        assert_eq!(store.lock_user(&email, locked_until).await, Ok(()));
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.failed_login_attempts, 0);
+        assert_eq!(result.locked_until, Some(locked_until));
+
+        assert_eq!(store.record_failed_login(&email).await, Ok(1));
+        assert_eq!(store.reset_failed_logins(&email).await, Ok(()));
+        assert_eq!(store.get_user(&email).await, Ok(user));
+
+        assert_eq!(
+            store.record_failed_login(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.lock_user(&another_email, locked_until).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.reset_failed_logins(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_totp_round_trip() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        store.add_user(&user).await.unwrap();
+
+        let secret = TotpSecret::default();
+        store
+            .set_pending_totp_secret(&email, secret.clone())
+            .await
+            .unwrap();
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.two_fa_method, TwoFAMethod::Email);
+        assert_eq!(result.pending_totp_secret, Some(secret.clone()));
+
+        let method = TwoFAMethod::Totp(secret);
+        store
+            .update_two_fa_method(&email, method.clone())
+            .await
+            .unwrap();
+        assert_eq!(store.record_totp_use(&email, 100).await, Ok(true));
+        assert_eq!(store.record_totp_use(&email, 100).await, Ok(false));
+        let result = store.get_user(&email).await.unwrap();
+        assert_eq!(result.two_fa_method, method);
+        assert_eq!(result.pending_totp_secret, None);
+        assert_eq!(result.totp_last_used_step, Some(100));
+
+        assert_eq!(
+            store
+                .record_totp_use(&default_email("user2@example.com"), 100)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recovery_codes_are_consumed_once() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let another_email = default_email("user2@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        store.add_user(&user).await.unwrap();
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(0));
+
+        let codes = [RecoveryCode::default(), RecoveryCode::default()];
+        assert_eq!(store.set_recovery_codes(&email, &codes).await, Ok(()));
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(2));
+        assert_eq!(store.has_recovery_code(&email, &codes[0]).await, Ok(true));
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[0]).await,
+            Ok(true)
+        );
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[0]).await,
+            Ok(false)
+        );
+        assert_eq!(store.has_recovery_code(&email, &codes[0]).await, Ok(false));
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(1));
+
+        // New codes replace the previous ones.
+        let new_codes = [RecoveryCode::default()];
+        assert_eq!(store.set_recovery_codes(&email, &new_codes).await, Ok(()));
+        assert_eq!(
+            store.consume_recovery_code(&email, &codes[1]).await,
+            Ok(false)
+        );
+        assert_eq!(
+            store.consume_recovery_code(&email, &new_codes[0]).await,
+            Ok(true)
+        );
+
+        assert_eq!(
+            store.set_recovery_codes(&another_email, &codes).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.count_recovery_codes(&another_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_tokens_are_consumed_once() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::None);
+        store.add_user(&user).await.unwrap();
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(60);
+
+        let token = PasswordResetToken::default();
+        assert_eq!(
+            store
+                .set_password_reset_token(&email, &token, expires_at)
+                .await,
+            Ok(())
+        );
+        // Looking the token up leaves it in place.
+        assert_eq!(
+            store.get_password_reset_email(&token).await,
+            Ok(Some(email.clone()))
+        );
+        assert_eq!(
+            store.consume_password_reset_token(&token).await,
+            Ok(Some(email.clone()))
+        );
+        assert_eq!(store.consume_password_reset_token(&token).await, Ok(None));
+        assert_eq!(store.get_password_reset_email(&token).await, Ok(None));
+
+        // A new token replaces the pending one.
+        let first = PasswordResetToken::default();
+        let second = PasswordResetToken::default();
+        let _ = store
+            .set_password_reset_token(&email, &first, expires_at)
+            .await;
+        let _ = store
+            .set_password_reset_token(&email, &second, expires_at)
+            .await;
+        assert_eq!(store.consume_password_reset_token(&first).await, Ok(None));
+        assert_eq!(
+            store.consume_password_reset_token(&second).await,
+            Ok(Some(email.clone()))
+        );
+
+        let expired = PasswordResetToken::default();
+        let _ = store
+            .set_password_reset_token(&email, &expired, Utc::now())
+            .await;
+        assert_eq!(store.get_password_reset_email(&expired).await, Ok(None));
+        assert_eq!(store.consume_password_reset_token(&expired).await, Ok(None));
+
+        assert_eq!(
+            store
+                .set_password_reset_token(&default_email("user2@example.com"), &token, expires_at)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_change_tokens_are_consumed_once() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let new_email = default_email("new@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash.clone(), TwoFAMethod::None);
+        store.add_user(&user).await.unwrap();
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(60);
+
+        let token = EmailChangeToken::default();
+        assert_eq!(
+            store
+                .set_email_change_token(&email, &new_email, &token, expires_at)
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            store.consume_email_change_token(&token).await,
+            Ok(Some((email.clone(), new_email.clone())))
+        );
+        assert_eq!(store.consume_email_change_token(&token).await, Ok(None));
+
+        // A new token replaces the pending one, and changing the password drops it.
+        let first = EmailChangeToken::default();
+        let second = EmailChangeToken::default();
+        let _ = store
+            .set_email_change_token(&email, &new_email, &first, expires_at)
+            .await;
+        let _ = store
+            .set_email_change_token(&email, &new_email, &second, expires_at)
+            .await;
+        assert_eq!(store.consume_email_change_token(&first).await, Ok(None));
+        store.update_password(&email, password_hash).await.unwrap();
+        assert_eq!(store.consume_email_change_token(&second).await, Ok(None));
+
+        let expired = EmailChangeToken::default();
+        let _ = store
+            .set_email_change_token(&email, &new_email, &expired, Utc::now())
+            .await;
+        assert_eq!(store.consume_email_change_token(&expired).await, Ok(None));
+
+        // Deleting the user drops the pending change.
+        let _ = store
+            .set_email_change_token(&email, &new_email, &token, expires_at)
+            .await;
+        store.delete_user(&email).await.unwrap();
+        assert_eq!(store.consume_email_change_token(&token).await, Ok(None));
+        assert_eq!(
+            store
+                .set_email_change_token(&email, &new_email, &token, expires_at)
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_email() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let new_email = default_email("new@example.com");
+        let taken_email = default_email("taken@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        for email in [&email, &taken_email] {
+            let user = User::new(email.clone(), password_hash.clone(), TwoFAMethod::Email);
+            store.add_user(&user).await.unwrap();
+        }
+        let codes = [RecoveryCode::default()];
+        store.set_recovery_codes(&email, &codes).await.unwrap();
+
+        assert_eq!(
+            store.update_email(&email, &taken_email).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+        assert_eq!(store.update_email(&email, &new_email).await, Ok(()));
+        assert_eq!(
+            store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(store.get_user(&new_email).await.unwrap().email, new_email);
+        assert_eq!(store.count_recovery_codes(&new_email).await, Ok(1));
+        assert_eq!(
+            store.update_email(&email, &new_email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let (mut store, _dir) = store().await;
+        let email = default_email("user@example.com");
+        let password_hash = default_password_hash("YWFhYWFhYWFhYWFhYWFhYQ");
+        let user = User::new(email.clone(), password_hash, TwoFAMethod::Email);
+        store.add_user(&user).await.unwrap();
+        let codes = [RecoveryCode::default()];
+        store.set_recovery_codes(&email, &codes).await.unwrap();
+        let token = PasswordResetToken::default();
+        let expires_at = Utc::now() + chrono::TimeDelta::seconds(600);
+        store
+            .set_password_reset_token(&email, &token, expires_at)
+            .await
+            .unwrap();
+
+        assert_eq!(store.delete_user(&email).await, Ok(()));
+        assert_eq!(
+            store.get_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        assert_eq!(
+            store.delete_user(&email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+
+        // Someone signing up again with the address inherits nothing.
+        store.add_user(&user).await.unwrap();
+        assert_eq!(store.count_recovery_codes(&email).await, Ok(0));
+        assert_eq!(store.consume_password_reset_token(&token).await, Ok(None));
+    }
+}
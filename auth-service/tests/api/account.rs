@@ -0,0 +1,211 @@
+use auth_service::{
+    api::{
+        dtos::AccountExportResponse,
+        utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+    domain::{
+        models::{CosePublicKey, Email, LoginAttemptId, PasskeyCredential, TwoFACode},
+        ports::{PasskeyStore, TwoFACodeStore, UserStore, UserStoreError},
+    },
+};
+use ciborium::Value;
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+// Signs up a user who verified their email and logs them in, returning their `jwt` and
+// refresh token.
+async fn logged_in_user(app: &TestApp, email: &str) -> (String, String) {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let cookie = |name| {
+        response
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .expect("No cookie found")
+            .value()
+            .to_owned()
+    };
+    (cookie(JWT_COOKIE_NAME), cookie(REFRESH_TOKEN_COOKIE_NAME))
+}
+
+// Registers a passkey for the user straight in the store.
+async fn add_passkey(app: &TestApp, email: &Email) {
+    // An Ed25519 COSE_Key: OKP (1: 1), EdDSA (3: -8), Ed25519 (-1: 6) and x.
+    let key = Value::Map(vec![
+        (Value::from(1), Value::from(1)),
+        (Value::from(3), Value::from(-8)),
+        (Value::from(-1), Value::from(6)),
+        (Value::from(-2), Value::Bytes(vec![7; 32])),
+    ]);
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&key, &mut bytes).unwrap();
+    app.passkey_store
+        .write()
+        .await
+        .add_credential(PasskeyCredential {
+            credential_id: "passkey".to_owned(),
+            email: email.clone(),
+            public_key: CosePublicKey::parse(bytes).unwrap(),
+            sign_count: 0,
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn should_delete_account_and_everything_kept_about_it() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let (auth_token, refresh_token) = logged_in_user(&app, &email).await;
+    let parsed_email = Email::parse(&email).unwrap();
+    add_passkey(&app, &parsed_email).await;
+    app.two_fa_code_store
+        .write()
+        .await
+        .add_code(
+            parsed_email.clone(),
+            LoginAttemptId::default(),
+            TwoFACode::default(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(auth_cookie.value().is_empty());
+
+    assert_eq!(
+        app.user_store.read().await.get_user(&parsed_email).await,
+        Err(UserStoreError::UserNotFound)
+    );
+    assert_eq!(
+        app.passkey_store
+            .read()
+            .await
+            .get_credentials(&parsed_email)
+            .await,
+        Ok(vec![])
+    );
+    assert!(
+        app.two_fa_code_store
+            .read()
+            .await
+            .get_code(&parsed_email)
+            .await
+            .is_err()
+    );
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_password() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": "wrong-password" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    assert!(
+        app.user_store
+            .read()
+            .await
+            .get_user(&Email::parse(&email).unwrap())
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let response = app.get_account_export().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_export_everything_kept_about_the_user() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    add_passkey(&app, &Email::parse(&email).unwrap()).await;
+
+    let response = app.get_account_export().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers().get("content-disposition").unwrap(),
+        "attachment; filename=\"account-export.json\""
+    );
+    let archive = response
+        .json::<AccountExportResponse>()
+        .await
+        .expect("Could not deserialize response body to AccountExportResponse");
+    assert_eq!(archive.email, email);
+    assert!(archive.email_verified);
+    assert_eq!(archive.two_fa_method, "none");
+    assert_eq!(archive.recovery_codes_left, 0);
+    assert_eq!(archive.passkeys.len(), 1);
+    assert_eq!(archive.passkeys[0].credential_id, "passkey");
+}
+
+#[tokio::test]
+async fn should_allow_cross_origin_account_deletion() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .request(
+            reqwest::Method::OPTIONS,
+            format!("{}/account", &app.address),
+        )
+        .header("Origin", "http://localhost:8000")
+        .header("Access-Control-Request-Method", "DELETE")
+        .header("Access-Control-Request-Headers", "content-type")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let allowed = |header: &str| {
+        response
+            .headers()
+            .get(header)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    assert!(allowed("access-control-allow-methods").contains("delete"));
+    assert!(allowed("access-control-allow-headers").contains("content-type"));
+}
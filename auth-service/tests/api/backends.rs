@@ -0,0 +1,671 @@
+//! Store backends the API suite can run against, selected with `TEST_DATABASE`:
+//!
+//! - `memory` (default): the in-memory stores.
+//! - `sqlite`: users in a temp-file SQLite database.
+//! - `postgres`: users, 2FA codes, refresh tokens, OAuth clients and passkeys in a throwaway database
+//!   created on the server at `TEST_DATABASE_URL` and dropped when the test finishes.
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, Executor, PgConnection};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use auth_service::{
+    domain::{
+        models::{
+            AuthorizationCode, AuthorizationCodeRecord, Email, EmailChangeToken, LoginAttemptId,
+            OAuthClient, PasskeyChallenge, PasskeyChallengeRecord, PasskeyCredential, PasswordHash,
+            PasswordResetToken, RecoveryCode, RefreshToken, RefreshTokenRecord, TotpSecret,
+            TwoFACode, TwoFACodePolicy, TwoFAMethod, User,
+        },
+        ports::{
+            OAuthClientStore, OAuthClientStoreError, PasskeyStore, PasskeyStoreError,
+            RefreshTokenStore, RefreshTokenStoreError, TwoFACodeStore, TwoFACodeStoreError,
+            UserStore, UserStoreError,
+        },
+    },
+    get_postgres_pool, get_sqlite_pool,
+    services::{
+        hashmap_oauth_client_store::HashmapOAuthClientStore,
+        hashmap_passkey_store::HashmapPasskeyStore,
+        hashmap_refresh_token_store::HashmapRefreshTokenStore,
+        hashmap_two_fa_code_store::HashmapTwoFACodeStore, hashmap_user_store::HashmapUserStore,
+        postgres_oauth_client_store::PostgresOAuthClientStore,
+        postgres_passkey_store::PostgresPasskeyStore,
+        postgres_refresh_token_store::PostgresRefreshTokenStore,
+        postgres_two_fa_code_store::PostgresTwoFACodeStore, postgres_user_store::PostgresUserStore,
+        sqlite_user_store::SqliteUserStore,
+    },
+};
+
+const TEST_DATABASE_ENV_VAR: &str = "TEST_DATABASE";
+const TEST_DATABASE_URL_ENV_VAR: &str = "TEST_DATABASE_URL";
+const DEFAULT_TEST_DATABASE_URL: &str = "postgres://postgres@localhost:5432";
+
+/// A user store whose backend is chosen at runtime.
+#[derive(Clone)]
+pub enum TestUserStore {
+    Hashmap(HashmapUserStore),
+    Sqlite(SqliteUserStore),
+    Postgres(PostgresUserStore),
+}
+
+impl UserStore for TestUserStore {
+    async fn add_user(&mut self, user: &User) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_user(user).await,
+            Self::Sqlite(store) => store.add_user(user).await,
+            Self::Postgres(store) => store.add_user(user).await,
+        }
+    }
+
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_user(email).await,
+            Self::Sqlite(store) => store.get_user(email).await,
+            Self::Postgres(store) => store.get_user(email).await,
+        }
+    }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: PasswordHash,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.update_password(email, password_hash).await,
+            Self::Sqlite(store) => store.update_password(email, password_hash).await,
+            Self::Postgres(store) => store.update_password(email, password_hash).await,
+        }
+    }
+
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.update_email(email, new_email).await,
+            Self::Sqlite(store) => store.update_email(email, new_email).await,
+            Self::Postgres(store) => store.update_email(email, new_email).await,
+        }
+    }
+
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.delete_user(email).await,
+            Self::Sqlite(store) => store.delete_user(email).await,
+            Self::Postgres(store) => store.delete_user(email).await,
+        }
+    }
+
+    async fn mark_email_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.mark_email_verified(email).await,
+            Self::Sqlite(store) => store.mark_email_verified(email).await,
+            Self::Postgres(store) => store.mark_email_verified(email).await,
+        }
+    }
+
+    async fn record_failed_login(&mut self, email: &Email) -> Result<u32, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.record_failed_login(email).await,
+            Self::Sqlite(store) => store.record_failed_login(email).await,
+            Self::Postgres(store) => store.record_failed_login(email).await,
+        }
+    }
+
+    async fn lock_user(
+        &mut self,
+        email: &Email,
+        locked_until: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.lock_user(email, locked_until).await,
+            Self::Sqlite(store) => store.lock_user(email, locked_until).await,
+            Self::Postgres(store) => store.lock_user(email, locked_until).await,
+        }
+    }
+
+    async fn reset_failed_logins(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.reset_failed_logins(email).await,
+            Self::Sqlite(store) => store.reset_failed_logins(email).await,
+            Self::Postgres(store) => store.reset_failed_logins(email).await,
+        }
+    }
+
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.set_pending_totp_secret(email, secret).await,
+            Self::Sqlite(store) => store.set_pending_totp_secret(email, secret).await,
+            Self::Postgres(store) => store.set_pending_totp_secret(email, secret).await,
+        }
+    }
+
+    async fn update_two_fa_method(
+        &mut self,
+        email: &Email,
+        method: TwoFAMethod,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.update_two_fa_method(email, method).await,
+            Self::Sqlite(store) => store.update_two_fa_method(email, method).await,
+            Self::Postgres(store) => store.update_two_fa_method(email, method).await,
+        }
+    }
+
+    async fn record_totp_use(&mut self, email: &Email, step: u64) -> Result<bool, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.record_totp_use(email, step).await,
+            Self::Sqlite(store) => store.record_totp_use(email, step).await,
+            Self::Postgres(store) => store.record_totp_use(email, step).await,
+        }
+    }
+
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: &[RecoveryCode],
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.set_recovery_codes(email, codes).await,
+            Self::Sqlite(store) => store.set_recovery_codes(email, codes).await,
+            Self::Postgres(store) => store.set_recovery_codes(email, codes).await,
+        }
+    }
+
+    async fn has_recovery_code(
+        &self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.has_recovery_code(email, code).await,
+            Self::Sqlite(store) => store.has_recovery_code(email, code).await,
+            Self::Postgres(store) => store.has_recovery_code(email, code).await,
+        }
+    }
+
+    async fn consume_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<bool, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_recovery_code(email, code).await,
+            Self::Sqlite(store) => store.consume_recovery_code(email, code).await,
+            Self::Postgres(store) => store.consume_recovery_code(email, code).await,
+        }
+    }
+
+    async fn count_recovery_codes(&self, email: &Email) -> Result<usize, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.count_recovery_codes(email).await,
+            Self::Sqlite(store) => store.count_recovery_codes(email).await,
+            Self::Postgres(store) => store.count_recovery_codes(email).await,
+        }
+    }
+
+    async fn set_password_reset_token(
+        &mut self,
+        email: &Email,
+        token: &PasswordResetToken,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => {
+                store
+                    .set_password_reset_token(email, token, expires_at)
+                    .await
+            }
+            Self::Sqlite(store) => {
+                store
+                    .set_password_reset_token(email, token, expires_at)
+                    .await
+            }
+            Self::Postgres(store) => {
+                store
+                    .set_password_reset_token(email, token, expires_at)
+                    .await
+            }
+        }
+    }
+
+    async fn get_password_reset_email(
+        &self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_password_reset_email(token).await,
+            Self::Sqlite(store) => store.get_password_reset_email(token).await,
+            Self::Postgres(store) => store.get_password_reset_email(token).await,
+        }
+    }
+
+    async fn consume_password_reset_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Option<Email>, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_password_reset_token(token).await,
+            Self::Sqlite(store) => store.consume_password_reset_token(token).await,
+            Self::Postgres(store) => store.consume_password_reset_token(token).await,
+        }
+    }
+
+    async fn set_email_change_token(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+        token: &EmailChangeToken,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), UserStoreError> {
+        match self {
+            Self::Hashmap(store) => {
+                store
+                    .set_email_change_token(email, new_email, token, expires_at)
+                    .await
+            }
+            Self::Sqlite(store) => {
+                store
+                    .set_email_change_token(email, new_email, token, expires_at)
+                    .await
+            }
+            Self::Postgres(store) => {
+                store
+                    .set_email_change_token(email, new_email, token, expires_at)
+                    .await
+            }
+        }
+    }
+
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailChangeToken,
+    ) -> Result<Option<(Email, Email)>, UserStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_email_change_token(token).await,
+            Self::Sqlite(store) => store.consume_email_change_token(token).await,
+            Self::Postgres(store) => store.consume_email_change_token(token).await,
+        }
+    }
+}
+
+/// A 2FA code store whose backend is chosen at runtime.
+#[derive(Clone)]
+pub enum TestTwoFACodeStore {
+    Hashmap(HashmapTwoFACodeStore),
+    Postgres(PostgresTwoFACodeStore),
+}
+
+impl TwoFACodeStore for TestTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_code(email, login_attempt_id, code).await,
+            Self::Postgres(store) => store.add_code(email, login_attempt_id, code).await,
+        }
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Hashmap(store) => store.remove_code(email).await,
+            Self::Postgres(store) => store.remove_code(email).await,
+        }
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_code(email).await,
+            Self::Postgres(store) => store.get_code(email).await,
+        }
+    }
+
+    async fn consume_code(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        code: &TwoFACode,
+    ) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_code(email, login_attempt_id, code).await,
+            Self::Postgres(store) => store.consume_code(email, login_attempt_id, code).await,
+        }
+    }
+
+    async fn consume_attempt(
+        &mut self,
+        email: &Email,
+        login_attempt_id: &LoginAttemptId,
+        verified: bool,
+    ) -> Result<(), TwoFACodeStoreError> {
+        match self {
+            Self::Hashmap(store) => {
+                store
+                    .consume_attempt(email, login_attempt_id, verified)
+                    .await
+            }
+            Self::Postgres(store) => {
+                store
+                    .consume_attempt(email, login_attempt_id, verified)
+                    .await
+            }
+        }
+    }
+}
+
+/// A refresh token store whose backend is chosen at runtime.
+#[derive(Clone)]
+pub enum TestRefreshTokenStore {
+    Hashmap(HashmapRefreshTokenStore),
+    Postgres(PostgresRefreshTokenStore),
+}
+
+impl RefreshTokenStore for TestRefreshTokenStore {
+    async fn add_token(
+        &mut self,
+        token: &RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_token(token, record).await,
+            Self::Postgres(store) => store.add_token(token, record).await,
+        }
+    }
+
+    async fn get_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_token(token).await,
+            Self::Postgres(store) => store.get_token(token).await,
+        }
+    }
+
+    async fn use_token(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        match self {
+            Self::Hashmap(store) => store.use_token(token).await,
+            Self::Postgres(store) => store.use_token(token).await,
+        }
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        match self {
+            Self::Hashmap(store) => store.revoke_family(family_id).await,
+            Self::Postgres(store) => store.revoke_family(family_id).await,
+        }
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        match self {
+            Self::Hashmap(store) => store.revoke_user(email).await,
+            Self::Postgres(store) => store.revoke_user(email).await,
+        }
+    }
+}
+
+/// An OAuth client store whose backend is chosen at runtime.
+#[derive(Clone)]
+pub enum TestOAuthClientStore {
+    Hashmap(HashmapOAuthClientStore),
+    Postgres(PostgresOAuthClientStore),
+}
+
+impl OAuthClientStore for TestOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_client(client).await,
+            Self::Postgres(store) => store.add_client(client).await,
+        }
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_client(client_id).await,
+            Self::Postgres(store) => store.get_client(client_id).await,
+        }
+    }
+
+    async fn add_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+        record: AuthorizationCodeRecord,
+    ) -> Result<(), OAuthClientStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_authorization_code(code, record).await,
+            Self::Postgres(store) => store.add_authorization_code(code, record).await,
+        }
+    }
+
+    async fn consume_authorization_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationCodeRecord, OAuthClientStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_authorization_code(code).await,
+            Self::Postgres(store) => store.consume_authorization_code(code).await,
+        }
+    }
+}
+
+/// A passkey store whose backend is chosen at runtime.
+#[derive(Clone)]
+pub enum TestPasskeyStore {
+    Hashmap(HashmapPasskeyStore),
+    Postgres(PostgresPasskeyStore),
+}
+
+impl PasskeyStore for TestPasskeyStore {
+    async fn add_credential(
+        &mut self,
+        credential: PasskeyCredential,
+    ) -> Result<(), PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_credential(credential).await,
+            Self::Postgres(store) => store.add_credential(credential).await,
+        }
+    }
+
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.update_email(email, new_email).await,
+            Self::Postgres(store) => store.update_email(email, new_email).await,
+        }
+    }
+
+    async fn delete_credentials(&mut self, email: &Email) -> Result<(), PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.delete_credentials(email).await,
+            Self::Postgres(store) => store.delete_credentials(email).await,
+        }
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<PasskeyCredential>, PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_credentials(email).await,
+            Self::Postgres(store) => store.get_credentials(email).await,
+        }
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<PasskeyCredential, PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.get_credential(credential_id).await,
+            Self::Postgres(store) => store.get_credential(credential_id).await,
+        }
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<bool, PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.update_sign_count(credential_id, sign_count).await,
+            Self::Postgres(store) => store.update_sign_count(credential_id, sign_count).await,
+        }
+    }
+
+    async fn add_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+        record: PasskeyChallengeRecord,
+    ) -> Result<(), PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.add_challenge(challenge, record).await,
+            Self::Postgres(store) => store.add_challenge(challenge, record).await,
+        }
+    }
+
+    async fn consume_challenge(
+        &mut self,
+        challenge: &PasskeyChallenge,
+    ) -> Result<PasskeyChallengeRecord, PasskeyStoreError> {
+        match self {
+            Self::Hashmap(store) => store.consume_challenge(challenge).await,
+            Self::Postgres(store) => store.consume_challenge(challenge).await,
+        }
+    }
+}
+
+/// The stores a test app runs against.
+pub struct TestStores {
+    pub user_store: TestUserStore,
+    pub two_fa_code_store: TestTwoFACodeStore,
+    pub refresh_token_store: TestRefreshTokenStore,
+    pub oauth_client_store: TestOAuthClientStore,
+    pub passkey_store: TestPasskeyStore,
+}
+
+/// The database backing a test app, cleaned up when dropped.
+pub enum TestDatabase {
+    Memory,
+    Sqlite(TempDir),
+    Postgres { server_url: String, name: String },
+}
+
+impl TestDatabase {
+    /// Creates the stores for the backend selected by `TEST_DATABASE`.
+    pub async fn setup(two_fa_code_policy: TwoFACodePolicy) -> (TestStores, Self) {
+        match std::env::var(TEST_DATABASE_ENV_VAR).as_deref() {
+            Ok("sqlite") => {
+                let dir = tempfile::tempdir().expect("Failed to create temp dir");
+                let url = format!("sqlite://{}", dir.path().join("auth.db").display());
+                let pool = get_sqlite_pool(&url)
+                    .await
+                    .expect("Failed to create SQLite pool");
+                let stores = TestStores {
+                    user_store: TestUserStore::Sqlite(SqliteUserStore::new(pool)),
+                    two_fa_code_store: TestTwoFACodeStore::Hashmap(HashmapTwoFACodeStore::new(
+                        two_fa_code_policy,
+                    )),
+                    refresh_token_store: TestRefreshTokenStore::Hashmap(
+                        HashmapRefreshTokenStore::default(),
+                    ),
+                    oauth_client_store: TestOAuthClientStore::Hashmap(
+                        HashmapOAuthClientStore::default(),
+                    ),
+                    passkey_store: TestPasskeyStore::Hashmap(HashmapPasskeyStore::default()),
+                };
+                (stores, Self::Sqlite(dir))
+            }
+            Ok("postgres") => {
+                let server_url = std::env::var(TEST_DATABASE_URL_ENV_VAR)
+                    .unwrap_or_else(|_| DEFAULT_TEST_DATABASE_URL.to_owned());
+                let name = format!("auth_test_{}", Uuid::new_v4().simple());
+
+                let mut connection = PgConnection::connect(&server_url)
+                    .await
+                    .expect("Failed to connect to Postgres");
+                connection
+                    .execute(format!(r#"CREATE DATABASE "{name}";"#).as_str())
+                    .await
+                    .expect("Failed to create test database");
+
+                let pool = get_postgres_pool(&format!("{server_url}/{name}"), 5)
+                    .await
+                    .expect("Failed to create Postgres pool");
+                let stores = TestStores {
+                    user_store: TestUserStore::Postgres(PostgresUserStore::new(pool.clone())),
+                    two_fa_code_store: TestTwoFACodeStore::Postgres(PostgresTwoFACodeStore::new(
+                        pool.clone(),
+                        two_fa_code_policy,
+                    )),
+                    refresh_token_store: TestRefreshTokenStore::Postgres(
+                        PostgresRefreshTokenStore::new(pool.clone()),
+                    ),
+                    oauth_client_store: TestOAuthClientStore::Postgres(
+                        PostgresOAuthClientStore::new(pool.clone()),
+                    ),
+                    passkey_store: TestPasskeyStore::Postgres(PostgresPasskeyStore::new(pool)),
+                };
+                (stores, Self::Postgres { server_url, name })
+            }
+            _ => {
+                let stores = TestStores {
+                    user_store: TestUserStore::Hashmap(HashmapUserStore::default()),
+                    two_fa_code_store: TestTwoFACodeStore::Hashmap(HashmapTwoFACodeStore::new(
+                        two_fa_code_policy,
+                    )),
+                    refresh_token_store: TestRefreshTokenStore::Hashmap(
+                        HashmapRefreshTokenStore::default(),
+                    ),
+                    oauth_client_store: TestOAuthClientStore::Hashmap(
+                        HashmapOAuthClientStore::default(),
+                    ),
+                    passkey_store: TestPasskeyStore::Hashmap(HashmapPasskeyStore::default()),
+                };
+                (stores, Self::Memory)
+            }
+        }
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let Self::Postgres { server_url, name } = self else {
+            return;
+        };
+        let (server_url, name) = (server_url.clone(), name.clone());
+
+        // Drop runs inside the test's runtime, so do the async cleanup on a fresh one.
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+            runtime.block_on(async {
+                let mut connection = PgConnection::connect(&server_url)
+                    .await
+                    .expect("Failed to connect to Postgres");
+                connection
+                    .execute(format!(r#"DROP DATABASE IF EXISTS "{name}" WITH (FORCE);"#).as_str())
+                    .await
+                    .expect("Failed to drop test database");
+            });
+        })
+        .join()
+        .expect("Failed to clean up test database");
+    }
+}
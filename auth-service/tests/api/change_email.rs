@@ -0,0 +1,244 @@
+use auth_service::{
+    api::utils::{auth::generate_email_verification_token, constants::JWT_COOKIE_NAME},
+    domain::{
+        models::{Email, EmailChangeToken, PasswordResetToken},
+        ports::UserStore,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+// Signs up a user who verified their email and logs them in, returning their `jwt`.
+async fn logged_in_user(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned()
+}
+
+// Stores an email change token for the user, as `/change-email` does before emailing it to
+// the new address.
+async fn email_change_token(app: &TestApp, email: &str, new_email: &str) -> String {
+    let token = EmailChangeToken::default();
+    app.user_store
+        .write()
+        .await
+        .set_email_change_token(
+            &Email::parse(email).unwrap(),
+            &Email::parse(new_email).unwrap(),
+            &token,
+            Utc::now() + TimeDelta::seconds(600),
+        )
+        .await
+        .unwrap();
+    token.as_ref().to_owned()
+}
+
+async fn login_status(app: &TestApp, email: &str) -> u16 {
+    app.post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_change_email_once_confirmed() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let auth_token = logged_in_user(&app, &email).await;
+    let new_email = get_random_email();
+
+    let response = app
+        .post_change_email(&serde_json::json!({ "newEmail": new_email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    // Nothing changes until the new address is confirmed.
+    assert_eq!(login_status(&app, &email).await, 200);
+    assert_eq!(login_status(&app, &new_email).await, 401);
+
+    let token = email_change_token(&app, &email, &new_email).await;
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(login_status(&app, &email).await, 401);
+    assert_eq!(login_status(&app, &new_email).await, 200);
+
+    // Sessions issued to the previous address are revoked.
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The link works only once.
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_password() {
+    let app = TestApp::new().await;
+    let _ = logged_in_user(&app, &get_random_email()).await;
+
+    let response = app
+        .post_change_email(&serde_json::json!({
+            "newEmail": get_random_email(),
+            "password": "wrong-password",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_409_if_new_email_is_taken() {
+    let app = TestApp::new().await;
+    let taken_email = get_random_email();
+    let _ = logged_in_user(&app, &taken_email).await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+
+    let response = app
+        .post_change_email(&serde_json::json!({ "newEmail": taken_email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    // The address may be taken between the request and the confirmation.
+    let response = app
+        .get_confirm_email_change(&email_change_token(&app, &email, &taken_email).await)
+        .await;
+    assert_eq!(response.status().as_u16(), 409);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_new_email() {
+    let app = TestApp::new().await;
+    let _ = logged_in_user(&app, &get_random_email()).await;
+
+    let response = app
+        .post_change_email(
+            &serde_json::json!({ "newEmail": "invalid_email", "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_confirmation_token() {
+    let app = TestApp::new().await;
+
+    let response = app.get_confirm_email_change("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // An email verification token is not an email change token.
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    let token = generate_email_verification_token(&Email::parse(&email).unwrap()).unwrap();
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_link_was_requested_before_a_password_reset() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    let token = email_change_token(&app, &email, &get_random_email()).await;
+
+    let reset_token = PasswordResetToken::default();
+    app.user_store
+        .write()
+        .await
+        .set_password_reset_token(
+            &Email::parse(&email).unwrap(),
+            &reset_token,
+            Utc::now() + TimeDelta::seconds(600),
+        )
+        .await
+        .unwrap();
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": reset_token.as_ref(),
+            "newPassword": "new-password456",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_link_was_requested_before_a_password_change() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    let token = email_change_token(&app, &email, &get_random_email()).await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": PASSWORD,
+            "newPassword": "new-password456",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_account_was_deleted_and_created_again() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    let token = email_change_token(&app, &email, &get_random_email()).await;
+
+    let response = app
+        .delete_account(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let _ = logged_in_user(&app, &email).await;
+
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_the_link_is_replayed_after_moving_back() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let _ = logged_in_user(&app, &email).await;
+    let new_email = get_random_email();
+
+    let token = email_change_token(&app, &email, &new_email).await;
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app
+        .get_confirm_email_change(&email_change_token(&app, &new_email, &email).await)
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_confirm_email_change(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(login_status(&app, &email).await, 200);
+}
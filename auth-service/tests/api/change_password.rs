@@ -0,0 +1,117 @@
+use auth_service::api::{dtos::ErrorResponse, utils::constants::JWT_COOKIE_NAME};
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+const NEW_PASSWORD: &str = "new-password456";
+
+// Signs up a user who verified their email and logs them in.
+async fn logged_in_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+async fn login_status(app: &TestApp, email: &str, password: &str) -> u16 {
+    app.post_login(&serde_json::json!({ "email": email, "password": password }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_change_password_with_the_current_one() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": PASSWORD,
+            "newPassword": NEW_PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_eq!(login_status(&app, &email, PASSWORD).await, 401);
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_current_password() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": "wrong-password",
+            "newPassword": NEW_PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    assert_eq!(login_status(&app, &email, PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_new_password() {
+    let app = TestApp::new().await;
+    let _ = logged_in_user(&app).await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": PASSWORD,
+            "newPassword": "short",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Invalid password"
+    );
+}
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": PASSWORD,
+            "newPassword": NEW_PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+    app.cookie_jar.add_cookie_str(
+        &format!("{JWT_COOKIE_NAME}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/"),
+        &reqwest::Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
+    );
+
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "currentPassword": PASSWORD,
+            "newPassword": NEW_PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
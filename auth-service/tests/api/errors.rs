@@ -0,0 +1,145 @@
+use auth_service::api::dtos::{ErrorCode, ErrorResponse};
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn should_return_problem_details_with_a_code_and_request_id() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": get_random_email(),
+            "password": "password123"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok()),
+        Some("application/problem+json")
+    );
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+        .expect("No x-request-id header");
+
+    let body = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(body.code, ErrorCode::IncorrectCredentials);
+    assert_eq!(
+        body.problem_type,
+        "urn:auth-service:problem:incorrect_credentials"
+    );
+    assert_eq!(body.title, "Incorrect credentials");
+    assert_eq!(body.status, 401);
+    assert_eq!(body.request_id, Some(request_id));
+    assert!(body.errors.is_none());
+    assert!(body.violations.is_none());
+}
+
+#[tokio::test]
+async fn should_keep_the_request_id_sent_by_the_client() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/logout", &app.address))
+        .header("x-request-id", "req-1234")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .headers()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok()),
+        Some("req-1234")
+    );
+
+    let body = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(body.code, ErrorCode::MissingToken);
+    assert_eq!(body.request_id.as_deref(), Some("req-1234"));
+}
+
+#[tokio::test]
+async fn should_return_400_if_the_body_is_not_well_formed_json() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/signup", &app.address))
+        .header("content-type", "application/json")
+        .body(r#"{"email": "#)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .code,
+        ErrorCode::MalformedRequest
+    );
+}
+
+#[tokio::test]
+async fn should_return_415_if_the_body_is_not_json() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .post(format!("{}/login", &app.address))
+        .body("email=jane@example.com")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 415);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .code,
+        ErrorCode::UnsupportedMediaType
+    );
+}
+
+#[tokio::test]
+async fn should_return_413_if_the_body_is_too_large() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": get_random_email(),
+            "password": "a".repeat(64 * 1024),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 413);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok()),
+        Some("application/problem+json")
+    );
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .code,
+        ErrorCode::PayloadTooLarge
+    );
+}
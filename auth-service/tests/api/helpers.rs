@@ -2,14 +2,29 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use auth_service::{
-    Application, api::{AppState, utils::constants::test}, services::{
-        banned_user_store::HashSetBannedStore, hashmap_two_fa_code_store::HashmapTwoFACodeStore,
-        hashmap_user_store::HashmapUserStore, mock_email_client::MockEmailClient,
-    }
+    Application,
+    api::{
+        AppState,
+        middleware::RateLimitConfig,
+        utils::{
+            auth::generate_email_verification_token,
+            constants::{REFRESH_TOKEN_COOKIE_NAME, test},
+        },
+    },
+    domain::models::{Email, TwoFACodePolicy},
+    services::{
+        banned_user_store::HashSetBannedStore, hashmap_rate_limiter::HashmapRateLimiter,
+        mock_email_client::MockEmailClient,
+    },
 };
 use reqwest::{Client, cookie::Jar};
 use uuid::Uuid;
 
+use super::backends::{
+    TestDatabase, TestOAuthClientStore, TestPasskeyStore, TestRefreshTokenStore,
+    TestTwoFACodeStore, TestUserStore,
+};
+
 /// A helper struct to spawn and interact with a test instance of our application.
 pub struct TestApp {
     /// The address of the running instance of our application.
@@ -17,31 +32,72 @@ pub struct TestApp {
     /// The cookie jar to store cookies.
     pub cookie_jar: Arc<Jar>,
 
+    pub user_store: Arc<RwLock<TestUserStore>>,
+
     pub banned_token_store: Arc<RwLock<HashSetBannedStore>>,
 
-    pub two_fa_code_store: Arc<RwLock<HashmapTwoFACodeStore>>,
+    pub two_fa_code_store: Arc<RwLock<TestTwoFACodeStore>>,
+
+    pub refresh_token_store: Arc<RwLock<TestRefreshTokenStore>>,
+
+    pub oauth_client_store: Arc<RwLock<TestOAuthClientStore>>,
+
+    pub passkey_store: Arc<RwLock<TestPasskeyStore>>,
     /// The HTTP client to interact with the application.
     pub http_client: Client,
+    /// The database backing the stores, cleaned up when the app is dropped.
+    _database: TestDatabase,
 }
 
 impl TestApp {
     /// Spawns a new instance of our application and returns a `TestApp` instance.
     pub async fn new() -> Self {
-        let user_store = Arc::new(RwLock::new(HashmapUserStore::default()));
+        Self::spawn(TwoFACodePolicy::default(), RateLimitConfig::default()).await
+    }
+
+    /// Spawns a new instance of our application that applies `policy` to its 2FA codes.
+    pub async fn with_two_fa_code_policy(policy: TwoFACodePolicy) -> Self {
+        Self::spawn(policy, RateLimitConfig::default()).await
+    }
+
+    /// Spawns a new instance of our application with the given rate limits.
+    pub async fn with_rate_limit_config(config: RateLimitConfig) -> Self {
+        Self::spawn(TwoFACodePolicy::default(), config).await
+    }
+
+    async fn spawn(
+        two_fa_code_policy: TwoFACodePolicy,
+        rate_limit_config: RateLimitConfig,
+    ) -> Self {
+        let (stores, database) = TestDatabase::setup(two_fa_code_policy).await;
+        let user_store = Arc::new(RwLock::new(stores.user_store));
         let banned_token_store = Arc::new(RwLock::new(HashSetBannedStore::default()));
-        let two_fa_code_store = Arc::new(RwLock::new(HashmapTwoFACodeStore::default()));
-        let email_client = Arc::new(RwLock::new(MockEmailClient::default()));
+        let two_fa_code_store = Arc::new(RwLock::new(stores.two_fa_code_store));
+        let email_client = Arc::new(RwLock::new(MockEmailClient));
+        let refresh_token_store = Arc::new(RwLock::new(stores.refresh_token_store));
+        let oauth_client_store = Arc::new(RwLock::new(stores.oauth_client_store));
+        let passkey_store = Arc::new(RwLock::new(stores.passkey_store));
 
         let app_state = AppState::new(
-            user_store,
+            user_store.clone(),
             banned_token_store.clone(),
             two_fa_code_store.clone(),
             email_client,
+            refresh_token_store.clone(),
+            oauth_client_store.clone(),
+            passkey_store.clone(),
         );
 
-        let app = Application::build(app_state, test::APP_ADDRESS)
-            .await
-            .expect("Failed to build app");
+        let rate_limiter = Arc::new(RwLock::new(HashmapRateLimiter::default()));
+
+        let app = Application::build(
+            app_state,
+            rate_limiter,
+            rate_limit_config,
+            test::APP_ADDRESS,
+        )
+        .await
+        .expect("Failed to build app");
 
         let address = format!("http://{}", app.address.clone());
 
@@ -62,16 +118,77 @@ impl TestApp {
         Self {
             address,
             cookie_jar,
+            user_store,
             banned_token_store,
             two_fa_code_store,
+            refresh_token_store,
+            oauth_client_store,
+            passkey_store,
             http_client,
+            _database: database,
         }
     }
 
     /// Sends a GET request to the root endpoint ("/") of the application.
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/.well-known/jwks.json" endpoint of the application.
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/.well-known/openid-configuration" endpoint of the application.
+    pub async fn get_openid_configuration(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!(
+                "{}/.well-known/openid-configuration",
+                &self.address
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/authorize" endpoint with the given query parameters and
+    /// the cookies of the jar, without following the redirect to the client.
+    pub async fn get_authorize(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        Client::builder()
+            .cookie_provider(self.cookie_jar.clone())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+            .get(format!("{}/authorize", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a form-encoded POST request to the "/token" endpoint of the application.
+    pub async fn post_token(&self, form: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/userinfo" endpoint with the given bearer token.
+    pub async fn get_userinfo(&self, access_token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/userinfo", &self.address))
+            .bearer_auth(access_token)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -83,20 +200,148 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/verify-email" endpoint with the given token.
+    pub async fn get_verify_email(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/verify-email", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/resend-verification-email" endpoint of the application.
+    pub async fn post_resend_verification_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/resend-verification-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/password-reset/request" endpoint of the application.
+    pub async fn post_password_reset_request<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/request", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/password-reset/confirm" endpoint of the application.
+    pub async fn post_password_reset_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/password-strength" endpoint of the application.
+    pub async fn post_password_strength<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-strength", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/change-password" endpoint of the application.
+    pub async fn post_change_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/change-email" endpoint of the application.
+    pub async fn post_change_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/change-email", &self.address))
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    /// Sends a GET request to the "/confirm-email-change" endpoint of the application.
+    pub async fn get_confirm_email_change(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/confirm-email-change", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a DELETE request to the "/account" endpoint of the application.
+    pub async fn delete_account<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .delete(format!("{}/account", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a GET request to the "/account/export" endpoint of the application.
+    pub async fn get_account_export(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/export", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Follows the verification link emailed to a user who just signed up, so that they can
+    /// log in.
+    pub async fn verify_email(&self, email: &str) {
+        let token = generate_email_verification_token(&Email::parse(email).unwrap()).unwrap();
+        let response = self.get_verify_email(&token).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
     /// Sends a POST request to the "/login" endpoint of the application.
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
             .json(body)
             .send()
             .await
@@ -106,7 +351,30 @@ impl TestApp {
     /// Sends a POST request to the "/logout" endpoint of the application.
     pub async fn post_logout(&self) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/refresh" endpoint of the application.
+    pub async fn post_refresh(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/refresh", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/refresh" endpoint presenting only the given refresh token,
+    /// bypassing the cookie jar.
+    pub async fn post_refresh_with_token(&self, refresh_token: &str) -> reqwest::Response {
+        Client::new()
+            .post(format!("{}/refresh", &self.address))
+            .header(
+                reqwest::header::COOKIE,
+                format!("{REFRESH_TOKEN_COOKIE_NAME}={refresh_token}"),
+            )
             .send()
             .await
             .expect("Failed to execute request.")
@@ -118,7 +386,94 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/verify-2fa", &self.address))
+            .post(format!("{}/verify-2fa", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/enroll-totp" endpoint of the application.
+    pub async fn post_enroll_totp<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/enroll-totp", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/confirm-totp" endpoint of the application.
+    pub async fn post_confirm_totp<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/confirm-totp", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/recovery-codes" endpoint of the application.
+    pub async fn post_recovery_codes<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/recovery-codes", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/passkeys/register/start" endpoint of the application.
+    pub async fn post_passkey_register_start<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/register/start", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/passkeys/register/finish" endpoint of the application.
+    pub async fn post_passkey_register_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/register/finish", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/passkeys/login/start" endpoint of the application.
+    pub async fn post_passkey_login_start(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/passkeys/login/start", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Sends a POST request to the "/passkeys/login/finish" endpoint of the application.
+    pub async fn post_passkey_login_finish<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/passkeys/login/finish", &self.address))
             .json(body)
             .send()
             .await
@@ -131,7 +486,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/verify-token", &self.address))
+            .post(format!("{}/verify-token", &self.address))
             .json(body)
             .send()
             .await
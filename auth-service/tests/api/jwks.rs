@@ -0,0 +1,20 @@
+use jsonwebtoken::jwk::JwkSet;
+
+use super::helpers::TestApp;
+
+#[tokio::test]
+async fn should_return_200_with_key_set() {
+    let app = TestApp::new().await;
+
+    let response = app.get_jwks().await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["cache-control"], "public, max-age=300");
+
+    // The test app signs with the shared HMAC secret, which must never be published.
+    let jwks = response
+        .json::<JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+    assert!(jwks.keys.is_empty());
+}
@@ -1,10 +1,14 @@
 use auth_service::{
     api::{
-        dtos::{ErrorResponse, MFARequiredResponse},
-        utils::constants::JWT_COOKIE_NAME,
+        dtos::{ErrorCode, ErrorResponse, MFARequiredResponse},
+        utils::constants::{JWT_COOKIE_NAME, PASSWORD_POLICY},
+    },
+    domain::{
+        models::{Email, LockoutPolicy},
+        ports::{TwoFACodeStore, UserStore},
     },
-    domain::{models::Email, ports::TwoFACodeStore},
 };
+use chrono::Utc;
 
 use super::helpers::*;
 
@@ -49,41 +53,73 @@ async fn should_return_400_if_invalid_input() {
     let random_email = get_random_email();
 
     let input = [
-        serde_json::json!({
-            "email": "",
-            "password": "password123",
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "",
-        }),
-        serde_json::json!({
-            "email": "",
-            "password": "",
-        }),
-        serde_json::json!({
-            "email": "invalid_email",
-            "password": "password123",
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "invalid",
-        }),
+        (
+            serde_json::json!({
+                "email": "",
+                "password": "password123",
+            }),
+            vec!["email"],
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "password": "",
+            }),
+            vec!["password"],
+        ),
+        (
+            serde_json::json!({
+                "email": "",
+                "password": "",
+            }),
+            vec!["email", "password"],
+        ),
+        (
+            serde_json::json!({
+                "email": "invalid_email",
+                "password": "password123",
+            }),
+            vec!["email"],
+        ),
     ];
 
-    for i in input.iter() {
+    for (i, fields) in input.iter() {
         let response = app.post_login(i).await;
         assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", i);
 
+        let body = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(body.code, ErrorCode::InvalidInput);
         assert_eq!(
-            response
-                .json::<ErrorResponse>()
-                .await
-                .expect("Could not deserialize response body to ErrorResponse")
-                .error,
-            "Invalid credentials".to_owned()
+            body.errors
+                .expect("No invalid fields listed")
+                .iter()
+                .map(|error| error.field.as_str())
+                .collect::<Vec<_>>(),
+            *fields,
+            "Failed for input: {:?}",
+            i
         );
     }
+
+    // Passwords too long to have ever been accepted are refused before hashing.
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": random_email,
+            "password": "a".repeat(PASSWORD_POLICY.max_length + 1),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Invalid credentials".to_owned()
+    );
 }
 
 #[tokio::test]
@@ -108,9 +144,18 @@ async fn should_return_401_if_incorrect_credentials() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to ErrorResponse")
-            .error,
+            .title,
         "Incorrect credentials".to_owned()
     );
+
+    // Passwords shorter than the policy asks for are left to the hash to refuse.
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": random_email,
+            "password": "invalid",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
 }
 
 #[tokio::test]
@@ -128,6 +173,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
     let response = app.post_signup(&signup_body).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -161,6 +207,7 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
     let response = app.post_signup(&signup_body).await;
 
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -177,6 +224,7 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
         .expect("Could not deserialize response body to TwoFactorAuthResponse");
 
     assert_eq!(json_body.message, "2FA required".to_owned());
+    assert_eq!(json_body.two_fa_method, "email");
 
     // TODO: assert that `json_body.login_attempt_id` is stored inside `app.two_fa_code_store`
     let (stored_login_attempt_id, _) = app
@@ -188,3 +236,125 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
         .unwrap();
     assert_eq!(stored_login_attempt_id.as_ref(), json_body.login_attempt_id);
 }
+
+#[tokio::test]
+async fn should_return_423_after_too_many_failed_logins() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let wrong_login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrong-password",
+    });
+    let max_failed_attempts = LockoutPolicy::default().max_failed_attempts;
+    for _ in 1..max_failed_attempts {
+        let response = app.post_login(&wrong_login_body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app.post_login(&wrong_login_body).await;
+    assert_eq!(response.status().as_u16(), 423);
+    assert!(response.headers().contains_key("retry-after"));
+    let body = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(body.title, "Account temporarily locked".to_owned());
+    assert_eq!(body.code, ErrorCode::AccountLocked);
+    assert!(
+        body.detail
+            .is_some_and(|detail| detail.starts_with("Retry in "))
+    );
+
+    // Even the correct password is refused while the account is locked.
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 423);
+}
+
+#[tokio::test]
+async fn should_return_200_once_lockout_expires() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let email = Email::parse(&random_email).unwrap();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    // A lock whose window has already passed.
+    app.user_store
+        .write()
+        .await
+        .lock_user(&email, Utc::now() - chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert_eq!(user.locked_until, None);
+}
+
+#[tokio::test]
+async fn should_reset_failed_logins_after_success() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let wrong_login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrong-password",
+    });
+    let max_failed_attempts = LockoutPolicy::default().max_failed_attempts;
+    for _ in 1..max_failed_attempts {
+        let response = app.post_login(&wrong_login_body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": random_email,
+            "password": "password123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_login(&wrong_login_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
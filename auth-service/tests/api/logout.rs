@@ -19,6 +19,7 @@ async fn should_return_200_if_valid_jwt_cookie() {
     });
 
     let _ = app.post_signup(&signup_body).await;
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -48,6 +49,7 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
     });
 
     let _ = app.post_signup(&signup_body).await;
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -71,7 +73,7 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to UserBody")
-            .error,
+            .title,
         "Missing token"
     );
 }
@@ -89,7 +91,7 @@ async fn should_return_400_if_jwt_cookie_missing() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to UserBody")
-            .error,
+            .title,
         "Missing token"
     );
 }
@@ -116,7 +118,7 @@ async fn should_return_401_if_invalid_token() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to UserBody")
-            .error,
+            .title,
         "Invalid token"
     );
 }
@@ -1,7 +1,22 @@
+pub mod account;
+pub mod backends;
+pub mod change_email;
+pub mod change_password;
+pub mod errors;
 pub mod helpers;
+pub mod jwks;
 pub mod login;
 pub mod logout;
+pub mod oidc;
+pub mod passkeys;
+pub mod password_reset;
+pub mod password_strength;
+pub mod rate_limit;
+pub mod recovery_codes;
+pub mod refresh;
 pub mod root;
 pub mod signup;
+pub mod totp;
 pub mod verify_2fa;
+pub mod verify_email;
 pub mod verify_token;
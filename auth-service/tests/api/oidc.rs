@@ -0,0 +1,441 @@
+use auth_service::{
+    api::{
+        dtos::{OAuthErrorResponse, OpenIdConfiguration, TokenResponse, UserInfoResponse},
+        utils::constants::{JWT_COOKIE_NAME, JWT_ISSUER},
+    },
+    domain::{models::OAuthClient, ports::OAuthClientStore},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use reqwest::{Client, Url};
+
+use super::helpers::*;
+
+const CLIENT_ID: &str = "app";
+const CLIENT_SECRET: &str = "app-secret";
+const REDIRECT_URI: &str = "https://app.example.com/callback";
+// The example of RFC 7636, appendix B.
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+// Registers the client, public unless given a secret.
+async fn register_client(app: &TestApp, client_secret: Option<&str>) {
+    let client = OAuthClient::new(
+        CLIENT_ID.to_owned(),
+        "App".to_owned(),
+        vec![REDIRECT_URI.to_owned()],
+        client_secret,
+    );
+    app.oauth_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .unwrap();
+}
+
+// Signs up and logs in a user without 2FA, returning their email.
+async fn login(app: &TestApp) -> String {
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    random_email
+}
+
+fn authorize_query() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("response_type", "code"),
+        ("client_id", CLIENT_ID),
+        ("redirect_uri", REDIRECT_URI),
+        ("scope", "openid email"),
+        ("state", "af0ifjsldkj"),
+        ("nonce", "n-0S6_WzA2Mj"),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+    ]
+}
+
+// Returns the redirect URI the response sends the user back to, and its query parameters.
+fn redirect_location(response: &reqwest::Response) -> (String, Vec<(String, String)>) {
+    assert_eq!(response.status().as_u16(), 303);
+    let location = Url::parse(response.headers()["location"].to_str().unwrap()).unwrap();
+    let params = location.query_pairs().into_owned().collect();
+    let mut redirect_uri = location;
+    redirect_uri.set_query(None);
+    (redirect_uri.to_string(), params)
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+// Runs the authorization request and returns the code handed to the client.
+async fn authorize(app: &TestApp) -> String {
+    let response = app.get_authorize(&authorize_query()).await;
+    let (redirect_uri, params) = redirect_location(&response);
+    assert_eq!(redirect_uri, REDIRECT_URI);
+    assert_eq!(param(&params, "state"), Some("af0ifjsldkj"));
+    param(&params, "code")
+        .expect("No code in the redirect")
+        .to_owned()
+}
+
+fn token_form(code: &str) -> Vec<(&str, &str)> {
+    vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", REDIRECT_URI),
+        ("client_id", CLIENT_ID),
+        ("code_verifier", CODE_VERIFIER),
+    ]
+}
+
+async fn assert_oauth_error(response: reqwest::Response, status: u16, error: &str) {
+    assert_eq!(response.status().as_u16(), status);
+    assert_eq!(
+        response
+            .json::<OAuthErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to OAuthErrorResponse")
+            .error,
+        error
+    );
+}
+
+#[tokio::test]
+async fn should_serve_discovery_document() {
+    let app = TestApp::new().await;
+
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let configuration = response
+        .json::<OpenIdConfiguration>()
+        .await
+        .expect("Could not deserialize response body to OpenIdConfiguration");
+    assert_eq!(configuration.issuer, *JWT_ISSUER);
+    assert!(configuration.authorization_endpoint.ends_with("/authorize"));
+    assert!(configuration.token_endpoint.ends_with("/token"));
+    assert!(configuration.userinfo_endpoint.ends_with("/userinfo"));
+    assert!(configuration.jwks_uri.ends_with("/.well-known/jwks.json"));
+    assert_eq!(configuration.code_challenge_methods_supported, ["S256"]);
+    assert_eq!(
+        configuration.id_token_signing_alg_values_supported,
+        ["HS256"]
+    );
+}
+
+#[tokio::test]
+async fn should_issue_tokens_for_authorization_code() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    let email = login(&app).await;
+
+    let code = authorize(&app).await;
+    let response = app.post_token(&token_form(&code)).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+
+    let tokens = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_eq!(tokens.token_type, "Bearer");
+    assert_eq!(tokens.scope, "openid email");
+
+    // The ID token is meant for the client and answers its authorization request.
+    let payload = tokens.id_token.split('.').nth(1).unwrap();
+    let claims: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+    assert_eq!(claims["aud"], CLIENT_ID);
+    assert_eq!(claims["iss"], JWT_ISSUER.as_str());
+    assert_eq!(claims["sub"], email.as_str());
+    assert_eq!(claims["nonce"], "n-0S6_WzA2Mj");
+
+    let response = app.get_userinfo(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let user_info = response
+        .json::<UserInfoResponse>()
+        .await
+        .expect("Could not deserialize response body to UserInfoResponse");
+    assert_eq!(user_info.sub, email);
+    assert_eq!(user_info.email, Some(email));
+}
+
+#[tokio::test]
+async fn should_leave_email_out_of_userinfo_without_email_scope() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    let email = login(&app).await;
+
+    let query: Vec<_> = authorize_query()
+        .into_iter()
+        .map(|(key, value)| (key, if key == "scope" { "openid" } else { value }))
+        .collect();
+    let response = app.get_authorize(&query).await;
+    let (_, params) = redirect_location(&response);
+    let code = param(&params, "code").expect("No code in the redirect");
+    let tokens = app
+        .post_token(&token_form(code))
+        .await
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_eq!(tokens.scope, "openid");
+
+    let response = app.get_userinfo(&tokens.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let user_info = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Could not deserialize response body");
+    assert_eq!(user_info, serde_json::json!({ "sub": email }));
+}
+
+#[tokio::test]
+async fn should_redeem_code_only_once() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    login(&app).await;
+
+    let code = authorize(&app).await;
+    let response = app.post_token(&token_form(&code)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_oauth_error(
+        app.post_token(&token_form(&code)).await,
+        400,
+        "invalid_grant",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn should_return_invalid_grant_if_code_verifier_wrong() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    login(&app).await;
+
+    let code = authorize(&app).await;
+    let wrong_verifier = "a".repeat(43);
+    let mut form = token_form(&code);
+    form.retain(|(name, _)| *name != "code_verifier");
+    form.push(("code_verifier", &wrong_verifier));
+
+    assert_oauth_error(app.post_token(&form).await, 400, "invalid_grant").await;
+
+    // A code presented with the wrong verifier is burnt.
+    assert_oauth_error(
+        app.post_token(&token_form(&code)).await,
+        400,
+        "invalid_grant",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn should_return_invalid_grant_if_redirect_uri_differs() {
+    let app = TestApp::new().await;
+    let client = OAuthClient::new(
+        CLIENT_ID.to_owned(),
+        "App".to_owned(),
+        vec![
+            REDIRECT_URI.to_owned(),
+            "https://app.example.com/other".to_owned(),
+        ],
+        None,
+    );
+    app.oauth_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .unwrap();
+    login(&app).await;
+
+    let code = authorize(&app).await;
+    let mut form = token_form(&code);
+    form.retain(|(name, _)| *name != "redirect_uri");
+    form.push(("redirect_uri", "https://app.example.com/other"));
+
+    assert_oauth_error(app.post_token(&form).await, 400, "invalid_grant").await;
+}
+
+#[tokio::test]
+async fn should_authenticate_confidential_client() {
+    let app = TestApp::new().await;
+    register_client(&app, Some(CLIENT_SECRET)).await;
+    login(&app).await;
+
+    let code = authorize(&app).await;
+    assert_oauth_error(
+        app.post_token(&token_form(&code)).await,
+        401,
+        "invalid_client",
+    )
+    .await;
+
+    let mut form = token_form(&code);
+    form.push(("client_secret", "wrong"));
+    assert_oauth_error(app.post_token(&form).await, 401, "invalid_client").await;
+
+    // Failed client authentication leaves the code alone.
+    let mut form = token_form(&code);
+    form.push(("client_secret", CLIENT_SECRET));
+    let response = app.post_token(&form).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_unsupported_grant_type() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+
+    let form = [("grant_type", "password"), ("client_id", CLIENT_ID)];
+    assert_oauth_error(app.post_token(&form).await, 400, "unsupported_grant_type").await;
+}
+
+#[tokio::test]
+async fn should_redirect_login_required_if_not_logged_in() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+
+    let response = app.get_authorize(&authorize_query()).await;
+    let (redirect_uri, params) = redirect_location(&response);
+    assert_eq!(redirect_uri, REDIRECT_URI);
+    assert_eq!(param(&params, "error"), Some("login_required"));
+    assert_eq!(param(&params, "state"), Some("af0ifjsldkj"));
+    assert_eq!(param(&params, "code"), None);
+}
+
+#[tokio::test]
+async fn should_redirect_error_if_request_invalid() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    login(&app).await;
+
+    let test_cases = [
+        ("response_type", "token", "unsupported_response_type"),
+        ("scope", "email", "invalid_scope"),
+        ("code_challenge_method", "plain", "invalid_request"),
+        ("code_challenge", "too-short", "invalid_request"),
+    ];
+
+    for (name, value, error) in test_cases {
+        let mut query = authorize_query();
+        query.retain(|(key, _)| *key != name);
+        query.push((name, value));
+
+        let response = app.get_authorize(&query).await;
+        let (_, params) = redirect_location(&response);
+        assert_eq!(param(&params, "error"), Some(error), "{name}={value}");
+    }
+}
+
+#[tokio::test]
+async fn should_not_redirect_to_unregistered_uri() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    login(&app).await;
+
+    let mut query = authorize_query();
+    query.retain(|(key, _)| *key != "redirect_uri");
+    query.push(("redirect_uri", "https://evil.example.com/callback"));
+    let response = app.get_authorize(&query).await;
+    assert!(response.headers().get("location").is_none());
+    assert_oauth_error(response, 400, "invalid_request").await;
+
+    let mut query = authorize_query();
+    query.retain(|(key, _)| *key != "client_id");
+    query.push(("client_id", "unknown"));
+    let response = app.get_authorize(&query).await;
+    assert!(response.headers().get("location").is_none());
+    assert_oauth_error(response, 400, "invalid_request").await;
+}
+
+#[tokio::test]
+async fn should_return_401_from_userinfo_if_token_invalid() {
+    let app = TestApp::new().await;
+
+    let response = app.get_userinfo("invalid").await;
+    assert_eq!(
+        response.headers()["www-authenticate"],
+        r#"Bearer error="invalid_token""#
+    );
+    assert_oauth_error(response, 401, "invalid_token").await;
+}
+
+#[tokio::test]
+async fn should_accept_access_token_only_at_userinfo() {
+    let app = TestApp::new().await;
+    register_client(&app, None).await;
+    login(&app).await;
+
+    let code = authorize(&app).await;
+    let tokens = app
+        .post_token(&token_form(&code))
+        .await
+        .json::<TokenResponse>()
+        .await
+        .unwrap();
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": tokens.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Nor does it pass for the `jwt` cookie of a session.
+    for path in ["/enroll-totp", "/recovery-codes", "/logout"] {
+        let response = Client::new()
+            .post(format!("{}{path}", &app.address))
+            .header(
+                reqwest::header::COOKIE,
+                format!("{JWT_COOKIE_NAME}={}", tokens.access_token),
+            )
+            .json(&serde_json::json!({ "password": "password123" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 401, "{path}");
+    }
+
+    assert_eq!(app.get_userinfo(&tokens.access_token).await.status(), 200);
+}
+
+#[tokio::test]
+async fn should_return_401_from_userinfo_if_given_session_token() {
+    let app = TestApp::new().await;
+    let email = login(&app).await;
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+        }))
+        .await;
+    let session_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    assert_oauth_error(app.get_userinfo(&session_token).await, 401, "invalid_token").await;
+}
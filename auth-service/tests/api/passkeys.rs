@@ -0,0 +1,452 @@
+use auth_service::{
+    api::{
+        dtos::{ErrorResponse, PasskeyLoginOptions, PasskeyRegistrationOptions},
+        utils::constants::{
+            JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME, WEBAUTHN_ORIGIN, WEBAUTHN_RP_ID,
+        },
+    },
+    domain::{
+        models::{Email, PasskeyCeremony, PasskeyChallenge, PasskeyChallengeRecord},
+        ports::{PasskeyStore, UserStore},
+    },
+};
+use aws_lc_rs::{
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{TimeDelta, Utc};
+use ciborium::Value;
+use sha2::{Digest, Sha256};
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+/// A software authenticator holding a single ES256 passkey, standing in for a security key
+/// or a phone. It always verifies the user.
+#[derive(Clone)]
+struct SoftwareAuthenticator {
+    credential_id: Vec<u8>,
+    pkcs8: Vec<u8>,
+    /// The signature counter, or `None` for an authenticator that keeps none.
+    sign_count: Option<u32>,
+    user_handle: Option<String>,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &SystemRandom::new())
+                .unwrap();
+        Self {
+            credential_id: uuid::Uuid::new_v4().as_bytes().to_vec(),
+            pkcs8: pkcs8.as_ref().to_vec(),
+            sign_count: None,
+            user_handle: None,
+        }
+    }
+
+    fn with_sign_count() -> Self {
+        Self {
+            sign_count: Some(0),
+            ..Self::new()
+        }
+    }
+
+    fn key_pair(&self) -> EcdsaKeyPair {
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &self.pkcs8).unwrap()
+    }
+
+    fn credential_id(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.credential_id)
+    }
+
+    // The public key as a COSE_Key: EC2 (1: 2), ES256 (3: -7), P-256 (-1: 1), x and y.
+    fn cose_public_key(&self) -> Value {
+        let key_pair = self.key_pair();
+        let point = key_pair.public_key().as_ref();
+        Value::Map(vec![
+            (Value::from(1), Value::from(2)),
+            (Value::from(3), Value::from(-7)),
+            (Value::from(-1), Value::from(1)),
+            (Value::from(-2), Value::Bytes(point[1..33].to_vec())),
+            (Value::from(-3), Value::Bytes(point[33..].to_vec())),
+        ])
+    }
+
+    fn authenticator_data(&self, attested: bool) -> Vec<u8> {
+        // User present and verified, plus attested credential data in registrations.
+        let flags = if attested { 0x45 } else { 0x05 };
+        let mut data = Sha256::digest(WEBAUTHN_RP_ID.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend(self.sign_count.unwrap_or(0).to_be_bytes());
+        if attested {
+            data.extend([0; 16]);
+            data.extend((self.credential_id.len() as u16).to_be_bytes());
+            data.extend(&self.credential_id);
+            ciborium::into_writer(&self.cose_public_key(), &mut data).unwrap();
+        }
+        data
+    }
+
+    /// Creates the passkey, answering the options of `/passkeys/register/start`.
+    fn create(&mut self, options: &PasskeyRegistrationOptions) -> serde_json::Value {
+        assert_eq!(options.rp.id, *WEBAUTHN_RP_ID);
+        self.user_handle = Some(options.user.id.clone());
+
+        let attestation_object = Value::Map(vec![
+            (Value::from("fmt"), Value::from("none")),
+            (Value::from("attStmt"), Value::Map(vec![])),
+            (
+                Value::from("authData"),
+                Value::Bytes(self.authenticator_data(true)),
+            ),
+        ]);
+        let mut attestation_object_bytes = Vec::new();
+        ciborium::into_writer(&attestation_object, &mut attestation_object_bytes).unwrap();
+
+        serde_json::json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": client_data("webauthn.create", &options.challenge),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation_object_bytes),
+            },
+        })
+    }
+
+    /// Signs in with the passkey, answering the options of `/passkeys/login/start`.
+    fn get(&mut self, options: &PasskeyLoginOptions) -> serde_json::Value {
+        assert_eq!(options.rp_id, *WEBAUTHN_RP_ID);
+        self.sign_count = self.sign_count.map(|count| count + 1);
+
+        let client_data_json = client_data("webauthn.get", &options.challenge);
+        let authenticator_data = self.authenticator_data(false);
+        let mut signed = authenticator_data.clone();
+        signed.extend(Sha256::digest(
+            URL_SAFE_NO_PAD.decode(&client_data_json).unwrap(),
+        ));
+        let signature = self.key_pair().sign(&SystemRandom::new(), &signed).unwrap();
+
+        serde_json::json!({
+            "id": self.credential_id(),
+            "rawId": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": client_data_json,
+                "authenticatorData": URL_SAFE_NO_PAD.encode(authenticator_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+                "userHandle": self.user_handle,
+            },
+        })
+    }
+}
+
+// The base64url-encoded client data the browser would collect.
+fn client_data(ceremony_type: &str, challenge: &str) -> String {
+    let client_data = serde_json::json!({
+        "type": ceremony_type,
+        "challenge": challenge,
+        "origin": *WEBAUTHN_ORIGIN,
+        "crossOrigin": false,
+    });
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&client_data).unwrap())
+}
+
+// Signs up and logs in a user without 2FA, leaving the auth cookie in the app's jar.
+async fn logged_in_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+async fn register_passkey(app: &TestApp, authenticator: &mut SoftwareAuthenticator) {
+    let response = app
+        .post_passkey_register_start(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let options = response
+        .json::<PasskeyRegistrationOptions>()
+        .await
+        .expect("Could not deserialize response body to PasskeyRegistrationOptions");
+
+    let response = app
+        .post_passkey_register_finish(&authenticator.create(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn login_options(app: &TestApp) -> PasskeyLoginOptions {
+    let response = app.post_passkey_login_start().await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<PasskeyLoginOptions>()
+        .await
+        .expect("Could not deserialize response body to PasskeyLoginOptions")
+}
+
+async fn assert_error(response: reqwest::Response, status: u16, error: &str) {
+    assert_eq!(response.status().as_u16(), status);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        error
+    );
+}
+
+#[tokio::test]
+async fn should_register_passkey_and_log_in_with_it() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_passkey_register_start(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let options = response.json::<PasskeyRegistrationOptions>().await.unwrap();
+    assert_eq!(options.user.name, email);
+    assert_eq!(options.attestation, "none");
+    assert!(
+        options
+            .pub_key_cred_params
+            .iter()
+            .any(|param| param.alg == -7)
+    );
+
+    let mut authenticator = SoftwareAuthenticator::new();
+    let response = app
+        .post_passkey_register_finish(&authenticator.create(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let options = login_options(&app).await;
+    assert!(options.allow_credentials.is_empty());
+    let response = app
+        .post_passkey_login_finish(&authenticator.get(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // A passkey login issues the same cookies as a password login, with no second factor.
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(
+        response
+            .cookies()
+            .any(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+    );
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_cookie.value() }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_exclude_registered_passkeys_from_new_registrations() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    let options = app
+        .post_passkey_register_start(&serde_json::json!({ "password": PASSWORD }))
+        .await
+        .json::<PasskeyRegistrationOptions>()
+        .await
+        .unwrap();
+    assert_eq!(options.exclude_credentials.len(), 1);
+    assert_eq!(
+        options.exclude_credentials[0].id,
+        authenticator.credential_id()
+    );
+
+    // An authenticator ignoring the exclusion cannot register the passkey twice.
+    let response = app
+        .post_passkey_register_finish(&authenticator.create(&options))
+        .await;
+    assert_error(response, 409, "Passkey already registered").await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_registering_with_wrong_password() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_passkey_register_start(&serde_json::json!({ "password": "wrong-password" }))
+        .await;
+    assert_error(response, 401, "Incorrect credentials").await;
+
+    let email = Email::parse(&email).unwrap();
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert_eq!(user.failed_login_attempts, 1);
+}
+
+#[tokio::test]
+async fn should_return_400_if_registering_without_token() {
+    let app = TestApp::new().await;
+    let response = app
+        .post_passkey_register_start(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_error(response, 400, "Missing token").await;
+}
+
+#[tokio::test]
+async fn should_return_401_for_unknown_passkey() {
+    let app = TestApp::new().await;
+    let options = login_options(&app).await;
+    let response = app
+        .post_passkey_login_finish(&SoftwareAuthenticator::new().get(&options))
+        .await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_signature_is_invalid() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    // Another key claiming the registered credential ID.
+    let mut impostor = SoftwareAuthenticator {
+        credential_id: authenticator.credential_id.clone(),
+        user_handle: authenticator.user_handle.clone(),
+        ..SoftwareAuthenticator::new()
+    };
+    let options = login_options(&app).await;
+    let response = app.post_passkey_login_finish(&impostor.get(&options)).await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_not_accept_the_same_assertion_twice() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    let assertion = authenticator.get(&login_options(&app).await);
+    let response = app.post_passkey_login_finish(&assertion).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let response = app.post_passkey_login_finish(&assertion).await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_reject_cloned_authenticators() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::with_sign_count();
+    register_passkey(&app, &mut authenticator).await;
+    let mut clone = authenticator.clone();
+
+    let response = app
+        .post_passkey_login_finish(&authenticator.get(&login_options(&app).await))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The clone reports the counter the original already used.
+    let response = app
+        .post_passkey_login_finish(&clone.get(&login_options(&app).await))
+        .await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_not_log_in_with_a_registration_challenge() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    let options = app
+        .post_passkey_register_start(&serde_json::json!({ "password": PASSWORD }))
+        .await
+        .json::<PasskeyRegistrationOptions>()
+        .await
+        .unwrap();
+    let login_options = PasskeyLoginOptions {
+        challenge: options.challenge,
+        timeout: options.timeout,
+        rp_id: options.rp.id,
+        allow_credentials: Vec::new(),
+        user_verification: "required".to_owned(),
+    };
+    let response = app
+        .post_passkey_login_finish(&authenticator.get(&login_options))
+        .await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_challenge_expired() {
+    let app = TestApp::new().await;
+    logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    let challenge = PasskeyChallenge::default();
+    app.passkey_store
+        .write()
+        .await
+        .add_challenge(
+            &challenge,
+            PasskeyChallengeRecord {
+                ceremony: PasskeyCeremony::Authentication,
+                email: None,
+                expires_at: Utc::now() - TimeDelta::seconds(1),
+            },
+        )
+        .await
+        .unwrap();
+    let options = PasskeyLoginOptions {
+        challenge: challenge.as_ref().to_owned(),
+        ..login_options(&app).await
+    };
+    let response = app
+        .post_passkey_login_finish(&authenticator.get(&options))
+        .await;
+    assert_error(response, 401, "Incorrect credentials").await;
+}
+
+#[tokio::test]
+async fn should_return_423_if_account_is_locked() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register_passkey(&app, &mut authenticator).await;
+
+    app.user_store
+        .write()
+        .await
+        .lock_user(
+            &Email::parse(&email).unwrap(),
+            Utc::now() + TimeDelta::minutes(15),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .post_passkey_login_finish(&authenticator.get(&login_options(&app).await))
+        .await;
+    assert_error(response, 423, "Account temporarily locked").await;
+}
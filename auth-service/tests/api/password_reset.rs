@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use auth_service::{
+    api::{
+        dtos::ErrorResponse,
+        middleware::{RateLimitConfig, RouteRateLimits},
+        utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+    domain::{
+        models::{Email, PasswordResetToken, RateLimitPolicy},
+        ports::UserStore,
+    },
+};
+use chrono::{TimeDelta, Utc};
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+const NEW_PASSWORD: &str = "new-password456";
+
+// Signs up a user who verified their email.
+async fn verified_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    email
+}
+
+// Stores a reset token for the user, as `/password-reset/request` does before emailing it.
+async fn reset_token(app: &TestApp, email: &str) -> PasswordResetToken {
+    let token = PasswordResetToken::default();
+    app.user_store
+        .write()
+        .await
+        .set_password_reset_token(
+            &Email::parse(email).unwrap(),
+            &token,
+            Utc::now() + TimeDelta::seconds(600),
+        )
+        .await
+        .unwrap();
+    token
+}
+
+async fn confirm(app: &TestApp, token: &PasswordResetToken, new_password: &str) -> u16 {
+    app.post_password_reset_confirm(&serde_json::json!({
+        "token": token.as_ref(),
+        "newPassword": new_password,
+    }))
+    .await
+    .status()
+    .as_u16()
+}
+
+async fn login_status(app: &TestApp, email: &str, password: &str) -> u16 {
+    app.post_login(&serde_json::json!({ "email": email, "password": password }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_reset_password_once_with_the_emailed_token() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+    let token = reset_token(&app, &email).await;
+
+    assert_eq!(confirm(&app, &token, NEW_PASSWORD).await, 200);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, 401);
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+
+    assert_eq!(confirm(&app, &token, "another-password").await, 401);
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_revoke_every_session_on_reset() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let cookie = |name| {
+        response
+            .cookies()
+            .find(|cookie| cookie.name() == name)
+            .expect("No cookie found")
+            .value()
+            .to_owned()
+    };
+    let auth_token = cookie(JWT_COOKIE_NAME);
+    let refresh_token = cookie(REFRESH_TOKEN_COOKIE_NAME);
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = reset_token(&app, &email).await;
+    assert_eq!(confirm(&app, &token, NEW_PASSWORD).await, 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_keep_the_token_when_the_new_password_is_invalid() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+    let token = reset_token(&app, &email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token.as_ref(),
+            "newPassword": "short",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Invalid password"
+    );
+
+    assert_eq!(confirm(&app, &token, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_keep_the_token_when_the_new_password_contains_the_email() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+    let token = reset_token(&app, &email).await;
+
+    let local_part = email.split('@').next().unwrap();
+    assert_eq!(
+        confirm(&app, &token, &format!("{local_part}-password456")).await,
+        400
+    );
+
+    assert_eq!(confirm(&app, &token, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+    let _ = reset_token(&app, &email).await;
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": "invalid",
+            "newPassword": NEW_PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        confirm(&app, &PasswordResetToken::default(), NEW_PASSWORD).await,
+        401
+    );
+    assert_eq!(login_status(&app, &email, PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn should_accept_reset_requests_alike_for_every_address() {
+    let app = TestApp::new().await;
+    let email = verified_user(&app).await;
+    let pending_token = reset_token(&app, &email).await;
+
+    for email in [email, get_random_email()] {
+        let response = app
+            .post_password_reset_request(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 202);
+    }
+
+    // The emailed token replaced the pending one.
+    assert_eq!(confirm(&app, &pending_token, NEW_PASSWORD).await, 401);
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": "invalid_email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_429_when_email_exceeds_reset_quota() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        password_reset: RouteRateLimits {
+            per_ip: RateLimitPolicy {
+                capacity: 100,
+                period: Duration::from_secs(60),
+            },
+            per_email: RateLimitPolicy {
+                capacity: 1,
+                period: Duration::from_secs(3600),
+            },
+        },
+        ..Default::default()
+    })
+    .await;
+    let body = serde_json::json!({ "email": get_random_email() });
+
+    let response = app.post_password_reset_request(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let response = app.post_password_reset_request(&body).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().get("retry-after").is_some());
+}
@@ -0,0 +1,110 @@
+use auth_service::api::dtos::{ErrorCode, ErrorResponse, PasswordStrengthResponse};
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn should_score_common_passwords_as_weak() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_strength(&serde_json::json!({ "password": "password" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("cache-control")
+            .and_then(|value| value.to_str().ok()),
+        Some("no-store")
+    );
+
+    let body = response
+        .json::<PasswordStrengthResponse>()
+        .await
+        .expect("Could not deserialize response body to PasswordStrengthResponse");
+    assert_eq!(body.score, 0);
+    assert_eq!(
+        body.warning.as_deref(),
+        Some("This is a top-10 common password")
+    );
+    assert!(!body.suggestions.is_empty());
+    assert_eq!(body.crack_time_display, "less than a second");
+    assert!(body.violations.is_empty());
+}
+
+#[tokio::test]
+async fn should_score_long_passphrases_as_strong() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_strength(&serde_json::json!({
+            "password": "correct horse battery staple"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response
+        .json::<PasswordStrengthResponse>()
+        .await
+        .expect("Could not deserialize response body to PasswordStrengthResponse");
+    assert_eq!(body.score, 4);
+    assert_eq!(body.warning, None);
+    assert!(body.suggestions.is_empty());
+    assert!(body.crack_time_seconds > 1e9);
+}
+
+#[tokio::test]
+async fn should_list_the_password_rules_that_fail() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_strength(&serde_json::json!({
+            "password": "jane.doe",
+            "email": "jane.doe@example.com"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response
+        .json::<PasswordStrengthResponse>()
+        .await
+        .expect("Could not deserialize response body to PasswordStrengthResponse");
+    assert_eq!(
+        body.warning.as_deref(),
+        Some("Contains words from your email address")
+    );
+    assert_eq!(
+        body.violations
+            .iter()
+            .map(|violation| violation.rule.as_str())
+            .collect::<Vec<_>>(),
+        ["email_local_part"]
+    );
+}
+
+#[tokio::test]
+async fn should_return_400_if_the_email_is_invalid() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_strength(&serde_json::json!({
+            "password": "password",
+            "email": "invalid_email"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let body = response
+        .json::<ErrorResponse>()
+        .await
+        .expect("Could not deserialize response body to ErrorResponse");
+    assert_eq!(body.code, ErrorCode::InvalidInput);
+    assert_eq!(
+        body.errors
+            .expect("No invalid fields listed")
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect::<Vec<_>>(),
+        ["email"]
+    );
+}
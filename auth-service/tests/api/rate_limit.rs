@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use auth_service::{
+    api::{
+        dtos::ErrorResponse,
+        middleware::{RateLimitConfig, RouteRateLimits},
+    },
+    domain::models::RateLimitPolicy,
+};
+
+use super::helpers::*;
+
+fn policy(capacity: u32) -> RateLimitPolicy {
+    RateLimitPolicy {
+        capacity,
+        period: Duration::from_secs(60),
+    }
+}
+
+fn login_body(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "password": "password123",
+    })
+}
+
+#[tokio::test]
+async fn should_return_429_when_ip_exceeds_login_quota() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: policy(2),
+            per_email: policy(100),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    for _ in 0..2 {
+        let response = app.post_login(&login_body(&get_random_email())).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = app.post_login(&login_body(&get_random_email())).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let retry_after: u64 = response
+        .headers()
+        .get("retry-after")
+        .expect("No Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 30);
+
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Too many requests".to_owned()
+    );
+}
+
+#[tokio::test]
+async fn should_return_429_when_email_exceeds_login_quota() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: policy(100),
+            per_email: policy(2),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+    for _ in 0..2 {
+        let response = app.post_login(&login_body(&random_email)).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // Changing the case of the email does not get around the quota.
+    let response = app
+        .post_login(&login_body(&random_email.to_uppercase()))
+        .await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let response = app.post_login(&login_body(&get_random_email())).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_limit_routes_independently() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: policy(1),
+            per_email: policy(100),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+    let response = app.post_login(&login_body(&random_email)).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.post_login(&login_body(&random_email)).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": random_email,
+            "password": "password123",
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[tokio::test]
+async fn should_return_429_when_email_exceeds_signup_quota() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        signup: RouteRateLimits {
+            per_ip: policy(100),
+            per_email: policy(1),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let signup_body = serde_json::json!({
+        "email": get_random_email(),
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 429);
+}
+
+// Logs in from `client`, as a reverse proxy in front of the service would report it.
+async fn post_login_forwarded_for(app: &TestApp, client: &str) -> u16 {
+    app.http_client
+        .post(format!("{}/login", &app.address))
+        .header("x-forwarded-for", client)
+        .json(&login_body(&get_random_email()))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_limit_the_client_named_by_a_trusted_proxy() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: policy(1),
+            per_email: policy(100),
+        },
+        trust_proxy: true,
+        ..Default::default()
+    })
+    .await;
+
+    assert_eq!(post_login_forwarded_for(&app, "203.0.113.1").await, 401);
+    assert_eq!(post_login_forwarded_for(&app, "203.0.113.1").await, 429);
+    // Only the address the proxy appended counts, not those the client sent before it.
+    assert_eq!(
+        post_login_forwarded_for(&app, "198.51.100.7, 203.0.113.1").await,
+        429
+    );
+    assert_eq!(post_login_forwarded_for(&app, "203.0.113.2").await, 401);
+}
+
+#[tokio::test]
+async fn should_ignore_forwarded_addresses_without_a_trusted_proxy() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        login: RouteRateLimits {
+            per_ip: policy(1),
+            per_email: policy(100),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    assert_eq!(post_login_forwarded_for(&app, "203.0.113.1").await, 401);
+    assert_eq!(post_login_forwarded_for(&app, "203.0.113.2").await, 429);
+}
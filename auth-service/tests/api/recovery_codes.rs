@@ -0,0 +1,286 @@
+use std::time::Duration;
+
+use auth_service::{
+    api::{
+        dtos::{
+            MFARequiredResponse, RecoveryCodesResponse, SignUpResponse, TotpEnrollmentResponse,
+        },
+        utils::constants::{JWT_COOKIE_NAME, RECOVERY_CODE_COUNT},
+    },
+    domain::{
+        models::{Email, TOTP_STEP_SECONDS, TotpSecret, TwoFACodePolicy},
+        ports::UserStore,
+    },
+};
+use chrono::Utc;
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+// Signs up a user with 2FA, returning their email and recovery codes.
+async fn two_fa_user(app: &TestApp) -> (String, Vec<String>) {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": true
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let body = response
+        .json::<SignUpResponse>()
+        .await
+        .expect("Could not deserialize response body to SignUpResponse");
+    (email, body.recovery_codes.expect("No recovery codes"))
+}
+
+// Logs in a user with 2FA and returns the login attempt ID.
+async fn login_attempt(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+    response
+        .json::<MFARequiredResponse>()
+        .await
+        .expect("Could not deserialize response body to MFARequiredResponse")
+        .login_attempt_id
+}
+
+async fn verify_with(app: &TestApp, email: &str, login_attempt_id: &str, code: &str) -> u16 {
+    app.post_verify_2fa(&serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code,
+    }))
+    .await
+    .status()
+    .as_u16()
+}
+
+async fn codes_left(app: &TestApp, email: &str) -> usize {
+    app.user_store
+        .read()
+        .await
+        .count_recovery_codes(&Email::parse(email).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn should_hand_out_recovery_codes_on_signup_with_2fa_only() {
+    let app = TestApp::new().await;
+
+    let (email, codes) = two_fa_user(&app).await;
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT);
+
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": get_random_email(),
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    let body = response
+        .json::<SignUpResponse>()
+        .await
+        .expect("Could not deserialize response body to SignUpResponse");
+    assert_eq!(body.recovery_codes, None);
+}
+
+#[tokio::test]
+async fn should_log_in_with_a_recovery_code_once() {
+    let app = TestApp::new().await;
+    let (email, codes) = two_fa_user(&app).await;
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            // Codes may be typed in upper case and without the dash.
+            "2FACode": codes[0].to_uppercase().replace('-', ""),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        response
+            .cookies()
+            .any(|cookie| cookie.name() == JWT_COOKIE_NAME)
+    );
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT - 1);
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        401
+    );
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[1]).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn should_not_use_up_a_recovery_code_without_the_pending_login_attempt() {
+    let app = TestApp::new().await;
+    let (email, codes) = two_fa_user(&app).await;
+
+    // No login is pending.
+    let login_attempt_id = uuid::Uuid::new_v4().to_string();
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        401
+    );
+
+    let _ = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        401
+    );
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT);
+}
+
+#[tokio::test]
+async fn should_not_use_up_a_recovery_code_on_an_expired_login_attempt() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        lifetime: Duration::from_secs(1),
+        ..Default::default()
+    })
+    .await;
+    let (email, codes) = two_fa_user(&app).await;
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        401
+    );
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT);
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn should_count_a_wrong_recovery_code_as_a_failed_attempt() {
+    let app = TestApp::new().await;
+    let (email, codes) = two_fa_user(&app).await;
+    let (_, other_codes) = two_fa_user(&app).await;
+
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &other_codes[0]).await,
+        401
+    );
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn should_regenerate_recovery_codes() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_recovery_codes(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let (email, old_codes) = two_fa_user(&app).await;
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &old_codes[0]).await,
+        200
+    );
+
+    // A session alone cannot take over the codes.
+    let response = app
+        .post_recovery_codes(&serde_json::json!({ "password": "wrong-password" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT - 1);
+
+    let response = app
+        .post_recovery_codes(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let new_codes = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodesResponse")
+        .recovery_codes;
+    assert_eq!(new_codes.len(), RECOVERY_CODE_COUNT);
+    assert_eq!(codes_left(&app, &email).await, RECOVERY_CODE_COUNT);
+
+    // The previous codes no longer work.
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &old_codes[1]).await,
+        401
+    );
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &new_codes[0]).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn should_hand_out_recovery_codes_on_totp_enrollment() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let enrollment = app
+        .post_enroll_totp(&serde_json::json!({ "password": PASSWORD }))
+        .await
+        .json::<TotpEnrollmentResponse>()
+        .await
+        .expect("Could not deserialize response body to TotpEnrollmentResponse");
+    let secret = TotpSecret::parse(enrollment.secret).unwrap();
+    let code = secret.code_at(Utc::now().timestamp() as u64 / TOTP_STEP_SECONDS);
+    let response = app
+        .post_confirm_totp(&serde_json::json!({ "2FACode": code, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let codes = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodesResponse")
+        .recovery_codes;
+    assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+    // A recovery code stands in for the app's code as well.
+    let login_attempt_id = login_attempt(&app, &email).await;
+    assert_eq!(
+        verify_with(&app, &email, &login_attempt_id, &codes[0]).await,
+        200
+    );
+}
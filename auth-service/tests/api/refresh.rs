@@ -0,0 +1,220 @@
+use auth_service::{
+    api::{
+        dtos::ErrorResponse,
+        utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    },
+    domain::{
+        models::Email,
+        ports::{TwoFACodeStore, UserStore},
+    },
+};
+use chrono::Utc;
+
+use super::helpers::*;
+
+// Signs up and logs in a user without 2FA, returning the refresh token set on login.
+async fn login(app: &TestApp) -> String {
+    login_as(app, &get_random_email()).await
+}
+
+async fn login_as(app: &TestApp, random_email: &str) -> String {
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    refresh_cookie(&response)
+}
+
+fn refresh_cookie(response: &reqwest::Response) -> String {
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME)
+        .expect("No refresh cookie found")
+        .value()
+        .to_owned()
+}
+
+async fn assert_invalid_token(response: reqwest::Response) {
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Invalid token"
+    );
+}
+
+#[tokio::test]
+async fn should_return_400_if_refresh_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_refresh().await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Missing token"
+    );
+}
+
+#[tokio::test]
+async fn should_return_401_if_refresh_token_unknown() {
+    let app = TestApp::new().await;
+
+    assert_invalid_token(app.post_refresh_with_token("malformed").await).await;
+    assert_invalid_token(app.post_refresh_with_token(&"a".repeat(43)).await).await;
+}
+
+#[tokio::test]
+async fn should_issue_refresh_cookie_on_login() {
+    let app = TestApp::new().await;
+
+    let refresh_token = login(&app).await;
+
+    assert_eq!(refresh_token.len(), 43);
+}
+
+#[tokio::test]
+async fn should_issue_refresh_cookie_on_verify_2fa() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(
+        response
+            .cookies()
+            .all(|cookie| cookie.name() != REFRESH_TOKEN_COOKIE_NAME)
+    );
+
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(&random_email).unwrap())
+        .await
+        .unwrap();
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": random_email,
+            "loginAttemptId": login_attempt_id.as_ref(),
+            "2FACode": two_fa_code.as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let refresh_token = refresh_cookie(&response);
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_rotate_refresh_token_and_issue_new_jwt() {
+    let app = TestApp::new().await;
+
+    let refresh_token = login(&app).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+    let rotated_token = refresh_cookie(&response);
+    assert_ne!(rotated_token, refresh_token);
+
+    // The rotated token, kept by the cookie jar, can itself be rotated.
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_revoke_family_if_refresh_token_reused() {
+    let app = TestApp::new().await;
+
+    let stolen_token = login(&app).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let rotated_token = refresh_cookie(&response);
+
+    // Replaying the already rotated token is refused...
+    assert_invalid_token(app.post_refresh_with_token(&stolen_token).await).await;
+
+    // ...and takes down the token the legitimate client holds as well.
+    assert_invalid_token(app.post_refresh_with_token(&rotated_token).await).await;
+}
+
+#[tokio::test]
+async fn should_revoke_refresh_token_on_logout() {
+    let app = TestApp::new().await;
+
+    let refresh_token = login(&app).await;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    assert_invalid_token(app.post_refresh_with_token(&refresh_token).await).await;
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_423_if_account_locked_since_login() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let refresh_token = login_as(&app, &random_email).await;
+
+    app.user_store
+        .write()
+        .await
+        .lock_user(
+            &Email::parse(&random_email).unwrap(),
+            Utc::now() + chrono::Duration::seconds(60),
+        )
+        .await
+        .unwrap();
+
+    let response = app.post_refresh_with_token(&refresh_token).await;
+    assert_eq!(response.status().as_u16(), 423);
+    assert!(
+        response
+            .cookies()
+            .all(|cookie| cookie.name() != JWT_COOKIE_NAME)
+    );
+}
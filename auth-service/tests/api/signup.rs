@@ -1,4 +1,4 @@
-use auth_service::api::dtos::{ErrorResponse, SignUpResponse};
+use auth_service::api::dtos::{ErrorCode, ErrorResponse, SignUpResponse};
 
 use crate::helpers::{TestApp, get_random_email};
 
@@ -18,36 +18,31 @@ async fn should_return_201_if_valid_input() {
 
     assert_eq!(response.status().as_u16(), 201);
 
+    let response = response
+        .json::<SignUpResponse>()
+        .await
+        .expect("Could not deserialize response body to UserBody");
+
+    // The recovery codes of a user with 2FA are random.
     let expected_response = SignUpResponse {
         message: "User created successfully!".to_owned(),
+        recovery_codes: response.recovery_codes.clone(),
     };
 
-    assert_eq!(
-        response
-            .json::<SignUpResponse>()
-            .await
-            .expect("Could not deserialize response body to UserBody"),
-        expected_response
-    );
+    assert_eq!(response, expected_response);
+    assert_eq!(response.recovery_codes.map(|codes| codes.len()), Some(10));
 }
 
 #[tokio::test]
 async fn should_return_400_if_invalid_input() {
     let app = TestApp::new().await;
 
-    let random_email = get_random_email();
-
     let input = [
         serde_json::json!({
             "email": "",
             "password": "password123",
             "requires2FA": true
         }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "",
-            "requires2FA": true
-        }),
         serde_json::json!({
             "email": "",
             "password": "",
@@ -58,24 +53,60 @@ async fn should_return_400_if_invalid_input() {
             "password": "password123",
             "requires2FA": true
         }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "invalid",
-            "requires2FA": true
-        }),
     ];
 
     for i in input.iter() {
         let response = app.post_signup(i).await;
         assert_eq!(response.status().as_u16(), 400, "Failed for input: {:?}", i);
 
+        let body = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(body.title, "Invalid input".to_owned());
+        assert_eq!(body.code, ErrorCode::InvalidInput);
+        let errors = body.errors.expect("No invalid fields listed");
+        assert_eq!(errors.len(), 1, "Failed for input: {:?}", i);
+        assert_eq!(errors[0].field, "email");
+        assert_eq!(errors[0].message, "Must be a valid email address");
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_listing_the_password_rules_that_fail() {
+    let app = TestApp::new().await;
+
+    for (password, rules) in [
+        ("", vec!["min_length"]),
+        ("invalid", vec!["min_length"]),
+        ("jane.doe-password", vec!["email_local_part"]),
+    ] {
+        let response = app
+            .post_signup(&serde_json::json!({
+                "email": "jane.doe@example.com",
+                "password": password,
+                "requires2FA": true
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 400);
+
+        let body = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(body.title, "Invalid password");
+        let violations = body.violations.expect("No violations listed");
         assert_eq!(
-            response
-                .json::<ErrorResponse>()
-                .await
-                .expect("Could not deserialize response body to ErrorResponse")
-                .error,
-            "Invalid credentials".to_owned()
+            violations
+                .iter()
+                .map(|violation| violation.rule.as_str())
+                .collect::<Vec<_>>(),
+            rules
+        );
+        assert!(
+            violations
+                .iter()
+                .all(|violation| !violation.message.is_empty())
         );
     }
 }
@@ -105,7 +136,7 @@ async fn should_return_409_if_email_already_exists() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to ErrorResponse")
-            .error,
+            .title,
         "User already exists".to_owned()
     );
 }
@@ -117,27 +148,39 @@ async fn should_return_422_if_malformed_input() {
     let random_email = get_random_email();
 
     let test_cases = [
-        serde_json::json!({
-            "password": "password123",
-            "requires2FA": true
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "requires2FA": true
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "password123",
-        }),
-        serde_json::json!({
-            "email": random_email,
-            "password": "password123",
-            "requires2FA": "true"
-        }),
-        serde_json::json!({}),
+        (
+            serde_json::json!({
+                "password": "password123",
+                "requires2FA": true
+            }),
+            "email",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "requires2FA": true
+            }),
+            "password",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "password": "password123",
+            }),
+            "requires2FA",
+        ),
+        (
+            serde_json::json!({
+                "email": random_email,
+                "password": "password123",
+                "requires2FA": "true"
+            }),
+            "requires2FA",
+        ),
+        (serde_json::json!({}), "email"),
     ];
 
-    for test_case in test_cases.iter() {
+    for (test_case, field) in test_cases.iter() {
         let response = app.post_signup(test_case).await;
         assert_eq!(
             response.status().as_u16(),
@@ -145,5 +188,17 @@ async fn should_return_422_if_malformed_input() {
             "Failed for input: {:?}",
             test_case
         );
+
+        let body = response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse");
+        assert_eq!(body.code, ErrorCode::UnprocessableContent);
+        assert_eq!(
+            body.errors.expect("No invalid fields listed")[0].field,
+            *field,
+            "Failed for input: {:?}",
+            test_case
+        );
     }
 }
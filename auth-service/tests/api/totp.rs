@@ -0,0 +1,267 @@
+use auth_service::{
+    api::{
+        dtos::{MFARequiredResponse, TotpEnrollmentResponse},
+        utils::constants::JWT_COOKIE_NAME,
+    },
+    domain::{
+        models::{Email, TOTP_STEP_SECONDS, TotpSecret, TwoFACodePolicy, TwoFAMethod},
+        ports::UserStore,
+    },
+};
+use chrono::Utc;
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+// The code the authenticator app shows right now.
+fn current_code(secret: &TotpSecret) -> String {
+    secret.code_at(Utc::now().timestamp() as u64 / TOTP_STEP_SECONDS)
+}
+
+// A code no step of the skew window accepts.
+fn wrong_code(secret: &TotpSecret) -> String {
+    let step = Utc::now().timestamp() as u64 / TOTP_STEP_SECONDS;
+    let valid: Vec<String> = (step - 2..=step + 2).map(|s| secret.code_at(s)).collect();
+    (0..)
+        .map(|n| format!("{n:06}"))
+        .find(|code| !valid.contains(code))
+        .unwrap()
+}
+
+// Signs up and logs in a user without 2FA, leaving the auth cookie in the app's jar.
+async fn logged_in_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    email
+}
+
+// Signs up a user and switches them to TOTP, returning their email and secret.
+async fn totp_user(app: &TestApp) -> (String, TotpSecret) {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": true
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let secret = TotpSecret::default();
+    app.user_store
+        .write()
+        .await
+        .update_two_fa_method(
+            &Email::parse(&email).unwrap(),
+            TwoFAMethod::Totp(secret.clone()),
+        )
+        .await
+        .unwrap();
+    (email, secret)
+}
+
+// Logs in a TOTP user and returns the login attempt ID.
+async fn login_attempt(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 206);
+    let body = response
+        .json::<MFARequiredResponse>()
+        .await
+        .expect("Could not deserialize response body to MFARequiredResponse");
+    assert_eq!(body.two_fa_method, "totp");
+    body.login_attempt_id
+}
+
+#[tokio::test]
+async fn should_return_400_if_not_logged_in() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_enroll_totp(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_confirm_totp(&serde_json::json!({ "2FACode": "123456", "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_password_incorrect() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_enroll_totp(&serde_json::json!({ "password": "wrong-password" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let enrollment = app
+        .post_enroll_totp(&serde_json::json!({ "password": PASSWORD }))
+        .await
+        .json::<TotpEnrollmentResponse>()
+        .await
+        .expect("Could not deserialize response body to TotpEnrollmentResponse");
+    let secret = TotpSecret::parse(enrollment.secret).unwrap();
+    let response = app
+        .post_confirm_totp(&serde_json::json!({
+            "2FACode": current_code(&secret),
+            "password": "wrong-password",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // A session alone cannot swap the second factor.
+    let email = Email::parse(&email).unwrap();
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert_eq!(user.two_fa_method, TwoFAMethod::None);
+    assert_eq!(user.failed_login_attempts, 2);
+}
+
+#[tokio::test]
+async fn should_enroll_an_authenticator_app() {
+    let app = TestApp::new().await;
+    let email = logged_in_user(&app).await;
+
+    let response = app
+        .post_enroll_totp(&serde_json::json!({ "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-store");
+    let enrollment = response
+        .json::<TotpEnrollmentResponse>()
+        .await
+        .expect("Could not deserialize response body to TotpEnrollmentResponse");
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(
+        enrollment
+            .otpauth_uri
+            .contains(&format!("secret={}", enrollment.secret))
+    );
+    assert!(enrollment.qr_code_svg.contains("<svg"));
+
+    // Nothing changes until the enrollment is confirmed.
+    let email = Email::parse(&email).unwrap();
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert_eq!(user.two_fa_method, TwoFAMethod::None);
+
+    let secret = TotpSecret::parse(enrollment.secret).unwrap();
+    let response = app
+        .post_confirm_totp(&serde_json::json!({
+            "2FACode": wrong_code(&secret),
+            "password": PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_confirm_totp(&serde_json::json!({
+            "2FACode": current_code(&secret),
+            "password": PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let user = app.user_store.read().await.get_user(&email).await.unwrap();
+    assert_eq!(user.two_fa_method, TwoFAMethod::Totp(secret.clone()));
+    assert_eq!(user.pending_totp_secret, None);
+
+    // The enrollment was used up.
+    let response = app
+        .post_confirm_totp(&serde_json::json!({
+            "2FACode": current_code(&secret),
+            "password": PASSWORD,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_log_in_with_a_totp_code() {
+    let app = TestApp::new().await;
+    let (email, secret) = totp_user(&app).await;
+    let login_attempt_id = login_attempt(&app, &email).await;
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": wrong_code(&secret),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let code = current_code(&secret);
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        response
+            .cookies()
+            .any(|cookie| cookie.name() == JWT_COOKIE_NAME)
+    );
+
+    // A code is accepted once, even for a new login attempt.
+    let login_attempt_id = login_attempt(&app, &email).await;
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_429_after_too_many_wrong_totp_codes() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        max_attempts: 2,
+        ..Default::default()
+    })
+    .await;
+    let (email, secret) = totp_user(&app).await;
+    let login_attempt_id = login_attempt(&app, &email).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": wrong_code(&secret),
+    });
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    // The login attempt is gone, so even the right code needs a new login.
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": current_code(&secret),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
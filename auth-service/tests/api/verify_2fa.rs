@@ -1,13 +1,11 @@
+use std::time::Duration;
+
 use auth_service::{
     domain::{
-        models::{Email, LoginAttemptId, TwoFACode},
+        models::{Email, LockoutPolicy, TwoFACodePolicy},
         ports::TwoFACodeStore,
     },
-    api::{
-        dtos::{MFARequiredResponse, ErrorResponse},
-        utils::constants::JWT_COOKIE_NAME, // New!
-        
-    },
+    api::{dtos::ErrorResponse, utils::constants::JWT_COOKIE_NAME},
 };
 
 use super::helpers::*;
@@ -115,6 +113,7 @@ async fn should_return_401_if_old_code() {
     });
     let response = app.post_signup(&signup_body).await;
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -161,6 +160,7 @@ async fn should_return_200_if_correct_code() {
     });
     let response = app.post_signup(&signup_body).await;
     assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -193,4 +193,302 @@ async fn should_return_200_if_correct_code() {
         .expect("No auth cookie found");
 
     assert!(!auth_cookie.value().is_empty());
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn should_return_401_if_same_code_twice() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(&random_email).unwrap())
+        .await
+        .unwrap();
+
+    let verify_body = serde_json::json!({
+        "email": random_email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+    });
+    let response = app.post_verify_2fa(&verify_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_verify_2fa(&verify_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_expired_code() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        lifetime: Duration::from_secs(1),
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(&random_email).unwrap())
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": random_email,
+            "loginAttemptId": login_attempt_id.as_ref(),
+            "2FACode": two_fa_code.as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "2FA code expired".to_owned()
+    );
+}
+
+#[tokio::test]
+async fn should_return_429_after_too_many_incorrect_codes() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        max_attempts: 3,
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(&random_email).unwrap())
+        .await
+        .unwrap();
+    let wrong_code = if two_fa_code.as_ref() == "000000" {
+        "111111"
+    } else {
+        "000000"
+    };
+
+    let wrong_body = serde_json::json!({
+        "email": random_email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": wrong_code,
+    });
+    for _ in 0..2 {
+        let response = app.post_verify_2fa(&wrong_body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+    let response = app.post_verify_2fa(&wrong_body).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Too many failed 2FA attempts, please log in again".to_owned()
+    );
+
+    // The code is gone, even the correct one is rejected now.
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": random_email,
+            "loginAttemptId": login_attempt_id.as_ref(),
+            "2FACode": two_fa_code.as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_after_new_login_following_lockout() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        max_attempts: 1,
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+    let email = Email::parse(&random_email).unwrap();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let (login_attempt_id, two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .unwrap();
+    let wrong_code = if two_fa_code.as_ref() == "000000" {
+        "111111"
+    } else {
+        "000000"
+    };
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": random_email,
+            "loginAttemptId": login_attempt_id.as_ref(),
+            "2FACode": wrong_code,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let (new_login_attempt_id, new_two_fa_code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .unwrap();
+    assert_ne!(new_login_attempt_id, login_attempt_id);
+
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": random_email,
+            "loginAttemptId": new_login_attempt_id.as_ref(),
+            "2FACode": new_two_fa_code.as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_return_423_after_too_many_incorrect_codes_across_logins() {
+    let app = TestApp::with_two_fa_code_policy(TwoFACodePolicy {
+        max_attempts: 1,
+        ..Default::default()
+    })
+    .await;
+
+    let random_email = get_random_email();
+    let email = Email::parse(&random_email).unwrap();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    // A new login does not hand out fresh guesses: every wrong code counts toward the lockout.
+    let max_failed_attempts = LockoutPolicy::default().max_failed_attempts;
+    for attempt in 1..=max_failed_attempts {
+        let response = app.post_login(&login_body).await;
+        assert_eq!(response.status().as_u16(), 206);
+
+        let (login_attempt_id, two_fa_code) = app
+            .two_fa_code_store
+            .read()
+            .await
+            .get_code(&email)
+            .await
+            .unwrap();
+        let wrong_code = if two_fa_code.as_ref() == "000000" {
+            "111111"
+        } else {
+            "000000"
+        };
+        let response = app
+            .post_verify_2fa(&serde_json::json!({
+                "email": random_email,
+                "loginAttemptId": login_attempt_id.as_ref(),
+                "2FACode": wrong_code,
+            }))
+            .await;
+        let expected = if attempt < max_failed_attempts {
+            429
+        } else {
+            423
+        };
+        assert_eq!(response.status().as_u16(), expected);
+    }
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 423);
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use auth_service::{
+    api::{
+        dtos::ErrorResponse,
+        middleware::{RateLimitConfig, RouteRateLimits},
+        utils::constants::JWT_COOKIE_NAME,
+    },
+    domain::{
+        models::{Email, RateLimitPolicy},
+        ports::UserStore,
+    },
+};
+
+use super::helpers::*;
+
+const PASSWORD: &str = "password123";
+
+// Signs up a user, who is left to verify their email.
+async fn unverified_user(app: &TestApp) -> String {
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "requires2FA": false
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    email
+}
+
+async fn is_verified(app: &TestApp, email: &str) -> bool {
+    app.user_store
+        .read()
+        .await
+        .get_user(&Email::parse(email).unwrap())
+        .await
+        .unwrap()
+        .email_verified
+}
+
+#[tokio::test]
+async fn should_refuse_login_until_email_is_verified() {
+    let app = TestApp::new().await;
+    let email = unverified_user(&app).await;
+    let login_body = serde_json::json!({ "email": email, "password": PASSWORD });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .title,
+        "Email not verified"
+    );
+
+    app.verify_email(&email).await;
+    assert!(is_verified(&app, &email).await);
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Following the link again changes nothing.
+    app.verify_email(&email).await;
+}
+
+#[tokio::test]
+async fn should_not_tell_unverified_accounts_apart_without_the_password() {
+    let app = TestApp::new().await;
+    let email = unverified_user(&app).await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "wrongpassword" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+    let email = unverified_user(&app).await;
+
+    let response = app.get_verify_email("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // An auth token does not verify the address it was issued to.
+    app.verify_email(&email).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    let auth_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    let other_email = unverified_user(&app).await;
+    let response = app.get_verify_email(&auth_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(!is_verified(&app, &other_email).await);
+}
+
+#[tokio::test]
+async fn should_return_400_if_token_is_missing() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .http_client
+        .get(format!("{}/verify-email", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_accept_resend_requests_alike_for_every_address() {
+    let app = TestApp::new().await;
+    let unverified_email = unverified_user(&app).await;
+    let verified_email = unverified_user(&app).await;
+    app.verify_email(&verified_email).await;
+
+    for email in [unverified_email, verified_email, get_random_email()] {
+        let response = app
+            .post_resend_verification_email(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 202);
+    }
+
+    let response = app
+        .post_resend_verification_email(&serde_json::json!({ "email": "invalid_email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_429_when_email_exceeds_resend_quota() {
+    let app = TestApp::with_rate_limit_config(RateLimitConfig {
+        resend_verification_email: RouteRateLimits {
+            per_ip: RateLimitPolicy {
+                capacity: 100,
+                period: Duration::from_secs(60),
+            },
+            per_email: RateLimitPolicy {
+                capacity: 1,
+                period: Duration::from_secs(3600),
+            },
+        },
+        ..Default::default()
+    })
+    .await;
+    let email = unverified_user(&app).await;
+    let body = serde_json::json!({ "email": email });
+
+    let response = app.post_resend_verification_email(&body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let response = app.post_resend_verification_email(&body).await;
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().get("retry-after").is_some());
+}
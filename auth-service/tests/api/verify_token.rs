@@ -16,6 +16,7 @@ async fn should_return_200_valid_token() {
     });
 
     let _ = app.post_signup(&signup_body).await;
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -54,6 +55,7 @@ async fn should_return_401_if_banned_token() {
     });
 
     let _ = app.post_signup(&signup_body).await;
+    app.verify_email(&random_email).await;
 
     let login_body = serde_json::json!({
         "email": random_email,
@@ -84,7 +86,7 @@ async fn should_return_401_if_banned_token() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to ErrorResponse")
-            .error,
+            .title,
         "Invalid token"
     );
 }
@@ -106,7 +108,7 @@ async fn should_return_401_if_invalid_token() {
             .json::<ErrorResponse>()
             .await
             .expect("Could not deserialize response body to ErrorResponse")
-            .error,
+            .title,
         "Invalid token"
     );
 }
@@ -144,3 +146,51 @@ async fn should_return_422_if_malformed_input() {
         );
     }
 }
+
+#[tokio::test]
+async fn should_only_ban_the_logged_out_token() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let password = "password123";
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": password,
+        "requires2FA": false
+    });
+
+    let _ = app.post_signup(&signup_body).await;
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": password,
+    });
+
+    let mut tokens = Vec::new();
+    for _ in 0..2 {
+        let response = app.post_login(&login_body).await;
+        let token = response
+            .cookies()
+            .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+            .unwrap()
+            .value()
+            .to_string();
+        tokens.push(token);
+    }
+
+    // Logs out the second session, whose token the cookie jar now holds.
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": tokens[0] }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": tokens[1] }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}